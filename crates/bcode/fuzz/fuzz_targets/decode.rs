@@ -0,0 +1,23 @@
+#![no_main]
+
+use bcode::{DecodeOptions, Decoder, Mode};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for mode in [Mode::Strict, Mode::Lenient] {
+        let options = DecodeOptions {
+            mode,
+            max_depth: 64,
+            max_size: 1 << 20,
+            max_string_length: 1 << 16,
+            max_entries: 1 << 12,
+        };
+
+        let mut decoder = Decoder::with_options(data, options);
+
+        if let Ok(value) = decoder.decode_spanned() {
+            let _ = decoder.finish();
+            let _ = bcode::encode(bcode::Value::from(value));
+        }
+    }
+});
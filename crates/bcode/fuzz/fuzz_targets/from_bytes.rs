@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(value) = bcode::from_bytes::<bcode::Value>(data) {
+        let _ = bcode::to_bytes(&value);
+    }
+});
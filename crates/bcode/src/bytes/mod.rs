@@ -0,0 +1,24 @@
+//! Read and write `Vec<u8>` as a byte string, used by `#[bcode(bytes)]`.
+
+use crate::*;
+
+/// Convert bytes to a byte string.
+///
+/// # Arguments
+///
+/// * `bytes` - bytes to convert.
+pub fn bencode(bytes: &[u8]) -> Value {
+    Value::ByteString(bytes.to_vec())
+}
+
+/// Convert a byte string to bytes.
+///
+/// # Arguments
+///
+/// * `value` - value to convert.
+pub fn bdecode(value: Value) -> Result<Vec<u8>, Error> {
+    match value {
+        Value::ByteString(out) => Ok(out),
+        _ => Err(Error::Custom("Value is not a byte string".to_string())),
+    }
+}
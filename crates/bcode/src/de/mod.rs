@@ -0,0 +1,346 @@
+use crate::*;
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Deserialize bcode to a value.
+///
+/// # Arguments
+///
+/// * `data` - bytes to deserialize, must contain exactly one bcode value.
+pub fn from_bytes<T: DeserializeOwned>(data: &[u8]) -> Result<T, Error> {
+    let mut index = 0;
+    let value = decode(data, &mut index)?;
+
+    if index != data.len() {
+        return Err(Error::TrailingData(Position::new(index)));
+    }
+
+    from_value(value)
+}
+
+/// Deserialize a `bcode::Value` to a value.
+///
+/// # Arguments
+///
+/// * `value` - `bcode::Value` to deserialize.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+    T::deserialize(Deserializer::new(value))
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+/// Visitor building a `bcode::Value` from any self-describing format.
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a bcode value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Integer(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        i64::try_from(v)
+            .map(Value::Integer)
+            .map_err(|_| E::custom(format!("{v} is too large")))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::ByteString(v.as_bytes().to_vec()))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::ByteString(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::ByteString(v))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut list = vec![];
+
+        while let Some(value) = seq.next_element()? {
+            list.push(value);
+        }
+
+        Ok(Value::List(list))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut dictionary = BTreeMap::new();
+
+        while let Some((key, value)) = map.next_entry::<Value, Value>()? {
+            match key {
+                Value::ByteString(key) => dictionary.insert(key, value),
+                _ => return Err(de::Error::custom("Dictionary key must be a byte string")),
+            };
+        }
+
+        Ok(Value::Dictionary(dictionary))
+    }
+}
+
+/// Deserializer reading from a `bcode::Value`.
+///
+/// Byte strings are handed to visitors as bytes, or as UTF-8 strings when a string is asked for.
+/// Booleans are read from the integers `0` and `1`.
+pub struct Deserializer {
+    value: Value,
+}
+
+impl Deserializer {
+    /// Create a new deserializer.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - `bcode::Value` to deserialize from.
+    pub fn new(value: Value) -> Deserializer {
+        Deserializer { value }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Integer(inner) => visitor.visit_i64(inner),
+            Value::ByteString(inner) => visitor.visit_byte_buf(inner),
+            Value::List(inner) => visitor.visit_seq(ListAccess {
+                iter: inner.into_iter(),
+            }),
+            Value::Dictionary(inner) => visitor.visit_map(DictionaryAccess {
+                iter: inner.into_iter(),
+                current_value: None,
+            }),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Integer(0) => visitor.visit_bool(false),
+            Value::Integer(1) => visitor.visit_bool(true),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::ByteString(inner) => match String::from_utf8(inner) {
+                Ok(string) => visitor.visit_string(string),
+                Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            Value::ByteString(variant) => visitor.visit_enum(EnumAccess {
+                variant,
+                value: None,
+            }),
+            Value::Dictionary(inner) if inner.len() == 1 => {
+                let (variant, value) = inner.into_iter().next().unwrap();
+
+                visitor.visit_enum(EnumAccess {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            _ => Err(Error::Custom(
+                "Enums must be a byte string or a dictionary with a single key".to_string(),
+            )),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 bytes byte_buf
+        seq tuple tuple_struct map struct
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Deserializer;
+
+    fn into_deserializer(self) -> Deserializer {
+        Deserializer::new(self)
+    }
+}
+
+/// Access to the elements of a list.
+struct ListAccess {
+    iter: std::vec::IntoIter<Value>,
+}
+
+impl<'de> de::SeqAccess<'de> for ListAccess {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.iter
+            .next()
+            .map(|value| seed.deserialize(Deserializer::new(value)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Access to the entries of a dictionary.
+struct DictionaryAccess {
+    iter: std::collections::btree_map::IntoIter<Vec<u8>, Value>,
+    current_value: Option<Value>,
+}
+
+impl<'de> de::MapAccess<'de> for DictionaryAccess {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.current_value = Some(value);
+                seed.deserialize(Deserializer::new(Value::ByteString(key)))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let value = self
+            .current_value
+            .take()
+            .ok_or_else(|| Error::Custom("Dictionary value without a key".to_string()))?;
+
+        seed.deserialize(Deserializer::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Access to an enum variant, and the data it carries (if any).
+struct EnumAccess {
+    variant: Vec<u8>,
+    value: Option<Value>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = Error;
+    type Variant = VariantAccess;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantAccess), Error> {
+        let variant = seed.deserialize(Deserializer::new(Value::ByteString(self.variant)))?;
+
+        Ok((variant, VariantAccess { value: self.value }))
+    }
+}
+
+/// Access to the data of an enum variant.
+struct VariantAccess {
+    value: Option<Value>,
+}
+
+impl VariantAccess {
+    fn into_deserializer(self) -> Result<Deserializer, Error> {
+        self.value
+            .map(Deserializer::new)
+            .ok_or_else(|| Error::Custom("Missing enum variant data".to_string()))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self.into_deserializer()?)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self.into_deserializer()?, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self.into_deserializer()?, visitor)
+    }
+}
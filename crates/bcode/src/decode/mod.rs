@@ -1,208 +1,454 @@
+mod node;
+
 use crate::*;
-use anyhow::{anyhow, Result};
+use node::Node;
 use std::collections::BTreeMap;
 
 /// Decode data as bcode to a `bcode::Value`.
 ///
+/// Non-canonical bcode is rejected, see `bcode::Decoder` to accept or inspect it.
+/// Data after the value is left for the next call.
+///
 /// # Arguments
 ///
 /// * `data` - bytes to decode.
 /// * `index` - index of where to start decoding, usually `0`.
-pub fn decode(data: &[u8], index: &mut usize) -> Result<Value> {
-    match get(data, *index)? {
-        // Integer
-        b'i' => decode_integer(data, index),
-        // Byte string
-        48..=57 => decode_byte_string(data, index),
-        // List
-        b'l' => decode_list(data, index),
-        // Dictionary
-        b'd' => decode_dictionary(data, index),
-        // Other
-        _ => Err(anyhow!("Unexpected byte")),
-    }
+pub fn decode(data: &[u8], index: &mut usize) -> Result<Value, Error> {
+    decode_ref(data, index).map(Value::from)
 }
 
-/// Helper function to get byte at index
+/// Decode data as bcode to a `bcode::ValueRef`, borrowing byte strings from `data`.
 ///
 /// # Arguments
 ///
-/// * `data` - reference to data.
-/// * `at` - index to get byte from.
-fn get(data: &[u8], at: usize) -> Result<u8> {
-    if at > data.len() - 1 {
-        Err(anyhow!(
-            "Index out of range (index: {}, data length: {})",
-            at,
-            data.len()
-        ))
-    } else {
-        Ok(*data.get(at).unwrap())
-    }
+/// * `data` - bytes to decode.
+/// * `index` - index of where to start decoding, usually `0`.
+pub fn decode_ref<'a>(data: &'a [u8], index: &mut usize) -> Result<ValueRef<'a>, Error> {
+    let mut decoder = Decoder::new(data, Mode::Strict);
+    decoder.index = *index;
+
+    let value = decoder.decode_ref()?;
+    *index = decoder.index;
+
+    Ok(value)
 }
 
-/// Decode a bencoded integer.
+/// Decode data as bcode to a `bcode::Spanned`, keeping the byte range of every value.
 ///
 /// # Arguments
 ///
 /// * `data` - bytes to decode.
-/// * `index` - index of where to start decoding.
-fn decode_integer(data: &[u8], index: &mut usize) -> Result<Value> {
-    if get(data, *index)? as char == 'i' {
-        *index += 1;
-    } else {
-        return Err(anyhow!("Integers must start with 'i'"));
-    }
-
-    let mut number_buf: Vec<char> = vec![];
-
-    loop {
-        match get(data, *index)? {
-            // Minus sign
-            b'-' => {
-                if !number_buf.is_empty() {
-                    return Err(anyhow!("Unexpected minus sign"));
-                }
+/// * `index` - index of where to start decoding, usually `0`.
+pub fn decode_spanned<'a>(data: &'a [u8], index: &mut usize) -> Result<Spanned<'a>, Error> {
+    let mut decoder = Decoder::new(data, Mode::Strict);
+    decoder.index = *index;
 
-                number_buf.push(get(data, *index)? as char);
-            }
-            // Digits
-            48..=57 => {
-                number_buf.push(get(data, *index)? as char);
+    let value = decoder.decode_spanned()?;
+    *index = decoder.index;
 
-                if number_buf.first() == Some(&'-') && get(data, *index)? as char == '0' {
-                    return Err(anyhow!("\"-0\" is not allowed"));
-                }
+    Ok(value)
+}
 
-                if number_buf.first() == Some(&'0') && number_buf.len() > 1 {
-                    return Err(anyhow!("Leading zeros are not allowed"));
-                }
-            }
-            // End character 'e'
-            b'e' => {
-                *index += 1;
+/// How strictly the decoder follows canonical bcode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Reject unsorted or duplicate keys, non-canonical integers and trailing data.
+    #[default]
+    Strict,
+    /// Accept non-canonical bcode, and report what was wrong with it.
+    Lenient,
+}
 
-                return Ok(Value::Integer(
-                    number_buf.iter().collect::<String>().parse()?,
-                ));
-            }
-            // Other
-            _ => return Err(anyhow!("Unexpected byte while decoding integer")),
-        }
+/// Options for decoding, with limits to protect against hostile data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// How strictly to follow canonical bcode.
+    pub mode: Mode,
+    /// Maximum amount of nested lists and dictionaries.
+    pub max_depth: usize,
+    /// Maximum length of the data in bytes.
+    pub max_size: usize,
+    /// Maximum length of a byte string.
+    pub max_string_length: usize,
+    /// Maximum amount of elements in a list, or entries in a dictionary.
+    pub max_entries: usize,
+}
 
-        // Increase index
-        *index += 1;
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            mode: Mode::default(),
+            max_depth: 128,
+            max_size: usize::MAX,
+            max_string_length: usize::MAX,
+            max_entries: usize::MAX,
+        }
     }
 }
 
-/// Decode a bencoded byte string.
-///
-/// # Arguments
+/// Decoder for bcode data.
 ///
-/// * `data` - bytes to decode.
-/// * `index` - index of where to start decoding.
-fn decode_byte_string(data: &[u8], index: &mut usize) -> Result<Value> {
-    let mut length_buf: Vec<char> = vec![];
-
-    loop {
-        match get(data, *index)? {
-            // Digits
-            48..=57 => {
-                length_buf.push(get(data, *index)? as char);
-
-                if length_buf.first() == Some(&'0') && length_buf.len() > 1 {
-                    return Err(anyhow!("Leading zeros are not allowed"));
-                }
-            }
-            // Seperator ':'
-            b':' => {
-                let length = length_buf.iter().collect::<String>().parse::<usize>()?;
-                let mut byte_string: Vec<u8> = vec![];
-
-                for _ in 0..length {
-                    *index += 1;
-                    byte_string.push(get(data, *index)?);
-                }
+/// Issues found in `Mode::Lenient` are collected, and can be read with `issues` or `finish`.
+#[derive(Debug)]
+pub struct Decoder<'a> {
+    data: &'a [u8],
+    index: usize,
+    options: DecodeOptions,
+    path: Vec<Segment<'a>>,
+    issues: Vec<Error>,
+}
+
+/// Borrowed version of `bcode::PathSegment`, used while decoding.
+#[derive(Debug, Clone, Copy)]
+enum Segment<'a> {
+    Key(&'a [u8]),
+    Index(usize),
+}
 
-                *index += 1;
+impl<'a> Decoder<'a> {
+    /// Create a new decoder with the default limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - bytes to decode.
+    /// * `mode` - how strictly to follow canonical bcode.
+    pub fn new(data: &'a [u8], mode: Mode) -> Decoder<'a> {
+        Decoder::with_options(
+            data,
+            DecodeOptions {
+                mode,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Create a new decoder.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - bytes to decode.
+    /// * `options` - mode and limits to decode with.
+    pub fn with_options(data: &'a [u8], options: DecodeOptions) -> Decoder<'a> {
+        Decoder {
+            data,
+            index: 0,
+            options,
+            path: vec![],
+            issues: vec![],
+        }
+    }
+
+    /// Returns the index of the next byte to decode.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the issues found so far (always empty in `Mode::Strict`).
+    pub fn issues(&self) -> &[Error] {
+        &self.issues
+    }
+
+    /// Decode the next value as a `bcode::Value`.
+    pub fn decode(&mut self) -> Result<Value, Error> {
+        self.decode_ref().map(Value::from)
+    }
+
+    /// Decode the next value as a `bcode::ValueRef`.
+    pub fn decode_ref(&mut self) -> Result<ValueRef<'a>, Error> {
+        self.decode_root()
+    }
+
+    /// Decode the next value as a `bcode::Spanned`.
+    pub fn decode_spanned(&mut self) -> Result<Spanned<'a>, Error> {
+        self.decode_root()
+    }
+
+    /// Check that all data has been decoded, and return the issues found.
+    pub fn finish(mut self) -> Result<Vec<Error>, Error> {
+        if self.index != self.data.len() {
+            self.report(Error::TrailingData(Position::new(self.index)))?;
+        }
+
+        Ok(self.issues)
+    }
+
+    /// Returns the position at the given offset, with the current path.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - byte offset into the data.
+    fn position(&self, offset: usize) -> Position {
+        Position {
+            offset,
+            path: self
+                .path
+                .iter()
+                .map(|segment| match segment {
+                    Segment::Key(key) => PathSegment::Key(key.to_vec()),
+                    Segment::Index(index) => PathSegment::Index(*index),
+                })
+                .collect(),
+        }
+    }
 
-                return Ok(Value::ByteString(byte_string));
+    /// Reject non-canonical bcode in `Mode::Strict`, otherwise take note of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue` - what was wrong.
+    fn report(&mut self, issue: Error) -> Result<(), Error> {
+        match self.options.mode {
+            Mode::Strict => Err(issue),
+            Mode::Lenient => {
+                self.issues.push(issue);
+                Ok(())
             }
-            // Other
-            _ => return Err(anyhow!("Unexpected byte while decoding byte string")),
         }
+    }
 
-        // Increase index
-        *index += 1;
+    /// Helper function to get byte at index.
+    ///
+    /// # Arguments
+    ///
+    /// * `at` - index to get byte from.
+    fn get(&self, at: usize) -> Result<u8, Error> {
+        self.data
+            .get(at)
+            .copied()
+            .ok_or_else(|| Error::UnexpectedEof(self.position(self.data.len())))
     }
-}
 
-/// Decode a bencoded list.
-///
-/// # Arguments
-///
-/// * `data` - bytes to decode.
-/// * `index` - index of where to start decoding.
-fn decode_list(data: &[u8], index: &mut usize) -> Result<Value> {
-    if get(data, *index)? as char == 'l' {
-        *index += 1;
-    } else {
-        return Err(anyhow!("Lists must start with 'l'"));
+    /// Check that entering another list or dictionary stays within `max_depth`.
+    fn check_depth(&self) -> Result<(), Error> {
+        if self.path.len() >= self.options.max_depth {
+            Err(Error::DepthExceeded(self.position(self.index)))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check that a list or dictionary with `entries` elements stays within `max_entries`.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - amount of elements so far.
+    /// * `start` - index of the list or dictionary.
+    fn check_entries(&self, entries: usize, start: usize) -> Result<(), Error> {
+        if entries > self.options.max_entries {
+            Err(Error::TooManyEntries(self.position(start)))
+        } else {
+            Ok(())
+        }
     }
 
-    let mut list = vec![];
+    /// Decode the next value, after checking that the data stays within `max_size`.
+    fn decode_root<N: Node<'a>>(&mut self) -> Result<N, Error> {
+        if self.data.len() > self.options.max_size {
+            return Err(Error::SizeExceeded(self.position(self.options.max_size)));
+        }
+
+        self.decode_node()
+    }
 
-    loop {
-        match get(data, *index)? {
-            // End character 'e'
-            b'e' => {
-                *index += 1;
+    /// Decode any bencoded value.
+    fn decode_node<N: Node<'a>>(&mut self) -> Result<N, Error> {
+        let start = self.index;
 
-                return Ok(Value::List(list));
+        match self.get(self.index)? {
+            // Integer
+            b'i' => {
+                let integer = self.decode_integer()?;
+                Ok(N::integer(integer, start..self.index))
+            }
+            // Byte string
+            b'0'..=b'9' => {
+                let byte_string = self.decode_byte_string()?;
+                Ok(N::byte_string(byte_string, start..self.index))
+            }
+            // List
+            b'l' => {
+                let list = self.decode_list()?;
+                Ok(N::list(list, start..self.index))
+            }
+            // Dictionary
+            b'd' => {
+                let dictionary = self.decode_dictionary()?;
+                Ok(N::dictionary(dictionary, start..self.index))
             }
             // Other
-            _ => list.push(decode(data, index)?),
+            byte => Err(Error::UnexpectedByte(byte, self.position(start))),
         }
     }
-}
 
-/// Decode a bencoded dictionary.
-///
-/// # Arguments
-///
-/// * `data` - bytes to decode.
-/// * `index` - index of where to start decoding.
-fn decode_dictionary(data: &[u8], index: &mut usize) -> Result<Value> {
-    if get(data, *index)? as char == 'd' {
-        *index += 1;
-    } else {
-        return Err(anyhow!("Dictionaries must start with 'd'"));
+    /// Decode digits up to (but not including) the terminator, and return the number.
+    ///
+    /// # Arguments
+    ///
+    /// * `terminator` - byte ending the number.
+    /// * `signed` - whether a minus sign is allowed.
+    fn decode_number(&mut self, terminator: u8, signed: bool) -> Result<i64, Error> {
+        let start = self.index;
+        let negative = signed && self.get(self.index)? == b'-';
+
+        if negative {
+            self.index += 1;
+        }
+
+        let digits_start = self.index;
+        let mut number: i64 = 0;
+
+        loop {
+            match self.get(self.index)? {
+                // Digits
+                byte @ b'0'..=b'9' => {
+                    let digit = (byte - b'0') as i64;
+                    number = number
+                        .checked_mul(10)
+                        .and_then(|x| {
+                            if negative {
+                                x.checked_sub(digit)
+                            } else {
+                                x.checked_add(digit)
+                            }
+                        })
+                        .ok_or_else(|| Error::InvalidInteger(self.position(start)))?;
+                }
+                // End character
+                byte if byte == terminator => {
+                    let digits = self.index - digits_start;
+
+                    if digits == 0 {
+                        return Err(Error::InvalidInteger(self.position(start)));
+                    }
+
+                    if digits > 1 && self.get(digits_start)? == b'0' {
+                        self.report(Error::LeadingZero(self.position(digits_start)))?;
+                    }
+
+                    if negative && number == 0 {
+                        self.report(Error::InvalidInteger(self.position(start)))?;
+                    }
+
+                    return Ok(number);
+                }
+                // Other
+                byte => return Err(Error::UnexpectedByte(byte, self.position(self.index))),
+            }
+
+            // Increase index
+            self.index += 1;
+        }
+    }
+
+    /// Decode a bencoded integer.
+    fn decode_integer(&mut self) -> Result<i64, Error> {
+        match self.get(self.index)? {
+            b'i' => self.index += 1,
+            byte => return Err(Error::UnexpectedByte(byte, self.position(self.index))),
+        }
+
+        let integer = self.decode_number(b'e', true)?;
+        self.index += 1;
+
+        Ok(integer)
     }
 
-    let mut dictionary: BTreeMap<Vec<u8>, Value> = BTreeMap::new();
-    let mut current_key: Option<Vec<u8>> = None;
+    /// Decode a bencoded byte string.
+    fn decode_byte_string(&mut self) -> Result<&'a [u8], Error> {
+        let length_start = self.index;
+        let length = self.decode_number(b':', false)?;
+        let length = usize::try_from(length)
+            .map_err(|_| Error::InvalidInteger(self.position(length_start)))?;
 
-    loop {
-        match get(data, *index)? {
-            // End character 'e'
-            b'e' => {
-                *index += 1;
+        if length > self.options.max_string_length {
+            return Err(Error::StringTooLong(self.position(length_start)));
+        }
+
+        let start = self.index + 1;
+        let byte_string = start
+            .checked_add(length)
+            .and_then(|end| self.data.get(start..end))
+            .ok_or_else(|| Error::UnexpectedEof(self.position(self.data.len())))?;
 
-                return Ok(Value::Dictionary(dictionary));
+        self.index = start + length;
+
+        Ok(byte_string)
+    }
+
+    /// Decode a bencoded list.
+    fn decode_list<N: Node<'a>>(&mut self) -> Result<Vec<N>, Error> {
+        let start = self.index;
+        self.check_depth()?;
+
+        match self.get(self.index)? {
+            b'l' => self.index += 1,
+            byte => return Err(Error::UnexpectedByte(byte, self.position(self.index))),
+        }
+
+        let mut list = vec![];
+
+        loop {
+            match self.get(self.index)? {
+                // End character 'e'
+                b'e' => {
+                    self.index += 1;
+
+                    return Ok(list);
+                }
+                // Other
+                _ => {
+                    self.check_entries(list.len() + 1, start)?;
+                    self.path.push(Segment::Index(list.len()));
+                    list.push(self.decode_node()?);
+                    self.path.pop();
+                }
             }
-            // Other
-            _ => {
-                if current_key.is_none() {
-                    if let Ok(Value::ByteString(new_key)) = decode_byte_string(data, index) {
-                        current_key = Some(new_key);
-                    } else {
-                        return Err(anyhow!("Dictionary key must be a byte string"));
+        }
+    }
+
+    /// Decode a bencoded dictionary.
+    fn decode_dictionary<N: Node<'a>>(&mut self) -> Result<BTreeMap<&'a [u8], N>, Error> {
+        let start = self.index;
+        self.check_depth()?;
+
+        match self.get(self.index)? {
+            b'd' => self.index += 1,
+            byte => return Err(Error::UnexpectedByte(byte, self.position(self.index))),
+        }
+
+        let mut dictionary: BTreeMap<&'a [u8], N> = BTreeMap::new();
+        let mut previous_key: Option<&'a [u8]> = None;
+
+        loop {
+            match self.get(self.index)? {
+                // End character 'e'
+                b'e' => {
+                    self.index += 1;
+
+                    return Ok(dictionary);
+                }
+                // Key
+                b'0'..=b'9' => {
+                    self.check_entries(dictionary.len() + 1, start)?;
+                    let key_start = self.index;
+                    let key = self.decode_byte_string()?;
+
+                    self.path.push(Segment::Key(key));
+
+                    if dictionary.contains_key(key) {
+                        self.report(Error::DuplicateKey(self.position(key_start)))?;
+                    } else if previous_key.is_some_and(|previous_key| key < previous_key) {
+                        self.report(Error::UnsortedKeys(self.position(key_start)))?;
                     }
-                } else {
-                    let new_value = decode(data, index)?;
-                    dictionary.insert(current_key.unwrap(), new_value);
-                    current_key = None;
+
+                    let value = self.decode_node()?;
+                    self.path.pop();
+
+                    dictionary.insert(key, value);
+                    previous_key = Some(key);
                 }
+                // Other
+                _ => return Err(Error::InvalidKey(self.position(self.index))),
             }
         }
     }
@@ -0,0 +1,59 @@
+use crate::*;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// A decoded value the decoder can build, given the byte range it occupied.
+pub(crate) trait Node<'a>: Sized {
+    fn integer(integer: i64, span: Range<usize>) -> Self;
+    fn byte_string(byte_string: &'a [u8], span: Range<usize>) -> Self;
+    fn list(list: Vec<Self>, span: Range<usize>) -> Self;
+    fn dictionary(dictionary: BTreeMap<&'a [u8], Self>, span: Range<usize>) -> Self;
+}
+
+impl<'a> Node<'a> for ValueRef<'a> {
+    fn integer(integer: i64, _: Range<usize>) -> Self {
+        ValueRef::Integer(integer)
+    }
+
+    fn byte_string(byte_string: &'a [u8], _: Range<usize>) -> Self {
+        ValueRef::ByteString(byte_string)
+    }
+
+    fn list(list: Vec<Self>, _: Range<usize>) -> Self {
+        ValueRef::List(list)
+    }
+
+    fn dictionary(dictionary: BTreeMap<&'a [u8], Self>, _: Range<usize>) -> Self {
+        ValueRef::Dictionary(dictionary)
+    }
+}
+
+impl<'a> Node<'a> for Spanned<'a> {
+    fn integer(integer: i64, span: Range<usize>) -> Self {
+        Spanned {
+            value: SpannedValue::Integer(integer),
+            span,
+        }
+    }
+
+    fn byte_string(byte_string: &'a [u8], span: Range<usize>) -> Self {
+        Spanned {
+            value: SpannedValue::ByteString(byte_string),
+            span,
+        }
+    }
+
+    fn list(list: Vec<Self>, span: Range<usize>) -> Self {
+        Spanned {
+            value: SpannedValue::List(list),
+            span,
+        }
+    }
+
+    fn dictionary(dictionary: BTreeMap<&'a [u8], Self>, span: Range<usize>) -> Self {
+        Spanned {
+            value: SpannedValue::Dictionary(dictionary),
+            span,
+        }
+    }
+}
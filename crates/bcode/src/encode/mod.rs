@@ -1,88 +1,130 @@
 use crate::*;
 use anyhow::Result;
 use std::collections::BTreeMap;
+use std::io::Write;
 
 /// Encode a `bcode::Value` to bcode.
 ///
 /// # Arguments
 ///
-/// * `data` - a `bcode::Value` to decode.
+/// * `data` - a `bcode::Value` to encode.
 pub fn encode(data: Value) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(encoded_len(&data));
+    encode_to(&data, &mut out)?;
+
+    Ok(out)
+}
+
+/// Encode a `bcode::Value` to bcode, writing it straight to a writer.
+///
+/// Dictionary keys are written in sorted order, as they are stored in a `BTreeMap`.
+///
+/// # Arguments
+///
+/// * `data` - a `bcode::Value` to encode.
+/// * `writer` - where to write the encoded data, e.g. a file or socket.
+pub fn encode_to<W: Write + ?Sized>(data: &Value, writer: &mut W) -> std::io::Result<()> {
     match data {
-        Value::Integer(inner) => encode_integer(inner),
-        Value::ByteString(inner) => encode_byte_string(inner),
-        Value::List(inner) => encode_list(inner),
-        Value::Dictionary(inner) => encode_dictionary(inner),
+        Value::Integer(inner) => encode_integer(*inner, writer),
+        Value::ByteString(inner) => encode_byte_string(inner, writer),
+        Value::List(inner) => encode_list(inner, writer),
+        Value::Dictionary(inner) => encode_dictionary(inner, writer),
     }
 }
 
-/// Encode an integer to bencode.
+/// Returns the amount of bytes a `bcode::Value` is encoded to, without encoding it.
 ///
 /// # Arguments
 ///
-/// * `data` - integer to encode.
-fn encode_integer(data: i64) -> Result<Vec<u8>> {
-    let mut out: Vec<u8> = vec![];
-
-    out.push(b'i');
-    out.append(&mut data.to_string().as_bytes().to_vec());
-    out.push(b'e');
-
-    Ok(out)
+/// * `data` - a `bcode::Value` to measure.
+pub fn encoded_len(data: &Value) -> usize {
+    match data {
+        Value::Integer(inner) => {
+            let sign = if *inner < 0 { 1 } else { 0 };
+            2 + sign + digits(inner.unsigned_abs())
+        }
+        Value::ByteString(inner) => byte_string_len(inner),
+        Value::List(inner) => 2 + inner.iter().map(encoded_len).sum::<usize>(),
+        Value::Dictionary(inner) => {
+            2 + inner
+                .iter()
+                .map(|(k, v)| byte_string_len(k) + encoded_len(v))
+                .sum::<usize>()
+        }
+    }
 }
 
-/// Encode a byte string to bencode.
+/// Returns the amount of decimal digits in a number.
 ///
 /// # Arguments
 ///
-/// * `data` - `Vec<u8>` to encode.
-pub fn encode_byte_string(data: Vec<u8>) -> Result<Vec<u8>> {
-    let mut out: Vec<u8> = vec![];
+/// * `number` - number to count digits of.
+fn digits(number: u64) -> usize {
+    number.checked_ilog10().unwrap_or(0) as usize + 1
+}
 
-    out.append(&mut data.len().to_string().as_bytes().to_vec());
-    out.push(b':');
+/// Returns the amount of bytes a byte string is encoded to.
+///
+/// # Arguments
+///
+/// * `data` - byte string to measure.
+fn byte_string_len(data: &[u8]) -> usize {
+    digits(data.len() as u64) + 1 + data.len()
+}
 
-    let mut temp = data;
-    out.append(&mut temp);
+/// Encode an integer to bencode.
+///
+/// # Arguments
+///
+/// * `data` - integer to encode.
+/// * `writer` - where to write the encoded data.
+fn encode_integer<W: Write + ?Sized>(data: i64, writer: &mut W) -> std::io::Result<()> {
+    write!(writer, "i{data}e")
+}
 
-    Ok(out)
+/// Encode a byte string to bencode.
+///
+/// # Arguments
+///
+/// * `data` - byte string to encode.
+/// * `writer` - where to write the encoded data.
+fn encode_byte_string<W: Write + ?Sized>(data: &[u8], writer: &mut W) -> std::io::Result<()> {
+    write!(writer, "{}:", data.len())?;
+    writer.write_all(data)
 }
 
 /// Encode a list to bencode.
 ///
 /// # Arguments
 ///
-/// * `data` - `Vec<Value>` to encode.
-pub fn encode_list(data: Vec<Value>) -> Result<Vec<u8>> {
-    let mut out: Vec<u8> = vec![];
-
-    out.push(b'l');
+/// * `data` - list to encode.
+/// * `writer` - where to write the encoded data.
+fn encode_list<W: Write + ?Sized>(data: &[Value], writer: &mut W) -> std::io::Result<()> {
+    writer.write_all(b"l")?;
 
     for value in data {
-        out.append(&mut encode(value)?);
+        encode_to(value, writer)?;
     }
 
-    out.push(b'e');
-
-    Ok(out)
+    writer.write_all(b"e")
 }
 
 /// Encode a dictionary to bencode.
 ///
 /// # Arguments
 ///
-/// * `data` - `BTreeMap<Vec<u8>, Value>` to encode
-pub fn encode_dictionary(data: BTreeMap<Vec<u8>, Value>) -> Result<Vec<u8>> {
-    let mut out: Vec<u8> = vec![];
-
-    out.push(b'd');
+/// * `data` - dictionary to encode.
+/// * `writer` - where to write the encoded data.
+fn encode_dictionary<W: Write + ?Sized>(
+    data: &BTreeMap<Vec<u8>, Value>,
+    writer: &mut W,
+) -> std::io::Result<()> {
+    writer.write_all(b"d")?;
 
     for (k, v) in data {
-        out.append(&mut encode_byte_string(k)?);
-        out.append(&mut encode(v)?);
+        encode_byte_string(k, writer)?;
+        encode_to(v, writer)?;
     }
 
-    out.push(b'e');
-
-    Ok(out)
+    writer.write_all(b"e")
 }
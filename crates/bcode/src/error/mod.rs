@@ -0,0 +1,170 @@
+use std::fmt;
+
+/// Errors returned while decoding bcode, or by the serde serializer and deserializer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Data ended before the value was complete.
+    UnexpectedEof(Position),
+    /// A byte that can't appear at this position.
+    UnexpectedByte(u8, Position),
+    /// An integer (or byte string length) that is empty, `-0` or doesn't fit.
+    InvalidInteger(Position),
+    /// An integer (or byte string length) with leading zeros.
+    LeadingZero(Position),
+    /// A dictionary key that isn't a byte string.
+    InvalidKey(Position),
+    /// A dictionary key that is smaller than the key before it.
+    UnsortedKeys(Position),
+    /// A dictionary key that appears more than once.
+    DuplicateKey(Position),
+    /// Values are nested deeper than allowed.
+    DepthExceeded(Position),
+    /// Data is longer than allowed.
+    SizeExceeded(Position),
+    /// A byte string is longer than allowed.
+    StringTooLong(Position),
+    /// A list or dictionary has more entries than allowed.
+    TooManyEntries(Position),
+    /// Data left over after the root value.
+    TrailingData(Position),
+    /// Free-form error message, usually produced by serde itself.
+    Custom(String),
+}
+
+/// Where in the data an error occurred.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Position {
+    /// Byte offset into the data.
+    pub offset: usize,
+    /// Dictionary keys and list indices leading to the failing value, outermost first.
+    pub path: Vec<PathSegment>,
+}
+
+/// A step into a nested value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(Vec<u8>),
+    Index(usize),
+}
+
+impl Position {
+    /// Create a new position with an empty path.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - byte offset into the data.
+    pub fn new(offset: usize) -> Position {
+        Position {
+            offset,
+            path: vec![],
+        }
+    }
+}
+
+impl Error {
+    /// Returns the position of the error, if it has one.
+    pub fn position(&self) -> Option<&Position> {
+        match self {
+            Error::UnexpectedEof(position)
+            | Error::UnexpectedByte(_, position)
+            | Error::InvalidInteger(position)
+            | Error::LeadingZero(position)
+            | Error::InvalidKey(position)
+            | Error::UnsortedKeys(position)
+            | Error::DuplicateKey(position)
+            | Error::DepthExceeded(position)
+            | Error::SizeExceeded(position)
+            | Error::StringTooLong(position)
+            | Error::TooManyEntries(position)
+            | Error::TrailingData(position) => Some(position),
+            Error::Custom(_) => None,
+        }
+    }
+
+    /// Returns the position of the error mutably, if it has one.
+    pub(crate) fn position_mut(&mut self) -> Option<&mut Position> {
+        match self {
+            Error::UnexpectedEof(position)
+            | Error::UnexpectedByte(_, position)
+            | Error::InvalidInteger(position)
+            | Error::LeadingZero(position)
+            | Error::InvalidKey(position)
+            | Error::UnsortedKeys(position)
+            | Error::DuplicateKey(position)
+            | Error::DepthExceeded(position)
+            | Error::SizeExceeded(position)
+            | Error::StringTooLong(position)
+            | Error::TooManyEntries(position)
+            | Error::TrailingData(position) => Some(position),
+            Error::Custom(_) => None,
+        }
+    }
+
+    /// Returns the byte offset of the error, if it has one.
+    pub fn offset(&self) -> Option<usize> {
+        self.position().map(|position| position.offset)
+    }
+
+    /// Returns the path to the failing value (empty if the root value failed).
+    pub fn path(&self) -> &[PathSegment] {
+        self.position()
+            .map(|position| position.path.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnexpectedEof(_) => write!(f, "Unexpected end of data")?,
+            Error::UnexpectedByte(byte, _) => write!(f, "Unexpected byte {:?}", char::from(*byte))?,
+            Error::InvalidInteger(_) => write!(f, "Invalid integer")?,
+            Error::LeadingZero(_) => write!(f, "Leading zeros are not allowed")?,
+            Error::InvalidKey(_) => write!(f, "Dictionary key must be a byte string")?,
+            Error::UnsortedKeys(_) => write!(f, "Dictionary keys are not sorted")?,
+            Error::DuplicateKey(_) => write!(f, "Duplicate dictionary key")?,
+            Error::DepthExceeded(_) => write!(f, "Maximum depth exceeded")?,
+            Error::SizeExceeded(_) => write!(f, "Maximum size exceeded")?,
+            Error::StringTooLong(_) => write!(f, "Maximum byte string length exceeded")?,
+            Error::TooManyEntries(_) => write!(f, "Maximum amount of entries exceeded")?,
+            Error::TrailingData(_) => write!(f, "Trailing data after bcode value")?,
+            Error::Custom(message) => return write!(f, "{message}"),
+        }
+
+        if let Some(position) = self.position() {
+            write!(f, " at byte {}", position.offset)?;
+
+            if !position.path.is_empty() {
+                write!(f, " (in ")?;
+
+                for (i, segment) in position.path.iter().enumerate() {
+                    match segment {
+                        PathSegment::Key(key) if i == 0 => {
+                            write!(f, "{}", String::from_utf8_lossy(key))?
+                        }
+                        PathSegment::Key(key) => write!(f, ".{}", String::from_utf8_lossy(key))?,
+                        PathSegment::Index(index) => write!(f, "[{index}]")?,
+                    }
+                }
+
+                write!(f, ")")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Error::Custom(message.to_string())
+    }
+}
+
+impl serde::de::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Error::Custom(message.to_string())
+    }
+}
@@ -0,0 +1,31 @@
+use crate::Error;
+
+/// Encode bytes as lowercase hex.
+///
+/// # Arguments
+///
+/// * `bytes` - bytes to encode.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decode hex to bytes, in either case.
+///
+/// # Arguments
+///
+/// * `hex` - hex to decode.
+pub fn from_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    // `from_str_radix` alone would accept a sign, like in "+f".
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(Error::Custom(format!("Invalid hex \"{hex}\"")));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| Error::Custom(format!("Invalid hex \"{hex}\"")))
+        })
+        .collect()
+}
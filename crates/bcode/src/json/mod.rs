@@ -0,0 +1,78 @@
+use crate::*;
+use serde_json::Map;
+use std::collections::BTreeMap;
+
+/// Prefix of JSON strings holding hex encoded byte strings.
+const HEX_PREFIX: &str = "hex:";
+
+/// Convert a `bcode::Value` to JSON, without losing any information.
+///
+/// Byte strings become JSON strings if they are UTF-8, otherwise (or if they would be
+/// mistaken for one) they become `"hex:"` followed by the bytes as hex.
+///
+/// # Arguments
+///
+/// * `value` - `bcode::Value` to convert.
+pub fn to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Integer(inner) => serde_json::Value::from(*inner),
+        Value::ByteString(inner) => serde_json::Value::String(byte_string_to_json(inner)),
+        Value::List(inner) => serde_json::Value::Array(inner.iter().map(to_json).collect()),
+        Value::Dictionary(inner) => serde_json::Value::Object(
+            inner
+                .iter()
+                .map(|(k, v)| (byte_string_to_json(k), to_json(v)))
+                .collect::<Map<String, serde_json::Value>>(),
+        ),
+    }
+}
+
+/// Convert JSON created by `bcode::to_json` back to a `bcode::Value`.
+///
+/// # Arguments
+///
+/// * `json` - JSON to convert.
+pub fn from_json(json: &serde_json::Value) -> Result<Value, Error> {
+    match json {
+        serde_json::Value::Number(inner) => inner
+            .as_i64()
+            .map(Value::Integer)
+            .ok_or_else(|| Error::Custom(format!("{inner} is not a bcode integer"))),
+        serde_json::Value::String(inner) => json_to_byte_string(inner).map(Value::ByteString),
+        serde_json::Value::Array(inner) => inner
+            .iter()
+            .map(from_json)
+            .collect::<Result<Vec<Value>, Error>>()
+            .map(Value::List),
+        serde_json::Value::Object(inner) => inner
+            .iter()
+            .map(|(k, v)| Ok((json_to_byte_string(k)?, from_json(v)?)))
+            .collect::<Result<BTreeMap<Vec<u8>, Value>, Error>>()
+            .map(Value::Dictionary),
+        _ => Err(Error::Custom(format!("{json} has no bcode equivalent"))),
+    }
+}
+
+/// Convert a byte string to a JSON string.
+///
+/// # Arguments
+///
+/// * `byte_string` - bytes to convert.
+fn byte_string_to_json(byte_string: &[u8]) -> String {
+    match std::str::from_utf8(byte_string) {
+        Ok(string) if !string.starts_with(HEX_PREFIX) => string.to_string(),
+        _ => format!("{HEX_PREFIX}{}", to_hex(byte_string)),
+    }
+}
+
+/// Convert a JSON string back to a byte string.
+///
+/// # Arguments
+///
+/// * `string` - JSON string to convert.
+fn json_to_byte_string(string: &str) -> Result<Vec<u8>, Error> {
+    match string.strip_prefix(HEX_PREFIX) {
+        Some(hex) => from_hex(hex),
+        None => Ok(string.as_bytes().to_vec()),
+    }
+}
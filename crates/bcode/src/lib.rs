@@ -1,15 +1,40 @@
 //! # Bcode
 //!
 //! `bcode` is a library for decoding and encoding to bcode.
+//!
+//! Types implementing serde's `Serialize` and `Deserialize` can be converted
+//! with [`to_bytes`] and [`from_bytes`]. Values can be shown with [`Value::pretty`],
+//! or converted to and from JSON with `to_json` and `from_json` (with the `json` feature).
+//!
+//! Structs can also be converted with `#[derive(BEncode, BDecode)]`, see [`BEncode`] and [`BDecode`].
 
 // TODO:
-// * Make sure dictionaries are sorted.
 // * Write more test cases.
 
+pub mod bytes;
+mod de;
 mod decode;
 mod encode;
+mod error;
+mod hex;
+#[cfg(feature = "json")]
+mod json;
+mod pretty;
+mod ser;
+mod stream;
+mod traits;
 mod value;
 
-pub use crate::decode::decode;
-pub use crate::encode::encode;
-pub use crate::value::{map_get, Value};
+pub use crate::de::{from_bytes, from_value, Deserializer};
+pub use crate::decode::{decode, decode_ref, decode_spanned, DecodeOptions, Decoder, Mode};
+pub use crate::encode::{encode, encode_to, encoded_len};
+pub use crate::error::{Error, PathSegment, Position};
+pub use crate::hex::{from_hex, to_hex};
+#[cfg(feature = "json")]
+pub use crate::json::{from_json, to_json};
+pub use crate::pretty::Pretty;
+pub use crate::ser::{to_bytes, to_value, Serializer};
+pub use crate::stream::StreamDecoder;
+pub use crate::traits::{BDecode, BEncode};
+pub use crate::value::{map_get, Spanned, SpannedValue, Value, ValueRef};
+pub use bcode_derive::{BDecode, BEncode};
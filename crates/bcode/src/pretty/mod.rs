@@ -0,0 +1,114 @@
+use crate::hex::to_hex;
+use crate::*;
+use std::fmt;
+
+/// Human-readable formatting of a `bcode::Value`, created with `Value::pretty`.
+///
+/// Byte strings are shown as quoted text when they are printable UTF-8,
+/// otherwise as their length and hex, truncated to `max_bytes`.
+#[derive(Debug, Clone, Copy)]
+pub struct Pretty<'a> {
+    value: &'a Value,
+    max_bytes: Option<usize>,
+}
+
+impl Value {
+    /// Returns a human-readable formatter for the value.
+    pub fn pretty(&self) -> Pretty<'_> {
+        Pretty {
+            value: self,
+            max_bytes: Some(32),
+        }
+    }
+}
+
+impl Pretty<'_> {
+    /// Set how many bytes of binary byte strings to show, `None` to show all.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_bytes` - amount of bytes to show.
+    pub fn max_bytes(mut self, max_bytes: Option<usize>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Write a value at the given indentation level.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - formatter to write to.
+    /// * `value` - value to write.
+    /// * `level` - indentation level.
+    fn write_value(&self, f: &mut fmt::Formatter<'_>, value: &Value, level: usize) -> fmt::Result {
+        let indent = "  ".repeat(level + 1);
+
+        match value {
+            Value::Integer(inner) => write!(f, "{inner}"),
+            Value::ByteString(inner) => self.write_byte_string(f, inner),
+            Value::List(inner) if inner.is_empty() => write!(f, "[]"),
+            Value::List(inner) => {
+                writeln!(f, "[")?;
+
+                for value in inner {
+                    write!(f, "{indent}")?;
+                    self.write_value(f, value, level + 1)?;
+                    writeln!(f, ",")?;
+                }
+
+                write!(f, "{}]", &indent[2..])
+            }
+            Value::Dictionary(inner) if inner.is_empty() => write!(f, "{{}}"),
+            Value::Dictionary(inner) => {
+                writeln!(f, "{{")?;
+
+                for (key, value) in inner {
+                    write!(f, "{indent}")?;
+                    self.write_byte_string(f, key)?;
+                    write!(f, ": ")?;
+                    self.write_value(f, value, level + 1)?;
+                    writeln!(f, ",")?;
+                }
+
+                write!(f, "{}}}", &indent[2..])
+            }
+        }
+    }
+
+    /// Write a byte string, as text if possible.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - formatter to write to.
+    /// * `byte_string` - byte string to write.
+    fn write_byte_string(&self, f: &mut fmt::Formatter<'_>, byte_string: &[u8]) -> fmt::Result {
+        match std::str::from_utf8(byte_string) {
+            Ok(string) if !string.chars().any(char::is_control) => write!(f, "{string:?}"),
+            _ => {
+                let shown = match self.max_bytes {
+                    Some(max_bytes) if max_bytes < byte_string.len() => &byte_string[..max_bytes],
+                    _ => byte_string,
+                };
+                let ellipsis = if shown.len() < byte_string.len() {
+                    "..."
+                } else {
+                    ""
+                };
+
+                write!(
+                    f,
+                    "<{} bytes: {}{}>",
+                    byte_string.len(),
+                    to_hex(shown),
+                    ellipsis
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for Pretty<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_value(f, self.value, 0)
+    }
+}
@@ -0,0 +1,390 @@
+use crate::*;
+use serde::ser::{self, Serialize};
+use std::collections::BTreeMap;
+
+/// Serialize a value to bcode.
+///
+/// Dictionary keys are written in sorted order, and `None` fields are left out.
+///
+/// # Arguments
+///
+/// * `value` - value to serialize.
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    encode(to_value(value)?).map_err(|e| Error::Custom(e.to_string()))
+}
+
+/// Serialize a value to a `bcode::Value`.
+///
+/// # Arguments
+///
+/// * `value` - value to serialize.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+    value
+        .serialize(Serializer)?
+        .ok_or_else(|| Error::Custom("Root value can not be empty".to_string()))
+}
+
+impl Serialize for Value {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Integer(inner) => serializer.serialize_i64(*inner),
+            Value::ByteString(inner) => serializer.serialize_bytes(inner),
+            Value::List(inner) => serializer.collect_seq(inner),
+            Value::Dictionary(inner) => {
+                serializer.collect_map(inner.iter().map(|(k, v)| (Bytes(k), v)))
+            }
+        }
+    }
+}
+
+/// Helper to serialize a byte slice as a byte string instead of a sequence.
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+/// Serializer producing a `bcode::Value`.
+///
+/// Values that can't be represented in bcode (`None` and unit) serialize to `None`,
+/// which dictionaries skip and everything else rejects.
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeVariant<SerializeList>;
+    type SerializeMap = SerializeDictionary;
+    type SerializeStruct = SerializeDictionary;
+    type SerializeStructVariant = SerializeVariant<SerializeDictionary>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Error> {
+        Ok(Some(Value::Integer(v as i64)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Error> {
+        Ok(Some(Value::Integer(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Error> {
+        let v = i64::try_from(v).map_err(|_| Error::Custom(format!("{v} is too large")))?;
+        self.serialize_i64(v)
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<Self::Ok, Error> {
+        Err(Error::Custom("Floats are not supported".to_string()))
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<Self::Ok, Error> {
+        Err(Error::Custom("Floats are not supported".to_string()))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Error> {
+        self.serialize_bytes(v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Error> {
+        Ok(Some(Value::ByteString(v.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Error> {
+        let mut dictionary = BTreeMap::new();
+        dictionary.insert(variant.as_bytes().to_vec(), to_value(value)?);
+
+        Ok(Some(Value::Dictionary(dictionary)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Ok(SerializeList {
+            list: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Ok(SerializeDictionary {
+            dictionary: BTreeMap::new(),
+            current_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<Self::SerializeStruct, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+/// Serializer state for lists, tuples and tuple structs.
+pub struct SerializeList {
+    list: Vec<Value>,
+}
+
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let value = value
+            .serialize(Serializer)?
+            .ok_or_else(|| Error::Custom("Lists can not contain empty values".to_string()))?;
+        self.list.push(value);
+
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        Ok(Some(Value::List(self.list)))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        Ok(Some(Value::List(self.list)))
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        Ok(Some(Value::List(self.list)))
+    }
+}
+
+/// Serializer state for maps and structs.
+pub struct SerializeDictionary {
+    dictionary: BTreeMap<Vec<u8>, Value>,
+    current_key: Option<Vec<u8>>,
+}
+
+impl SerializeDictionary {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: Vec<u8>, value: &T) -> Result<(), Error> {
+        if let Some(value) = value.serialize(Serializer)? {
+            self.dictionary.insert(key, value);
+        }
+
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for SerializeDictionary {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        match key.serialize(Serializer)? {
+            Some(Value::ByteString(key)) => {
+                self.current_key = Some(key);
+                Ok(())
+            }
+            _ => Err(Error::Custom(
+                "Dictionary key must be a byte string".to_string(),
+            )),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .current_key
+            .take()
+            .ok_or_else(|| Error::Custom("Dictionary value without a key".to_string()))?;
+
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        Ok(Some(Value::Dictionary(self.dictionary)))
+    }
+}
+
+impl ser::SerializeStruct for SerializeDictionary {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        Ok(Some(Value::Dictionary(self.dictionary)))
+    }
+}
+
+/// Serializer state for enum variants with data, written as `{ variant: data }`.
+pub struct SerializeVariant<T> {
+    variant: &'static str,
+    inner: T,
+}
+
+impl<T> SerializeVariant<T> {
+    fn wrap(variant: &'static str, value: Option<Value>) -> Result<Option<Value>, Error> {
+        let mut dictionary = BTreeMap::new();
+
+        if let Some(value) = value {
+            dictionary.insert(variant.as_bytes().to_vec(), value);
+        }
+
+        Ok(Some(Value::Dictionary(dictionary)))
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeList> {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.inner.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        Self::wrap(self.variant, ser::SerializeSeq::end(self.inner)?)
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeDictionary> {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.inner.insert(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        Self::wrap(self.variant, ser::SerializeMap::end(self.inner)?)
+    }
+}
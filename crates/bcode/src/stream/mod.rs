@@ -0,0 +1,253 @@
+use crate::*;
+
+/// Push-style decoder for bcode arriving in chunks, e.g. from a socket.
+///
+/// Bytes are scanned as they are pushed, so a value is only decoded once it is complete.
+/// `push` refuses data that would buffer more than `max_size` bytes, and the other limits in
+/// `DecodeOptions` are enforced while scanning, before a value is decoded.
+/// After an error, the buffered data is discarded.
+#[derive(Debug, Default)]
+pub struct StreamDecoder {
+    buffer: Vec<u8>,
+    options: DecodeOptions,
+    scanned: usize,
+    consumed: usize,
+    /// Lists and dictionaries the scanner is in, outermost first.
+    open: Vec<Open>,
+    state: State,
+}
+
+/// A list or dictionary being scanned.
+#[derive(Debug, Clone, Copy)]
+struct Open {
+    /// Index of its first byte.
+    start: usize,
+    dictionary: bool,
+    /// Elements started so far, keys and values both count in a dictionary.
+    elements: usize,
+}
+
+/// What the scanner expects next.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum State {
+    /// The start of a value, or the end of a list or dictionary.
+    #[default]
+    Value,
+    /// More digits of an integer, or its end.
+    Integer,
+    /// More digits of a byte string length, or the separator.
+    Length(usize),
+    /// The remaining bytes of a byte string.
+    ByteString(usize),
+}
+
+impl StreamDecoder {
+    /// Create a new stream decoder with the default options.
+    pub fn new() -> StreamDecoder {
+        StreamDecoder::default()
+    }
+
+    /// Create a new stream decoder.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - mode and limits to decode with, `max_size` applies to each value and to
+    ///   the buffered data.
+    pub fn with_options(options: DecodeOptions) -> StreamDecoder {
+        StreamDecoder {
+            options,
+            ..Default::default()
+        }
+    }
+
+    /// Add a chunk of data.
+    ///
+    /// Fails if more than `max_size` bytes would be buffered, discarding the buffered data.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - bytes to add.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        if self.buffer.len().saturating_add(chunk.len()) > self.options.max_size {
+            let error = Error::SizeExceeded(Position::new(self.options.max_size));
+            return Err(self.fail(error));
+        }
+
+        self.buffer.extend_from_slice(chunk);
+
+        Ok(())
+    }
+
+    /// Returns the amount of bytes pushed, but not yet returned as a value.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Take the bytes pushed, but not yet returned as a value.
+    ///
+    /// Useful when a bencoded value is followed by raw data, like in `ut_metadata` messages.
+    pub fn take_remaining(&mut self) -> Vec<u8> {
+        self.consumed += self.buffer.len();
+        self.reset();
+
+        std::mem::take(&mut self.buffer)
+    }
+
+    /// Decode the next value, returns `None` if more data is needed.
+    pub fn next_value(&mut self) -> Result<Option<Value>, Error> {
+        match self.scan() {
+            Ok(true) => {}
+            Ok(false) => return Ok(None),
+            Err(e) => return Err(self.fail(e)),
+        }
+
+        let length = self.scanned;
+        let mut decoder = Decoder::with_options(&self.buffer[..length], self.options);
+        let value = decoder.decode().and_then(|value| {
+            decoder.finish()?;
+            Ok(value)
+        });
+
+        match value {
+            Ok(value) => {
+                self.buffer.drain(..length);
+                self.consumed += length;
+                self.reset();
+
+                Ok(Some(value))
+            }
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+
+    /// Scan pushed data, returns `true` once `scanned` is at the end of a complete value.
+    fn scan(&mut self) -> Result<bool, Error> {
+        while self.scanned < self.buffer.len() {
+            let at = self.scanned;
+            let byte = self.buffer[at];
+            let mut complete = false;
+
+            if self.state == State::Value && byte != b'e' {
+                self.count_element()?;
+            }
+
+            match self.state {
+                State::Value => match byte {
+                    b'i' => self.state = State::Integer,
+                    b'0'..=b'9' => self.state = State::Length((byte - b'0') as usize),
+                    b'l' | b'd' => {
+                        if self.open.len() >= self.options.max_depth {
+                            return Err(Error::DepthExceeded(Position::new(at)));
+                        }
+
+                        self.open.push(Open {
+                            start: at,
+                            dictionary: byte == b'd',
+                            elements: 0,
+                        });
+                    }
+                    b'e' if !self.open.is_empty() => {
+                        self.open.pop();
+                        complete = self.open.is_empty();
+                    }
+                    _ => return Err(Error::UnexpectedByte(byte, Position::new(at))),
+                },
+                State::Integer => match byte {
+                    b'-' | b'0'..=b'9' => {}
+                    b'e' => {
+                        self.state = State::Value;
+                        complete = self.open.is_empty();
+                    }
+                    _ => return Err(Error::UnexpectedByte(byte, Position::new(at))),
+                },
+                State::Length(length) => match byte {
+                    b'0'..=b'9' => {
+                        let length = length
+                            .checked_mul(10)
+                            .and_then(|x| x.checked_add((byte - b'0') as usize))
+                            .ok_or_else(|| Error::InvalidInteger(Position::new(at)))?;
+
+                        if length > self.options.max_string_length {
+                            return Err(Error::StringTooLong(Position::new(at)));
+                        }
+
+                        self.state = State::Length(length);
+                    }
+                    b':' if length == 0 => {
+                        self.state = State::Value;
+                        complete = self.open.is_empty();
+                    }
+                    b':' => self.state = State::ByteString(length),
+                    _ => return Err(Error::UnexpectedByte(byte, Position::new(at))),
+                },
+                State::ByteString(remaining) => {
+                    // Skip as much of the byte string as is available at once.
+                    let available = (self.buffer.len() - at).min(remaining);
+                    self.scanned += available - 1;
+
+                    if available == remaining {
+                        self.state = State::Value;
+                        complete = self.open.is_empty();
+                    } else {
+                        self.state = State::ByteString(remaining - available);
+                    }
+                }
+            }
+
+            self.scanned += 1;
+
+            if self.scanned > self.options.max_size {
+                return Err(Error::SizeExceeded(Position::new(self.options.max_size)));
+            }
+
+            if complete {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Count a value starting in the innermost list or dictionary, checking `max_entries`.
+    fn count_element(&mut self) -> Result<(), Error> {
+        let Some(open) = self.open.last_mut() else {
+            return Ok(());
+        };
+        open.elements += 1;
+
+        let entries = match open.dictionary {
+            true => open.elements.div_ceil(2),
+            false => open.elements,
+        };
+
+        if entries > self.options.max_entries {
+            Err(Error::TooManyEntries(Position::new(open.start)))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Discard the buffered data, and return the error with an offset into the whole stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - error with an offset relative to the current value.
+    fn fail(&mut self, mut error: Error) -> Error {
+        if let Some(position) = error.position_mut() {
+            position.offset += self.consumed;
+        }
+
+        self.consumed += self.buffer.len();
+        self.buffer.clear();
+        self.reset();
+
+        error
+    }
+
+    /// Prepare for scanning the next value.
+    fn reset(&mut self) {
+        self.scanned = 0;
+        self.open.clear();
+        self.state = State::Value;
+    }
+}
@@ -0,0 +1,133 @@
+use crate::*;
+use std::collections::BTreeMap;
+
+/// Types that can be converted to a `bcode::Value`, usually with `#[derive(BEncode)]`.
+pub trait BEncode {
+    /// Convert to a `bcode::Value`.
+    fn bencode(&self) -> Value;
+}
+
+/// Types that can be created from a `bcode::Value`, usually with `#[derive(BDecode)]`.
+pub trait BDecode: Sized {
+    /// Create from a `bcode::Value`.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - value to convert.
+    fn bdecode(value: Value) -> Result<Self, Error>;
+}
+
+impl BEncode for Value {
+    fn bencode(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl BDecode for Value {
+    fn bdecode(value: Value) -> Result<Self, Error> {
+        Ok(value)
+    }
+}
+
+impl BEncode for i64 {
+    fn bencode(&self) -> Value {
+        Value::Integer(*self)
+    }
+}
+
+impl BDecode for i64 {
+    fn bdecode(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Integer(out) => Ok(out),
+            _ => Err(Error::Custom("Value is not a integer".to_string())),
+        }
+    }
+}
+
+/// Implement the traits for integer types that convert to and from `i64`.
+macro_rules! impl_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl BEncode for $ty {
+                fn bencode(&self) -> Value {
+                    Value::Integer(*self as i64)
+                }
+            }
+
+            impl BDecode for $ty {
+                fn bdecode(value: Value) -> Result<Self, Error> {
+                    let integer = i64::bdecode(value)?;
+
+                    <$ty>::try_from(integer)
+                        .map_err(|_| Error::Custom(format!("{integer} is out of range")))
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, u8, u16, u32);
+
+impl BEncode for bool {
+    fn bencode(&self) -> Value {
+        Value::Integer(*self as i64)
+    }
+}
+
+impl BDecode for bool {
+    fn bdecode(value: Value) -> Result<Self, Error> {
+        Ok(i64::bdecode(value)? != 0)
+    }
+}
+
+impl BEncode for String {
+    fn bencode(&self) -> Value {
+        Value::ByteString(self.as_bytes().to_vec())
+    }
+}
+
+impl BDecode for String {
+    fn bdecode(value: Value) -> Result<Self, Error> {
+        String::from_utf8(bytes::bdecode(value)?).map_err(|e| Error::Custom(e.to_string()))
+    }
+}
+
+impl<T: BEncode> BEncode for Vec<T> {
+    fn bencode(&self) -> Value {
+        Value::List(self.iter().map(BEncode::bencode).collect())
+    }
+}
+
+impl<T: BDecode> BDecode for Vec<T> {
+    fn bdecode(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::List(list) => list.into_iter().map(T::bdecode).collect(),
+            _ => Err(Error::Custom("Value is not a list".to_string())),
+        }
+    }
+}
+
+impl<T: BEncode> BEncode for BTreeMap<String, T> {
+    fn bencode(&self) -> Value {
+        Value::Dictionary(
+            self.iter()
+                .map(|(k, v)| (k.as_bytes().to_vec(), v.bencode()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: BDecode> BDecode for BTreeMap<String, T> {
+    fn bdecode(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Dictionary(dictionary) => dictionary
+                .into_iter()
+                .map(|(k, v)| {
+                    let k = String::from_utf8(k).map_err(|e| Error::Custom(e.to_string()))?;
+                    Ok((k, T::bdecode(v)?))
+                })
+                .collect(),
+            _ => Err(Error::Custom("Value is not a dictionary".to_string())),
+        }
+    }
+}
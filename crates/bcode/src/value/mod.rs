@@ -1,6 +1,12 @@
+mod spanned;
+mod value_ref;
+
 use anyhow::{anyhow, Result};
 use std::collections::BTreeMap;
 
+pub use spanned::{Spanned, SpannedValue};
+pub use value_ref::ValueRef;
+
 /// Data values supported by bcode.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Value {
@@ -10,6 +16,20 @@ pub enum Value {
     Dictionary(BTreeMap<Vec<u8>, Value>),
 }
 
+impl Value {
+    /// Get value from dictionary by reference, returns `None` if not found or if this is not a dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - key to search for.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dictionary(dictionary) => dictionary.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+}
+
 impl From<i64> for Value {
     fn from(integer: i64) -> Self {
         Value::Integer(integer)
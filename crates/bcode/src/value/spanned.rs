@@ -0,0 +1,66 @@
+use super::{Value, ValueRef};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// A decoded value, together with the byte range it occupied in the decoded data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned<'a> {
+    pub value: SpannedValue<'a>,
+    pub span: Range<usize>,
+}
+
+/// Data values supported by bcode, where every nested value keeps its byte range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpannedValue<'a> {
+    Integer(i64),
+    ByteString(&'a [u8]),
+    List(Vec<Spanned<'a>>),
+    Dictionary(BTreeMap<&'a [u8], Spanned<'a>>),
+}
+
+impl<'a> Spanned<'a> {
+    /// Get value from dictionary, returns `None` if not found or if this is not a dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - key to search for.
+    pub fn get(&self, key: &str) -> Option<&Spanned<'a>> {
+        match &self.value {
+            SpannedValue::Dictionary(dictionary) => dictionary.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+
+    /// Get the original bytes of this value.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - the data this value was decoded from.
+    pub fn raw<'b>(&self, data: &'b [u8]) -> &'b [u8] {
+        &data[self.span.clone()]
+    }
+}
+
+impl<'a> From<Spanned<'a>> for ValueRef<'a> {
+    fn from(spanned: Spanned<'a>) -> Self {
+        match spanned.value {
+            SpannedValue::Integer(inner) => ValueRef::Integer(inner),
+            SpannedValue::ByteString(inner) => ValueRef::ByteString(inner),
+            SpannedValue::List(inner) => {
+                ValueRef::List(inner.into_iter().map(ValueRef::from).collect())
+            }
+            SpannedValue::Dictionary(inner) => ValueRef::Dictionary(
+                inner
+                    .into_iter()
+                    .map(|(k, v)| (k, ValueRef::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a> From<Spanned<'a>> for Value {
+    fn from(spanned: Spanned<'a>) -> Self {
+        Value::from(ValueRef::from(spanned))
+    }
+}
@@ -0,0 +1,91 @@
+use super::Value;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+
+/// Data values supported by bcode, borrowing byte strings from the decoded data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueRef<'a> {
+    Integer(i64),
+    ByteString(&'a [u8]),
+    List(Vec<ValueRef<'a>>),
+    Dictionary(BTreeMap<&'a [u8], ValueRef<'a>>),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Get value from dictionary, returns `None` if not found or if this is not a dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - key to search for.
+    pub fn get(&self, key: &str) -> Option<&ValueRef<'a>> {
+        match self {
+            ValueRef::Dictionary(dictionary) => dictionary.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> From<ValueRef<'a>> for Value {
+    fn from(value: ValueRef<'a>) -> Self {
+        match value {
+            ValueRef::Integer(inner) => Value::Integer(inner),
+            ValueRef::ByteString(inner) => Value::ByteString(inner.to_vec()),
+            ValueRef::List(inner) => Value::List(inner.into_iter().map(Value::from).collect()),
+            ValueRef::Dictionary(inner) => Value::Dictionary(
+                inner
+                    .into_iter()
+                    .map(|(k, v)| (k.to_vec(), Value::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a> From<&'a Value> for ValueRef<'a> {
+    fn from(value: &'a Value) -> Self {
+        match value {
+            Value::Integer(inner) => ValueRef::Integer(*inner),
+            Value::ByteString(inner) => ValueRef::ByteString(inner),
+            Value::List(inner) => ValueRef::List(inner.iter().map(ValueRef::from).collect()),
+            Value::Dictionary(inner) => ValueRef::Dictionary(
+                inner
+                    .iter()
+                    .map(|(k, v)| (k.as_slice(), ValueRef::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a> TryFrom<&ValueRef<'a>> for i64 {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &ValueRef<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ValueRef::Integer(out) => Ok(*out),
+            _ => Err(anyhow!("Value is not a integer")),
+        }
+    }
+}
+
+impl<'a> TryFrom<&ValueRef<'a>> for &'a [u8] {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &ValueRef<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ValueRef::ByteString(out) => Ok(out),
+            _ => Err(anyhow!("Value is not a byte string")),
+        }
+    }
+}
+
+impl<'a> TryFrom<&ValueRef<'a>> for &'a str {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &ValueRef<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ValueRef::ByteString(out) => Ok(std::str::from_utf8(out)?),
+            _ => Err(anyhow!("Value is not a byte string")),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use bcode::{Decoder, Error, Mode, PathSegment, Position};
+
+#[test]
+fn decode_errors_bcode() {
+    let cases: Vec<(&[u8], Error)> = vec![
+        (b"", Error::UnexpectedEof(Position::new(0))),
+        (b"i42", Error::UnexpectedEof(Position::new(3))),
+        (b"i-0e", Error::InvalidInteger(Position::new(1))),
+        (b"ie", Error::InvalidInteger(Position::new(1))),
+        (
+            b"i99999999999999999999e",
+            Error::InvalidInteger(Position::new(1)),
+        ),
+        (b"i03e", Error::LeadingZero(Position::new(1))),
+        (b"03:abc", Error::LeadingZero(Position::new(0))),
+        (b"5:abc", Error::UnexpectedEof(Position::new(5))),
+        (b"x", Error::UnexpectedByte(b'x', Position::new(0))),
+        (b"di1ei2ee", Error::InvalidKey(Position::new(1))),
+    ];
+
+    for (data, error) in cases {
+        let mut decoder = Decoder::new(data, Mode::Strict);
+        assert_eq!(decoder.decode().unwrap_err(), error);
+    }
+
+    // Errors in nested values report the path leading to them.
+    let error = bcode::decode(b"d4:infod5:filesli1eixeeee", &mut 0_usize).unwrap_err();
+
+    assert_eq!(error.offset(), Some(20));
+    assert_eq!(
+        error.path(),
+        [
+            PathSegment::Key(b"info".to_vec()),
+            PathSegment::Key(b"files".to_vec()),
+            PathSegment::Index(1),
+        ]
+    );
+    assert_eq!(
+        error.to_string(),
+        "Unexpected byte 'x' at byte 20 (in info.files[1])"
+    );
+}
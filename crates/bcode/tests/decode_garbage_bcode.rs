@@ -0,0 +1,40 @@
+mod common;
+
+/// Simple xorshift generator, so the test is reproducible without extra dependencies.
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+#[test]
+fn decode_garbage_bcode() {
+    let mut state = 0x2545f4914f6cdd1d;
+    let alphabet = b"ilde0123456789:-x";
+
+    for (left, _) in common::get_comparison_data() {
+        for _ in 0..2_000 {
+            let mut data = left.to_vec();
+
+            // Flip, insert or remove a few bytes.
+            for _ in 0..(next(&mut state) % 4) {
+                let at = next(&mut state) as usize % (data.len() + 1);
+                let byte = alphabet[next(&mut state) as usize % alphabet.len()];
+
+                match next(&mut state) % 3 {
+                    0 if at < data.len() => data[at] = byte,
+                    1 => data.insert(at, byte),
+                    _ if at < data.len() => {
+                        data.remove(at);
+                    }
+                    _ => {}
+                }
+            }
+
+            // Must return, never panic.
+            let _ = bcode::decode(&data, &mut 0_usize);
+            let _ = bcode::from_bytes::<bcode::Value>(&data);
+        }
+    }
+}
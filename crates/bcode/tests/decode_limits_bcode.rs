@@ -0,0 +1,41 @@
+use bcode::{DecodeOptions, Decoder, Error, PathSegment, Position};
+
+#[test]
+fn decode_limits_bcode() {
+    let options = DecodeOptions {
+        max_depth: 2,
+        max_size: 32,
+        max_string_length: 4,
+        max_entries: 2,
+        ..Default::default()
+    };
+    let decode = |data: &[u8]| Decoder::with_options(data, options).decode();
+
+    assert!(decode(b"d1:ali1eee").is_ok());
+    assert_eq!(
+        decode(b"d1:alli1eeee"),
+        Err(Error::DepthExceeded(Position {
+            offset: 5,
+            path: vec![PathSegment::Key(b"a".to_vec()), PathSegment::Index(0)],
+        }))
+    );
+    assert_eq!(
+        decode(&[b'0'; 33]),
+        Err(Error::SizeExceeded(Position::new(32)))
+    );
+    assert_eq!(
+        decode(b"5:abcde"),
+        Err(Error::StringTooLong(Position::new(0)))
+    );
+    assert_eq!(
+        decode(b"li1ei2ei3ee"),
+        Err(Error::TooManyEntries(Position::new(0)))
+    );
+
+    // The default depth limit stops deeply nested data before it overflows the stack.
+    let nested = [b'l'; 1_000_000];
+    assert!(matches!(
+        bcode::decode(&nested, &mut 0_usize),
+        Err(Error::DepthExceeded(_))
+    ));
+}
@@ -0,0 +1,18 @@
+mod common;
+
+#[test]
+fn decode_ref_bcode() {
+    for (left, right) in common::get_comparison_data() {
+        let value_ref = bcode::decode_ref(left, &mut 0_usize).unwrap();
+
+        assert_eq!(bcode::ValueRef::from(&right), value_ref);
+        assert_eq!(bcode::Value::from(value_ref), right);
+    }
+
+    // Byte strings point into the original data.
+    let data = b"d6:pieces4:teste";
+    let value_ref = bcode::decode_ref(data, &mut 0_usize).unwrap();
+    let pieces: &[u8] = value_ref.get("pieces").unwrap().try_into().unwrap();
+
+    assert_eq!(pieces.as_ptr(), data[11..].as_ptr());
+}
@@ -0,0 +1,25 @@
+mod common;
+
+#[test]
+fn decode_spanned_bcode() {
+    for (left, right) in common::get_comparison_data() {
+        let spanned = bcode::decode_spanned(left, &mut 0_usize).unwrap();
+
+        assert_eq!(spanned.span, 0..left.len());
+        assert_eq!(
+            bcode::ValueRef::from(spanned),
+            bcode::ValueRef::from(&right)
+        );
+    }
+
+    // Spans are reported as they appear in the data, even if keys are unsorted.
+    let data = b"d1:bi2e1:ad1:zi0e1:ai1eee";
+    let spanned = bcode::Decoder::new(data, bcode::Mode::Lenient)
+        .decode_spanned()
+        .unwrap();
+    let inner = spanned.get("a").unwrap();
+
+    assert_eq!(inner.span, 10..24);
+    assert_eq!(inner.raw(data), b"d1:zi0e1:ai1ee");
+    assert_eq!(inner.get("z").unwrap().span, 14..17);
+}
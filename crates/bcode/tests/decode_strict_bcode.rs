@@ -0,0 +1,66 @@
+use bcode::{Decoder, Error, Mode, PathSegment, Position};
+
+#[test]
+fn decode_strict_bcode() {
+    let unsorted = b"d4:infod4:name1:a6:lengthi1eee";
+    let duplicate = b"d1:ai1e1:ai2ee";
+    let trailing = b"i42ei43e";
+    let leading_zero = b"li042ee";
+
+    let key_position = |offset, keys: &[&[u8]]| Position {
+        offset,
+        path: keys
+            .iter()
+            .map(|key| PathSegment::Key(key.to_vec()))
+            .collect(),
+    };
+
+    // Strict mode rejects non-canonical data.
+    let strict = |data: &[u8]| -> Result<Vec<Error>, Error> {
+        let mut decoder = Decoder::new(data, Mode::Strict);
+        decoder.decode()?;
+        decoder.finish()
+    };
+
+    assert_eq!(
+        strict(unsorted),
+        Err(Error::UnsortedKeys(key_position(17, &[b"info", b"length"])))
+    );
+    assert_eq!(
+        strict(duplicate),
+        Err(Error::DuplicateKey(key_position(7, &[b"a"])))
+    );
+    assert_eq!(strict(trailing), Err(Error::TrailingData(Position::new(4))));
+    assert_eq!(
+        strict(leading_zero),
+        Err(Error::LeadingZero(Position {
+            offset: 2,
+            path: vec![PathSegment::Index(0)],
+        }))
+    );
+
+    // Lenient mode accepts it, and reports the same issues.
+    for data in [&unsorted[..], duplicate, trailing, leading_zero] {
+        let mut decoder = Decoder::new(data, Mode::Lenient);
+        decoder.decode().unwrap();
+        let issues = decoder.finish().unwrap();
+
+        assert_eq!(issues, vec![strict(data).unwrap_err()]);
+    }
+
+    // Canonical data passes both.
+    assert_eq!(strict(b"d1:ai1e1:bi2ee"), Ok(vec![]));
+
+    // The decode functions are strict, but leave data after the value for the next call.
+    for data in [&b"i-0e"[..], b"i03e", b"03:abc", unsorted, duplicate] {
+        assert!(bcode::decode(data, &mut 0).is_err());
+        assert!(bcode::decode_ref(data, &mut 0).is_err());
+        assert!(bcode::decode_spanned(data, &mut 0).is_err());
+    }
+    let mut index = 0;
+    assert_eq!(
+        bcode::decode(trailing, &mut index),
+        Ok(bcode::Value::Integer(42))
+    );
+    assert_eq!(index, 4);
+}
@@ -0,0 +1,73 @@
+use bcode::{BDecode, BEncode, Value};
+use std::collections::BTreeMap;
+
+fn default_length() -> i64 {
+    42
+}
+
+#[derive(Debug, PartialEq, BEncode, BDecode)]
+struct Info {
+    name: String,
+    #[bcode(rename = "piece length", default = "default_length")]
+    piece_length: i64,
+    #[bcode(bytes)]
+    pieces: Vec<u8>,
+    private: Option<bool>,
+    #[bcode(default)]
+    files: Vec<String>,
+    #[bcode(skip)]
+    hash: Vec<u8>,
+    #[bcode(unknown)]
+    unknown: BTreeMap<Vec<u8>, Value>,
+}
+
+fn default_comment() -> Option<String> {
+    Some("none".to_string())
+}
+
+#[derive(Debug, PartialEq, BDecode)]
+struct Comment {
+    #[bcode(default = "default_comment")]
+    comment: Option<String>,
+}
+
+#[test]
+fn derive_bcode() {
+    let data = b"d4:name4:test6:pieces3:abc7:privatei1e6:source3:fooe";
+    let info = Info::bdecode(bcode::decode(data, &mut 0_usize).unwrap()).unwrap();
+
+    assert_eq!(
+        info,
+        Info {
+            name: "test".to_string(),
+            piece_length: 42,
+            pieces: b"abc".to_vec(),
+            private: Some(true),
+            files: vec![],
+            hash: vec![],
+            unknown: maplit::btreemap! {
+                b"source".to_vec() => Value::ByteString(b"foo".to_vec()),
+            },
+        }
+    );
+
+    // Unknown keys are written back, `None` fields are left out.
+    let info = Info {
+        private: None,
+        ..info
+    };
+    assert_eq!(
+        bcode::encode(info.bencode()).unwrap(),
+        b"d5:filesle4:name4:test12:piece lengthi42e6:pieces3:abc6:source3:fooe".to_vec()
+    );
+
+    // Missing keys without a default are an error.
+    let error = Info::bdecode(bcode::decode(b"d6:pieces0:e", &mut 0_usize).unwrap()).unwrap_err();
+    assert_eq!(error.to_string(), "Could not find \"name\" in map");
+
+    // Optional fields with a default are still wrapped when found.
+    let comment = Comment::bdecode(bcode::decode(b"d7:comment2:hie", &mut 0_usize).unwrap());
+    assert_eq!(comment.unwrap().comment.as_deref(), Some("hi"));
+    let comment = Comment::bdecode(bcode::decode(b"de", &mut 0_usize).unwrap());
+    assert_eq!(comment.unwrap().comment.as_deref(), Some("none"));
+}
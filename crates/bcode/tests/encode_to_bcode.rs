@@ -0,0 +1,26 @@
+mod common;
+
+#[test]
+fn encode_to_bcode() {
+    for (left, right) in common::get_comparison_data() {
+        let mut out = vec![];
+        bcode::encode_to(&right, &mut out).unwrap();
+
+        assert_eq!(out, left);
+        assert_eq!(bcode::encoded_len(&right), left.len());
+    }
+
+    for integer in [0, 9, 10, -1, -10, i64::MAX, i64::MIN] {
+        let value = bcode::Value::Integer(integer);
+        assert_eq!(
+            bcode::encoded_len(&value),
+            bcode::encode(value).unwrap().len()
+        );
+    }
+
+    // Works with any writer, including unsized ones.
+    let mut writer: Box<dyn std::io::Write> = Box::new(std::io::sink());
+    let value = bcode::Value::ByteString(vec![0; 1 << 16]);
+    bcode::encode_to(&value, &mut *writer).unwrap();
+    assert_eq!(bcode::encoded_len(&value), 6 + (1 << 16));
+}
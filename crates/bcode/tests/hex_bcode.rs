@@ -0,0 +1,13 @@
+use bcode::{from_hex, to_hex};
+
+#[test]
+fn hex_bcode() {
+    assert_eq!(to_hex(&[0x00, 0xab, 0x7f]), "00ab7f");
+    assert_eq!(from_hex("00aB7F").unwrap(), [0x00, 0xab, 0x7f]);
+    assert!(from_hex("").unwrap().is_empty());
+
+    // Only hex digits are accepted, in pairs.
+    for invalid in ["+f", "-1", "0x", "abc", "zz", "é1"] {
+        assert!(from_hex(invalid).is_err(), "{invalid}");
+    }
+}
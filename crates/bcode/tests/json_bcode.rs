@@ -0,0 +1,28 @@
+mod common;
+
+use bcode::Value;
+
+#[test]
+fn json_bcode() {
+    for (_, right) in common::get_comparison_data() {
+        assert_eq!(bcode::from_json(&bcode::to_json(&right)).unwrap(), right);
+    }
+
+    // Binary data, and text that looks like hex, are hex encoded.
+    let value = Value::Dictionary(maplit::btreemap! {
+        b"pieces".to_vec() => Value::ByteString(vec![0, 1, 255]),
+        b"comment".to_vec() => Value::ByteString(b"hex:ff".to_vec()),
+        b"\xff".to_vec() => Value::Integer(-1),
+    });
+    let json = bcode::to_json(&value);
+
+    assert_eq!(
+        json.to_string(),
+        r#"{"comment":"hex:6865783a6666","hex:ff":-1,"pieces":"hex:0001ff"}"#
+    );
+    assert_eq!(bcode::from_json(&json).unwrap(), value);
+
+    // JSON without a bcode equivalent is rejected.
+    let float: serde_json::Value = serde_json::from_str("1.5").unwrap();
+    assert!(bcode::from_json(&float).is_err());
+}
@@ -0,0 +1,25 @@
+use bcode::Value;
+
+#[test]
+fn pretty_bcode() {
+    let value = Value::Dictionary(maplit::btreemap! {
+        b"announce".to_vec() => Value::ByteString(b"http://localhost/announce".to_vec()),
+        b"info".to_vec() => Value::Dictionary(maplit::btreemap! {
+            b"length".to_vec() => Value::Integer(42),
+            b"pieces".to_vec() => Value::ByteString(vec![0xab; 40]),
+        }),
+        b"url-list".to_vec() => Value::List(vec![]),
+    });
+
+    assert_eq!(
+        value.pretty().max_bytes(Some(4)).to_string(),
+        r#"{
+  "announce": "http://localhost/announce",
+  "info": {
+    "length": 42,
+    "pieces": <40 bytes: abababab...>,
+  },
+  "url-list": [],
+}"#
+    );
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Info {
+    name: String,
+    #[serde(rename = "piece length")]
+    piece_length: i64,
+    #[serde(with = "serde_bytes")]
+    pieces: Vec<u8>,
+    private: Option<bool>,
+    md5sum: Option<String>,
+}
+
+#[test]
+fn serde_bcode() {
+    let info = Info {
+        name: "test".to_string(),
+        piece_length: 42,
+        pieces: vec![0, 1, 2, 255],
+        private: Some(true),
+        md5sum: None,
+    };
+
+    // Keys are sorted and `None` fields are left out.
+    let bytes = bcode::to_bytes(&info).unwrap();
+    assert_eq!(
+        bytes,
+        b"d4:name4:test12:piece lengthi42e6:pieces4:\x00\x01\x02\xff7:privatei1ee".to_vec()
+    );
+    assert_eq!(bcode::from_bytes::<Info>(&bytes).unwrap(), info);
+
+    for (left, right) in common::get_comparison_data() {
+        assert_eq!(bcode::from_bytes::<bcode::Value>(left).unwrap(), right);
+        assert_eq!(bcode::to_bytes(&right).unwrap(), left);
+    }
+}
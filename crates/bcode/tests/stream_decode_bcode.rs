@@ -0,0 +1,76 @@
+mod common;
+
+use bcode::{DecodeOptions, Error, Position, StreamDecoder};
+
+#[test]
+fn stream_decode_bcode() {
+    // Push all comparison data one byte at a time, values come out once complete.
+    let mut decoder = StreamDecoder::new();
+    let mut values = vec![];
+
+    for (left, _) in common::get_comparison_data() {
+        for byte in left {
+            assert_eq!(decoder.next_value(), Ok(None));
+            decoder.push(&[*byte]).unwrap();
+        }
+
+        values.push(decoder.next_value().unwrap().unwrap());
+    }
+
+    let expected: Vec<bcode::Value> = common::get_comparison_data()
+        .into_iter()
+        .map(|(_, right)| right)
+        .collect();
+    assert_eq!(values, expected);
+    assert_eq!(decoder.buffered(), 0);
+
+    // Several values in one chunk, followed by raw data.
+    decoder.push(b"i1e4:testd8:msg_typei1eeraw").unwrap();
+    assert_eq!(decoder.next_value(), Ok(Some(bcode::Value::Integer(1))));
+    assert_eq!(
+        decoder.next_value().unwrap().unwrap(),
+        b"test".to_vec().into()
+    );
+    assert!(decoder.next_value().unwrap().is_some());
+    assert_eq!(decoder.take_remaining(), b"raw".to_vec());
+
+    // Errors have offsets into the whole stream.
+    decoder.push(b"lx").unwrap();
+    assert_eq!(
+        decoder.next_value(),
+        Err(Error::UnexpectedByte(b'x', Position::new(76)))
+    );
+
+    // Data is refused before it's buffered, so the buffer can't grow past the limit.
+    let mut decoder = StreamDecoder::with_options(DecodeOptions {
+        max_size: 16,
+        ..Default::default()
+    });
+    decoder.push(b"100:").unwrap();
+    assert_eq!(decoder.next_value(), Ok(None));
+    assert_eq!(
+        decoder.push(&[0; 20]),
+        Err(Error::SizeExceeded(Position::new(16)))
+    );
+    assert_eq!(decoder.buffered(), 0);
+
+    // Too many entries are found while scanning, before the value is complete.
+    let mut decoder = StreamDecoder::with_options(DecodeOptions {
+        max_entries: 2,
+        ..Default::default()
+    });
+    decoder.push(b"d1:ali1ei2ee1:bi2e").unwrap();
+    assert_eq!(decoder.next_value(), Ok(None));
+    decoder.push(b"e").unwrap();
+    assert!(decoder.next_value().unwrap().is_some());
+    decoder.push(b"li1ei2ei3e").unwrap();
+    assert_eq!(
+        decoder.next_value(),
+        Err(Error::TooManyEntries(Position::new(19)))
+    );
+    decoder.push(b"d1:ai1e1:bi2e1:c").unwrap();
+    assert_eq!(
+        decoder.next_value(),
+        Err(Error::TooManyEntries(Position::new(29)))
+    );
+}
@@ -0,0 +1,92 @@
+use crate::field::{fields, Default};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::DeriveInput;
+
+/// Expand `#[derive(BDecode)]`.
+///
+/// # Arguments
+///
+/// * `input` - the struct deriving the trait.
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields = fields(input)?;
+
+    let lets = fields
+        .iter()
+        .filter(|field| !field.skip && !field.unknown)
+        .map(|field| {
+            let ident = field.ident;
+            let key = &field.key;
+            let ty = field.option.unwrap_or(field.ty);
+
+            let decode = match &field.with {
+                Some(with) => quote!(#with::bdecode(value)),
+                None => quote!(<#ty as ::bcode::BDecode>::bdecode(value)),
+            };
+            let decode = quote! {
+                #decode.map_err(|e| ::bcode::Error::Custom(::std::format!("Invalid \"{}\": {}", #key, e)))?
+            };
+
+            let found = match field.option {
+                Some(_) => quote!(::std::option::Option::Some(#decode)),
+                None => decode,
+            };
+            let missing = match (&field.option, &field.default) {
+                (Some(_), None) => quote!(::std::option::Option::None),
+                (_, Some(Default::Trait)) => quote!(::std::default::Default::default()),
+                (_, Some(Default::Path(path))) => quote!(#path()),
+                (None, None) => {
+                    quote! {
+                        return ::std::result::Result::Err(::bcode::Error::Custom(
+                            ::std::format!("Could not find \"{}\" in map", #key),
+                        ))
+                    }
+                }
+            };
+
+            quote! {
+                let #ident = match dictionary.remove(#key.as_bytes()) {
+                    ::std::option::Option::Some(value) => #found,
+                    ::std::option::Option::None => #missing,
+                };
+            }
+        })
+        .collect::<Vec<TokenStream>>();
+
+    let inits = fields
+        .iter()
+        .map(|field| {
+            let ident = field.ident;
+
+            if field.skip {
+                quote!(#ident: ::std::default::Default::default())
+            } else if field.unknown {
+                quote!(#ident: dictionary)
+            } else {
+                quote!(#ident)
+            }
+        })
+        .collect::<Vec<TokenStream>>();
+
+    Ok(quote! {
+        impl #impl_generics ::bcode::BDecode for #name #ty_generics #where_clause {
+            #[allow(unused_mut)]
+            fn bdecode(value: ::bcode::Value) -> ::std::result::Result<Self, ::bcode::Error> {
+                let mut dictionary = match value {
+                    ::bcode::Value::Dictionary(dictionary) => dictionary,
+                    _ => {
+                        return ::std::result::Result::Err(::bcode::Error::Custom(
+                            ::std::string::String::from("Value is not a dictionary"),
+                        ))
+                    }
+                };
+
+                #(#lets)*
+
+                ::std::result::Result::Ok(#name { #(#inits),* })
+            }
+        }
+    })
+}
@@ -0,0 +1,60 @@
+use crate::field::fields;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::DeriveInput;
+
+/// Expand `#[derive(BEncode)]`.
+///
+/// # Arguments
+///
+/// * `input` - the struct deriving the trait.
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let inserts = fields(input)?
+        .into_iter()
+        .filter(|field| !field.skip)
+        .map(|field| {
+            let ident = field.ident;
+            let key = &field.key;
+
+            let encode = |value: TokenStream| match &field.with {
+                Some(with) => quote!(#with::bencode(#value)),
+                None => quote!(::bcode::BEncode::bencode(#value)),
+            };
+
+            if field.unknown {
+                quote! {
+                    for (key, value) in &self.#ident {
+                        dictionary.entry(key.clone()).or_insert_with(|| value.clone());
+                    }
+                }
+            } else if field.option.is_some() {
+                let value = encode(quote!(value));
+
+                quote! {
+                    if let ::std::option::Option::Some(value) = &self.#ident {
+                        dictionary.insert(#key.as_bytes().to_vec(), #value);
+                    }
+                }
+            } else {
+                let value = encode(quote!(&self.#ident));
+
+                quote! {
+                    dictionary.insert(#key.as_bytes().to_vec(), #value);
+                }
+            }
+        })
+        .collect::<Vec<TokenStream>>();
+
+    Ok(quote! {
+        impl #impl_generics ::bcode::BEncode for #name #ty_generics #where_clause {
+            fn bencode(&self) -> ::bcode::Value {
+                let mut dictionary = ::std::collections::BTreeMap::new();
+                #(#inserts)*
+                ::bcode::Value::Dictionary(dictionary)
+            }
+        }
+    })
+}
@@ -0,0 +1,125 @@
+use syn::{Data, DeriveInput, Fields, GenericArgument, Ident, LitStr, Path, PathArguments, Type};
+
+/// A struct field, with its `#[bcode(...)]` attributes.
+pub struct Field<'a> {
+    pub ident: &'a Ident,
+    pub ty: &'a Type,
+    /// Dictionary key of the field.
+    pub key: String,
+    /// Inner type, if the field is an `Option`.
+    pub option: Option<&'a Type>,
+    pub default: Option<Default>,
+    pub with: Option<Path>,
+    pub skip: bool,
+    pub unknown: bool,
+}
+
+/// What to use when a key is missing.
+pub enum Default {
+    Trait,
+    Path(Path),
+}
+
+/// Get the fields of a struct with named fields.
+///
+/// # Arguments
+///
+/// * `input` - the struct deriving a trait.
+pub fn fields(input: &DeriveInput) -> syn::Result<Vec<Field<'_>>> {
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => named,
+            _ => return Err(error(input, "only structs with named fields are supported")),
+        },
+        _ => return Err(error(input, "only structs are supported")),
+    };
+
+    let fields = named
+        .named
+        .iter()
+        .map(parse_field)
+        .collect::<syn::Result<Vec<Field>>>()?;
+
+    if fields.iter().filter(|field| field.unknown).count() > 1 {
+        return Err(error(input, "only one field can be `unknown`"));
+    }
+
+    Ok(fields)
+}
+
+/// Parse a field and its attributes.
+///
+/// # Arguments
+///
+/// * `field` - field to parse.
+fn parse_field(field: &syn::Field) -> syn::Result<Field<'_>> {
+    let ident = field.ident.as_ref().unwrap();
+    let mut out = Field {
+        ident,
+        ty: &field.ty,
+        key: ident.to_string(),
+        option: option_inner(&field.ty),
+        default: None,
+        with: None,
+        skip: false,
+        unknown: false,
+    };
+
+    for attr in field.attrs.iter().filter(|x| x.path().is_ident("bcode")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                out.key = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("default") {
+                out.default = if meta.input.peek(syn::Token![=]) {
+                    Some(Default::Path(meta.value()?.parse::<LitStr>()?.parse()?))
+                } else {
+                    Some(Default::Trait)
+                };
+            } else if meta.path.is_ident("with") {
+                out.with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("bytes") {
+                out.with = Some(syn::parse_quote!(::bcode::bytes));
+            } else if meta.path.is_ident("skip") {
+                out.skip = true;
+            } else if meta.path.is_ident("unknown") {
+                out.unknown = true;
+            } else {
+                return Err(meta.error("unsupported bcode attribute"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(out)
+}
+
+/// Returns `T` if the type is `Option<T>`.
+///
+/// # Arguments
+///
+/// * `ty` - type to check.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+
+    match (segment.ident == "Option", arguments.args.first()) {
+        (true, Some(GenericArgument::Type(inner))) if arguments.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+/// Create an error pointing at the struct.
+///
+/// # Arguments
+///
+/// * `input` - the struct deriving a trait.
+/// * `message` - error message.
+fn error(input: &DeriveInput, message: &str) -> syn::Error {
+    syn::Error::new_spanned(&input.ident, message)
+}
@@ -0,0 +1,42 @@
+//! # Bcode derive
+//!
+//! `bcode_derive` provides `#[derive(BEncode, BDecode)]` for structs with named fields,
+//! converting them to and from a bencoded dictionary. Use it trough the `bcode` crate.
+//!
+//! Fields can be customized with `#[bcode(...)]`:
+//!
+//! * `rename = "key"` - use another dictionary key than the field name.
+//! * `default` / `default = "path"` - use `Default::default()` (or `path()`) if the key is missing.
+//! * `bytes` - read and write a `Vec<u8>` as a byte string instead of a list.
+//! * `with = "path"` - use `path::bencode` and `path::bdecode` for the field.
+//! * `skip` - leave the field out, and use `Default::default()` when decoding.
+//! * `unknown` - collect keys without a field into this `BTreeMap<Vec<u8>, bcode::Value>`.
+//!
+//! Fields of type `Option<T>` are left out when `None`, and are `None` if the key is missing.
+
+mod decode;
+mod encode;
+mod field;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Derive `bcode::BEncode`.
+#[proc_macro_derive(BEncode, attributes(bcode))]
+pub fn derive_bencode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    encode::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `bcode::BDecode`.
+#[proc_macro_derive(BDecode, attributes(bcode))]
+pub fn derive_bdecode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    decode::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
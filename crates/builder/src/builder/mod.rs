@@ -1,5 +1,7 @@
+use crate::Storage;
 use anyhow::{anyhow, Result};
 use rand::prelude::IteratorRandom;
+use std::collections::HashSet;
 
 /// Block of a piece.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -20,6 +22,20 @@ pub struct Builder {
 
     pub piece_amount: usize,
     pub piece_length: usize,
+    pub block_size: usize,
+    /// Size of all pieces together, the last piece is shorter unless it's a multiple of `piece_length`.
+    pub total_length: usize,
+
+    /// Finished bytes in each piece, counting each block once.
+    finished_bytes: Vec<usize>,
+    /// Index and begin of every block added with `add_finished_block`.
+    seen: HashSet<(usize, usize)>,
+    /// Bytes not finished yet.
+    left: usize,
+    /// Files finished pieces are verified and written to, and served from.
+    storage: Option<Storage>,
+    /// Indices of finished pieces, in the order they were finished.
+    completed: Vec<usize>,
 }
 
 impl Builder {
@@ -67,9 +83,79 @@ impl Builder {
             missing,
             piece_amount,
             piece_length,
+            block_size,
+            total_length: piece_amount * piece_length,
+            finished_bytes: vec![0; piece_amount],
+            seen: HashSet::new(),
+            left: piece_amount * piece_length,
+            storage: None,
+            completed: vec![],
         }
     }
 
+    /// Set the size of all pieces together, shortening the last piece to fit.
+    ///
+    /// Blocks past the end are dropped, so they're never requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `total_length` - size of the torrent in bytes, with its pieces laid out back to back.
+    pub fn with_total_length(mut self, total_length: usize) -> Builder {
+        self.total_length = total_length;
+        self.left = total_length.saturating_sub(self.finished_bytes.iter().sum());
+
+        let last_index = self.piece_amount.saturating_sub(1);
+        let last_length = self.get_piece_size(last_index);
+        self.missing.retain_mut(|block| {
+            if block.index == last_index {
+                block.data.truncate(last_length.saturating_sub(block.begin));
+            }
+
+            !block.data.is_empty()
+        });
+
+        self
+    }
+
+    /// Verify finished pieces against their hash and store them on disk, instead of in memory.
+    ///
+    /// Pieces already on disk are checked and finished, so they're seeded right away.
+    /// Call this after `with_total_length`.
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - files of the torrent.
+    pub fn with_storage(mut self, storage: Storage) -> Builder {
+        for index in 0..self.piece_amount {
+            let size = self.get_piece_size(index);
+            let verified = storage
+                .read((index * self.piece_length) as u64, size)
+                .is_ok_and(|data| storage.verify(index, &data));
+
+            if verified {
+                self.left -= size - self.finished_bytes[index];
+                self.finished_bytes[index] = size;
+                self.missing.retain(|block| block.index != index);
+                self.finished.retain(|block| block.index != index);
+                self.completed.push(index);
+            }
+        }
+
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Returns the length of a piece, which is shorter for the last one.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - index of the piece.
+    pub fn get_piece_size(&self, index: usize) -> usize {
+        self.total_length
+            .saturating_sub(index * self.piece_length)
+            .min(self.piece_length)
+    }
+
     pub fn assemble_piece(&self, index: usize) -> Vec<Option<u8>> {
         let mut out = vec![None; self.piece_length];
 
@@ -94,8 +180,51 @@ impl Builder {
         out
     }
 
+    /// Returns whether every block of a piece is finished.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - index of the piece.
+    pub fn is_piece_finished(&self, index: usize) -> bool {
+        self.finished_bytes
+            .get(index)
+            .is_some_and(|bytes| *bytes >= self.get_piece_size(index))
+    }
+
+    /// Returns the indices of finished pieces, in the order they were finished.
+    pub fn get_completed(&self) -> &[usize] {
+        &self.completed
+    }
+
+    /// Returns a bitfield of the pieces with every block finished.
+    pub fn get_bitfield(&self) -> Vec<u8> {
+        let mut bitfield = vec![0; self.piece_amount.div_ceil(8)];
+
+        for index in (0..self.piece_amount).filter(|x| self.is_piece_finished(*x)) {
+            bitfield[index / 8] |= 1 << (7 - index % 8);
+        }
+
+        bitfield
+    }
+
+    /// Returns the amount of bytes not finished yet, of blocks added with `add_finished_block`.
+    pub fn get_left(&self) -> usize {
+        self.left
+    }
+
     // TODO: Write tests
     pub fn get_finished_block(&self, index: usize, begin: usize, length: usize) -> Result<Block> {
+        // Stored pieces are read back from disk.
+        if let Some(storage) = &self.storage {
+            let end = begin.saturating_add(length);
+            if !self.is_piece_finished(index) || end > self.get_piece_size(index) {
+                return Err(anyhow!("Missing block"));
+            }
+
+            let data = storage.read((index * self.piece_length + begin) as u64, length)?;
+            return Ok(Block { index, begin, data });
+        }
+
         if let Some(piece) = self.assemble_piece(index).get(begin..begin + length) {
             let data = piece
                 .iter()
@@ -150,9 +279,63 @@ impl Builder {
         Ok(())
     }
 
-    pub fn add_finished_block(&mut self, block: Block) -> Result<()> {
+    /// Add a downloaded block, and returns whether it finished its piece.
+    ///
+    /// With storage, a finished piece is only kept if it matches its hash,
+    /// otherwise its blocks are missing again.
+    ///
+    /// # Arguments
+    ///
+    /// * `block` - downloaded block.
+    pub fn add_finished_block(&mut self, block: Block) -> Result<bool> {
+        let index = block.index;
+        if index >= self.piece_amount || !self.seen.insert((index, block.begin)) {
+            return Ok(false);
+        }
+
+        // Never count bytes past the end of the piece.
+        let size = self.get_piece_size(index);
+        let bytes = block.data.len().min(size.saturating_sub(block.begin));
+
+        self.finished_bytes[index] += bytes;
+        self.left = self.left.saturating_sub(bytes);
         self.finished.push(block);
 
-        Ok(())
+        if !self.is_piece_finished(index) {
+            return Ok(false);
+        }
+
+        let Some(storage) = &self.storage else {
+            self.completed.push(index);
+            return Ok(true);
+        };
+
+        let data = self.assemble_piece(index)[..size]
+            .iter()
+            .copied()
+            .collect::<Option<Vec<u8>>>();
+        self.finished.retain(|block| block.index != index);
+
+        match data {
+            Some(data) if storage.verify(index, &data) => {
+                storage.write((index * self.piece_length) as u64, &data)?;
+                self.completed.push(index);
+
+                Ok(true)
+            }
+            _ => {
+                self.left += self.finished_bytes[index];
+                self.finished_bytes[index] = 0;
+                self.seen.retain(|(x, _)| *x != index);
+                self.missing
+                    .extend((0..size).step_by(self.block_size).map(|begin| Block {
+                        index,
+                        begin,
+                        data: vec![0; self.block_size.min(size - begin)],
+                    }));
+
+                Ok(false)
+            }
+        }
     }
 }
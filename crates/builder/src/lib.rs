@@ -1,3 +1,5 @@
 mod builder;
+mod storage;
 
 pub use crate::builder::{Block, Builder};
+pub use crate::storage::Storage;
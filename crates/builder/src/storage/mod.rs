@@ -0,0 +1,261 @@
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use torrent::merkle::BLOCK_SIZE;
+use torrent::{Torrent, TorrentInfo};
+
+/// Files of a torrent on disk, read and written by their offset in the pieces.
+#[derive(Debug, Clone)]
+pub struct Storage {
+    /// Files with data, padding files aren't stored.
+    files: Vec<StoredFile>,
+    /// Hash of every piece.
+    hashes: Vec<PieceHash>,
+}
+
+/// File on disk, and where its data is in the pieces.
+#[derive(Debug, Clone)]
+struct StoredFile {
+    path: PathBuf,
+    offset: u64,
+    length: u64,
+}
+
+/// Hash to verify a piece with.
+#[derive(Debug, Clone)]
+enum PieceHash {
+    /// SHA-1 of the whole piece, padding included (v1).
+    Sha1(Vec<u8>),
+    /// Merkle root of the file bytes in the piece, padded to `leaves` blocks (v2 piece layer).
+    Layer {
+        hash: Vec<u8>,
+        length: usize,
+        leaves: usize,
+    },
+    /// `pieces root` of a file no longer than a piece (v2).
+    Root { hash: Vec<u8>, length: usize },
+    /// Unknown, since the piece layers are missing.
+    Unknown,
+}
+
+impl Storage {
+    /// Lay out the files of a torrent in a directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `torrent` - torrent with the files and piece hashes.
+    /// * `dir` - directory to store the files in.
+    pub fn new(torrent: &Torrent, dir: impl AsRef<Path>) -> Result<Storage> {
+        let mut files = vec![];
+        let mut offset = 0;
+
+        for file in torrent.get_layout() {
+            if !file.padding {
+                files.push(StoredFile {
+                    path: safe_path(dir.as_ref(), &file.path)?,
+                    offset,
+                    length: file.length,
+                });
+            }
+
+            offset += file.length;
+        }
+
+        Ok(Storage {
+            files,
+            hashes: piece_hashes(torrent),
+        })
+    }
+
+    /// Read bytes from the files, padding reads as zeros.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - byte offset in the pieces.
+    /// * `length` - amount of bytes to read.
+    pub fn read(&self, offset: u64, length: usize) -> Result<Vec<u8>> {
+        let mut out = vec![0; length];
+
+        for (file, start, range) in self.overlapping(offset, length) {
+            let mut handle = File::open(&file.path)?;
+            handle.seek(SeekFrom::Start(start))?;
+            handle.read_exact(&mut out[range])?;
+        }
+
+        Ok(out)
+    }
+
+    /// Write bytes to the files, creating them if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - byte offset in the pieces.
+    /// * `data` - bytes to write.
+    pub fn write(&self, offset: u64, data: &[u8]) -> Result<()> {
+        for (file, start, range) in self.overlapping(offset, data.len()) {
+            if let Some(parent) = file.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let mut handle = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&file.path)?;
+            handle.seek(SeekFrom::Start(start))?;
+            handle.write_all(&data[range])?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether a piece matches its hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - index of the piece.
+    /// * `data` - the whole piece.
+    pub fn verify(&self, index: usize, data: &[u8]) -> bool {
+        match self.hashes.get(index) {
+            Some(PieceHash::Sha1(hash)) => sha1_smol::Sha1::from(data).digest().bytes() == **hash,
+            Some(PieceHash::Layer {
+                hash,
+                length,
+                leaves,
+            }) => data
+                .get(..*length)
+                .is_some_and(|data| merkle_root(data, Some(*leaves)) == **hash),
+            Some(PieceHash::Root { hash, length }) => data
+                .get(..*length)
+                .is_some_and(|data| merkle_root(data, None) == **hash),
+            Some(PieceHash::Unknown) | None => false,
+        }
+    }
+
+    /// Returns the files overlapping a range of the pieces, with the offset to start at in
+    /// the file and the part of the range it covers.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - byte offset in the pieces.
+    /// * `length` - length of the range.
+    fn overlapping(
+        &self,
+        offset: u64,
+        length: usize,
+    ) -> impl Iterator<Item = (&StoredFile, u64, std::ops::Range<usize>)> {
+        let end = offset + length as u64;
+
+        self.files
+            .iter()
+            .filter(move |file| file.offset < end && offset < file.offset + file.length)
+            .map(move |file| {
+                let start = offset.max(file.offset);
+                let stop = end.min(file.offset + file.length);
+
+                (
+                    file,
+                    start - file.offset,
+                    (start - offset) as usize..(stop - offset) as usize,
+                )
+            })
+    }
+}
+
+/// Join the path of a file to the download directory, refusing paths that would leave it.
+///
+/// # Arguments
+///
+/// * `dir` - download directory.
+/// * `components` - path components from the torrent.
+fn safe_path(dir: &Path, components: &[String]) -> Result<PathBuf> {
+    let mut path = dir.to_path_buf();
+
+    for component in components {
+        let mut parts = Path::new(component).components();
+
+        match (parts.next(), parts.next()) {
+            (Some(Component::Normal(part)), None) => path.push(part),
+            _ => return Err(anyhow!("Invalid path component {component:?}")),
+        }
+    }
+
+    Ok(path)
+}
+
+/// Returns the hash of every piece of a torrent.
+///
+/// # Arguments
+///
+/// * `torrent` - torrent to get the hashes of.
+fn piece_hashes(torrent: &Torrent) -> Vec<PieceHash> {
+    let v1_pieces = match &torrent.info {
+        TorrentInfo::SingleFileInfo(info) => &info.pieces,
+        TorrentInfo::MultiFileInfo(info) => &info.pieces,
+        TorrentInfo::HybridInfo(info) => &info.pieces,
+        TorrentInfo::V2Info(_) => {
+            let piece_length = torrent.get_piece_length() as u64;
+            let mut hashes = vec![];
+
+            for (_, file) in torrent.get_v2_files().unwrap_or_default() {
+                let length = file.length as u64;
+                let pieces_root = file.pieces_root.clone().unwrap_or_default();
+
+                if length == 0 {
+                    continue;
+                } else if length <= piece_length {
+                    hashes.push(PieceHash::Root {
+                        hash: pieces_root,
+                        length: length as usize,
+                    });
+                    continue;
+                }
+
+                let layer = torrent
+                    .piece_layers
+                    .as_ref()
+                    .and_then(|layers| layers.get(&pieces_root));
+
+                for piece in 0..length.div_ceil(piece_length) {
+                    let start = piece * piece_length;
+                    hashes.push(match layer.and_then(|x| x.chunks(32).nth(piece as usize)) {
+                        Some(hash) => PieceHash::Layer {
+                            hash: hash.to_vec(),
+                            length: (length - start).min(piece_length) as usize,
+                            leaves: (piece_length / BLOCK_SIZE) as usize,
+                        },
+                        None => PieceHash::Unknown,
+                    });
+                }
+            }
+
+            return hashes;
+        }
+    };
+
+    v1_pieces
+        .chunks(20)
+        .map(|hash| PieceHash::Sha1(hash.to_vec()))
+        .collect()
+}
+
+/// Returns the merkle root of data, from the SHA-256 of its 16 KiB blocks.
+///
+/// # Arguments
+///
+/// * `data` - data to hash.
+/// * `leaves` - amount of leaves to pad to, otherwise the next power of two.
+fn merkle_root(data: &[u8], leaves: Option<usize>) -> [u8; 32] {
+    let mut layer = data
+        .chunks(BLOCK_SIZE as usize)
+        .flat_map(|block| Sha256::digest(block).to_vec())
+        .collect::<Vec<_>>();
+
+    if let Some(leaves) = leaves {
+        layer.resize(leaves * 32, 0);
+    }
+
+    torrent::merkle::root(&layer, [0; 32])
+}
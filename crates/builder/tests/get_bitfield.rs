@@ -0,0 +1,25 @@
+mod common;
+
+use builder::Builder;
+
+#[test]
+fn get_bitfield() {
+    let mut builder = Builder::new(10, 1 << 15, 1 << 14);
+    assert_eq!(builder.get_bitfield(), [0, 0]);
+
+    // A piece is only finished once all of its blocks are.
+    let blocks = builder.missing.clone();
+    for block in blocks.iter().filter(|x| x.index == 1) {
+        builder.add_finished_block(block.clone()).unwrap();
+    }
+    builder.add_finished_block(blocks[0].clone()).unwrap();
+    builder.add_finished_block(blocks[0].clone()).unwrap();
+    assert_eq!(builder.get_bitfield(), [0b0100_0000, 0]);
+    assert_eq!(builder.get_left(), 9 * (1 << 15) - (1 << 14));
+
+    for block in blocks {
+        builder.add_finished_block(block).unwrap();
+    }
+    assert_eq!(builder.get_bitfield(), [0xff, 0xc0]);
+    assert_eq!(builder.get_left(), 0);
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use builder::Builder;
+
+#[test]
+fn get_left() {
+    // Two and a half pieces, the last one is a single short block.
+    let mut builder = Builder::new(3, 1 << 15, 1 << 14).with_total_length(5 << 14);
+    assert_eq!(builder.get_left(), 5 << 14);
+    assert_eq!(builder.get_piece_size(2), 1 << 14);
+    assert_eq!(builder.missing.len(), 5);
+
+    let last = builder.missing.iter().find(|x| x.index == 2).unwrap();
+    assert_eq!(last.data.len(), 1 << 14);
+
+    // Every byte counts once, and the download ends at exactly 0 left.
+    let blocks = std::mem::take(&mut builder.missing);
+    builder.add_finished_block(blocks[0].clone()).unwrap();
+    builder.add_finished_block(blocks[0].clone()).unwrap();
+    assert_eq!(builder.get_left(), 4 << 14);
+
+    for block in blocks {
+        builder.add_finished_block(block).unwrap();
+    }
+    assert_eq!(builder.get_left(), 0);
+
+    // The short last piece is finished too, so the whole torrent can be seeded.
+    assert_eq!(builder.get_bitfield(), [0b1110_0000]);
+}
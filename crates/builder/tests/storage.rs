@@ -0,0 +1,121 @@
+mod common;
+
+use builder::{Builder, Storage};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use torrent::{FileTreeFile, FileTreeNode, Torrent, TorrentBuilder, TorrentInfo, V2Info};
+
+#[test]
+fn storage() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("storage");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("seed/release")).unwrap();
+
+    // Two files, with a piece crossing from one into the other.
+    let a = (0..20_000u32).map(|x| x as u8).collect::<Vec<_>>();
+    let b = vec![7; 30_000];
+    std::fs::write(root.join("seed/release/a.bin"), &a).unwrap();
+    std::fs::write(root.join("seed/release/b.bin"), &b).unwrap();
+    let data = [a.as_slice(), b.as_slice()].concat();
+
+    let torrent = TorrentBuilder::new(root.join("seed/release"))
+        .piece_length(1 << 14)
+        .build()
+        .unwrap();
+    let new_builder = |dir: &Path| {
+        Builder::new(torrent.get_piece_amount(), 1 << 14, 1 << 14)
+            .with_total_length(torrent.get_total_length() as usize)
+            .with_storage(Storage::new(&torrent, dir).unwrap())
+    };
+
+    // Files already on disk are seeded from it.
+    let seed = new_builder(&root.join("seed"));
+    assert_eq!(seed.get_left(), 0);
+    assert_eq!(seed.get_completed(), [0, 1, 2, 3]);
+    assert_eq!(
+        seed.get_finished_block(1, 100, 1000).unwrap().data,
+        data[(1 << 14) + 100..(1 << 14) + 1100]
+    );
+    assert!(seed.get_finished_block(3, 0, 1 << 14).is_err());
+
+    // Pieces not matching their hash are downloaded again.
+    let mut leecher = new_builder(&root.join("leech"));
+    assert_eq!(leecher.get_left(), 50_000);
+    let mut block = leecher.take_missing_relevant_block(&[0b1000_0000]).unwrap();
+    block.data = vec![0; block.data.len()];
+    assert!(!leecher.add_finished_block(block).unwrap());
+    assert_eq!(leecher.get_left(), 50_000);
+    assert_eq!(leecher.missing.len(), 4);
+
+    // Verified pieces are written to disk, and read back after a restart.
+    for mut block in std::mem::take(&mut leecher.missing) {
+        let start = block.index * (1 << 14) + block.begin;
+        block.data = data[start..start + block.data.len()].to_vec();
+        assert!(leecher.add_finished_block(block).unwrap());
+    }
+    assert_eq!(leecher.get_left(), 0);
+    assert!(leecher.finished.is_empty());
+    assert_eq!(std::fs::read(root.join("leech/release/b.bin")).unwrap(), b);
+    assert_eq!(new_builder(&root.join("leech")).get_left(), 0);
+
+    // Paths leaving the download directory are refused.
+    let mut evil = TorrentBuilder::new(root.join("seed/release/a.bin"))
+        .build()
+        .unwrap();
+    if let torrent::TorrentInfo::SingleFileInfo(info) = &mut evil.info {
+        info.name = "..".to_string();
+    }
+    assert!(Storage::new(&evil, root.join("leech")).is_err());
+
+    // Pieces of v2 torrents are verified against the piece layers.
+    let data = (0..70_000u32).map(|x| (x % 251) as u8).collect::<Vec<_>>();
+    let hash = |data: &[u8]| Sha256::digest(data).to_vec();
+    let mut leaves = data.chunks(1 << 14).map(hash).collect::<Vec<_>>();
+    leaves.resize(8, vec![0; 32]);
+    let pieces = leaves
+        .chunks(2)
+        .map(|pair| hash(&pair.concat()))
+        .collect::<Vec<_>>();
+    let pieces_root = hash(
+        &[
+            hash(&[pieces[0].clone(), pieces[1].clone()].concat()),
+            hash(&[pieces[2].clone(), pieces[3].clone()].concat()),
+        ]
+        .concat(),
+    );
+
+    let v2 = Torrent {
+        info: TorrentInfo::V2Info(V2Info {
+            meta_version: 2,
+            piece_length: 1 << 15,
+            private: None,
+            name: "v2.bin".to_string(),
+            file_tree: BTreeMap::from([(
+                "v2.bin".to_string(),
+                FileTreeNode::File(FileTreeFile {
+                    length: data.len() as i64,
+                    pieces_root: Some(pieces_root.clone()),
+                    extra: BTreeMap::new(),
+                }),
+            )]),
+            extra: BTreeMap::new(),
+        }),
+        announce: None,
+        announce_list: None,
+        creation_date: None,
+        comment: None,
+        created_by: None,
+        encoding: None,
+        piece_layers: Some(BTreeMap::from([(pieces_root, pieces[..3].concat())])),
+        extra: BTreeMap::new(),
+        info_hash: vec![],
+        info_hash_v2: None,
+        info_bytes: vec![],
+    };
+    let storage = Storage::new(&v2, root.join("v2")).unwrap();
+    for (index, piece) in data.chunks(1 << 15).enumerate() {
+        assert!(storage.verify(index, piece));
+        assert!(!storage.verify(index, &vec![0; piece.len()]));
+    }
+}
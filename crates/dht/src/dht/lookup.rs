@@ -0,0 +1,144 @@
+use super::*;
+use crate::NodeInfo;
+use async_std::net::ToSocketAddrs;
+use futures::future::join_all;
+use std::collections::BTreeSet;
+
+/// Amount of nodes queried at the same time during a lookup.
+const ALPHA: usize = 3;
+
+/// Result of an iterative lookup.
+#[derive(Debug, Default)]
+struct Lookup {
+    /// Closest responding nodes, with their tokens, closest first.
+    nodes: Vec<(NodeInfo, Option<Vec<u8>>)>,
+    /// Peers found, if looking for peers.
+    peers: Vec<SocketAddr>,
+}
+
+impl Dht {
+    /// Join the DHT through the bootstrap nodes, then fill the routing table with nodes close to us.
+    ///
+    /// Nodes already in the routing table (e.g. loaded from `config.state`) are used too.
+    pub async fn bootstrap(&self) -> Result<()> {
+        let id = self.id();
+        let mut addrs = vec![];
+
+        for host in &self.config.bootstrap {
+            if let Ok(resolved) = host.to_socket_addrs().await {
+                addrs.extend(resolved.filter(SocketAddr::is_ipv4));
+            }
+        }
+
+        join_all(addrs.into_iter().map(|addr| self.find_node(addr, id))).await;
+        self.lookup(id, false).await;
+
+        if self.routing.lock().unwrap().is_empty() {
+            return Err(anyhow!("Could not reach any DHT node"));
+        }
+
+        Ok(())
+    }
+
+    /// Find peers of a torrent.
+    ///
+    /// # Arguments
+    ///
+    /// * `info_hash` - info hash of the torrent.
+    pub async fn lookup_peers(&self, info_hash: &[u8]) -> Result<Vec<SocketAddr>> {
+        let lookup = self.lookup(NodeId::from_bytes(info_hash)?, true).await;
+
+        Ok(lookup.peers)
+    }
+
+    /// Find peers of a torrent, and announce ourselves to the closest nodes.
+    ///
+    /// Returns the peers found.
+    ///
+    /// # Arguments
+    ///
+    /// * `info_hash` - info hash of the torrent.
+    /// * `port` - port peers can connect to, `None` to use the port of the DHT socket.
+    pub async fn announce(&self, info_hash: &[u8], port: Option<u16>) -> Result<Vec<SocketAddr>> {
+        let info_hash = NodeId::from_bytes(info_hash)?;
+        let lookup = self.lookup(info_hash, true).await;
+
+        let announced = join_all(lookup.nodes.into_iter().filter_map(|(node, token)| {
+            Some(self.announce_peer(node.addr, info_hash, port, token?))
+        }))
+        .await;
+
+        if !announced.iter().any(Result::is_ok) {
+            return Err(anyhow!("Could not announce to any DHT node"));
+        }
+
+        Ok(lookup.peers)
+    }
+
+    /// Iteratively query the nodes closest to a target, until no closer nodes are found.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - id to find nodes close to.
+    /// * `get_peers` - send `get_peers` instead of `find_node`, collecting peers and tokens.
+    async fn lookup(&self, target: NodeId, get_peers: bool) -> Lookup {
+        let mut candidates: BTreeMap<NodeId, NodeInfo> = self
+            .routing
+            .lock()
+            .unwrap()
+            .closest(&target, K)
+            .into_iter()
+            .map(|node| (node.id.distance(&target), node))
+            .collect();
+        let mut queried = BTreeSet::new();
+        let mut responded = BTreeMap::new();
+        let mut peers = BTreeSet::new();
+
+        loop {
+            let batch: Vec<NodeInfo> = candidates
+                .values()
+                .take(K)
+                .filter(|node| !queried.contains(&node.addr))
+                .take(ALPHA)
+                .copied()
+                .collect();
+
+            if batch.is_empty() {
+                break;
+            }
+
+            let responses = join_all(batch.iter().map(|node| async move {
+                let response = match get_peers {
+                    true => self.get_peers(node.addr, target).await,
+                    false => self.find_node(node.addr, target).await,
+                };
+
+                (*node, response)
+            }))
+            .await;
+
+            for (node, response) in responses {
+                queried.insert(node.addr);
+
+                let Ok(response) = response else {
+                    candidates.remove(&node.id.distance(&target));
+                    continue;
+                };
+
+                peers.extend(response.values);
+                responded.insert(node.id.distance(&target), (node, response.token));
+
+                for found in response.nodes {
+                    if found.id != self.id() && !queried.contains(&found.addr) {
+                        candidates.insert(found.id.distance(&target), found);
+                    }
+                }
+            }
+        }
+
+        Lookup {
+            nodes: responded.into_values().take(K).collect(),
+            peers: peers.into_iter().collect(),
+        }
+    }
+}
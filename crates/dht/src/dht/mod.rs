@@ -0,0 +1,369 @@
+mod lookup;
+mod rpc;
+
+use crate::krpc::{Body, Message, Query, Response};
+use crate::{NodeId, RoutingTable, TokenManager, K};
+use anyhow::{anyhow, Result};
+use async_std::future::timeout;
+use async_std::net::UdpSocket;
+use futures::channel::oneshot;
+use futures::future::{self, Either};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+/// Peers announced to us are forgotten after this long.
+const PEER_LIFETIME: Duration = Duration::from_secs(30 * 60);
+
+/// How often to forget peers past their lifetime.
+const EXPIRE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Most info hashes to store peers for.
+const MAX_TORRENTS: usize = 1000;
+
+/// Most peers to store for an info hash.
+const MAX_PEERS_PER_TORRENT: usize = 100;
+
+/// Maximum amount of peers in a `get_peers` response.
+const MAX_VALUES: usize = 50;
+
+/// Queries waiting for a response, by transaction id and address of the node.
+type Pending = HashMap<(Vec<u8>, SocketAddr), oneshot::Sender<Message>>;
+
+/// Settings for a DHT node.
+#[derive(Debug, Clone)]
+pub struct DhtConfig {
+    /// Address to bind the UDP socket to.
+    pub bind: SocketAddr,
+    /// Id of the node, random (or loaded from `state`) if `None`.
+    pub id: Option<NodeId>,
+    /// Nodes to join the DHT through, as `host:port`.
+    pub bootstrap: Vec<String>,
+    /// How long to wait for a response to a query.
+    pub timeout: Duration,
+    /// File to load the routing table from, and save it to.
+    pub state: Option<PathBuf>,
+}
+
+impl Default for DhtConfig {
+    fn default() -> Self {
+        DhtConfig {
+            bind: SocketAddr::from(([0, 0, 0, 0], 6881)),
+            id: None,
+            bootstrap: vec![
+                "router.bittorrent.com:6881".to_string(),
+                "dht.transmissionbt.com:6881".to_string(),
+                "router.utorrent.com:6881".to_string(),
+            ],
+            timeout: Duration::from_secs(5),
+            state: None,
+        }
+    }
+}
+
+/// A DHT node, answering queries from other nodes and looking up peers.
+#[derive(Debug)]
+pub struct Dht {
+    config: DhtConfig,
+    socket: Arc<UdpSocket>,
+    routing: Mutex<RoutingTable>,
+    /// Peers announced to us, by info hash.
+    peers: Mutex<HashMap<NodeId, BTreeMap<SocketAddr, Instant>>>,
+    tokens: Mutex<TokenManager>,
+    pending: Mutex<Pending>,
+    transaction: AtomicU16,
+    /// Dropped with the node, which stops `receive` and frees the socket.
+    _stop: oneshot::Sender<()>,
+}
+
+impl Dht {
+    /// Bind the socket and start answering queries.
+    ///
+    /// The routing table is loaded from `config.state` if the file exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - settings for the node.
+    pub async fn bind(config: DhtConfig) -> Result<Arc<Dht>> {
+        let mut routing = match &config.state {
+            Some(path) if path.exists() => RoutingTable::load(&std::fs::read(path)?)?,
+            _ => RoutingTable::new(config.id.unwrap_or_else(NodeId::random)),
+        };
+
+        if let Some(id) = config.id.filter(|id| *id != routing.id()) {
+            let nodes = routing.nodes();
+            routing = RoutingTable::new(id);
+            nodes.into_iter().for_each(|node| {
+                routing.insert(node);
+            });
+        }
+
+        let (stop, stopped) = oneshot::channel();
+        let dht = Arc::new(Dht {
+            socket: Arc::new(UdpSocket::bind(config.bind).await?),
+            config,
+            routing: Mutex::new(routing),
+            peers: Mutex::new(HashMap::new()),
+            tokens: Mutex::new(TokenManager::new()),
+            pending: Mutex::new(HashMap::new()),
+            transaction: AtomicU16::new(rand::random()),
+            _stop: stop,
+        });
+
+        async_std::task::spawn(receive(dht.socket.clone(), Arc::downgrade(&dht), stopped));
+        async_std::task::spawn(expire(Arc::downgrade(&dht)));
+
+        Ok(dht)
+    }
+
+    /// Returns the id of this node.
+    pub fn id(&self) -> NodeId {
+        self.routing.lock().unwrap().id()
+    }
+
+    /// Returns the address the socket is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Returns a copy of the routing table.
+    pub fn routing_table(&self) -> RoutingTable {
+        self.routing.lock().unwrap().clone()
+    }
+
+    /// Write the routing table to `config.state`, if set.
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = &self.config.state {
+            std::fs::write(path, self.routing.lock().unwrap().save()?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Send a query and wait for the response.
+    ///
+    /// The node is added to the routing table if it responds, and marked as failed if not.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the node.
+    /// * `query` - query to send.
+    async fn request(&self, addr: SocketAddr, query: Query) -> Result<Response> {
+        let transaction_id = self
+            .transaction
+            .fetch_add(1, Ordering::Relaxed)
+            .to_be_bytes()
+            .to_vec();
+        let (sender, receiver) = oneshot::channel();
+        let key = (transaction_id.clone(), addr);
+
+        self.pending.lock().unwrap().insert(key.clone(), sender);
+
+        let message = Message {
+            transaction_id,
+            body: Body::Query(query),
+        };
+        let sent = self.socket.send_to(&message.into_bytes()?, addr).await;
+        let response = match sent {
+            Ok(_) => timeout(self.config.timeout, receiver).await.ok(),
+            Err(_) => None,
+        };
+
+        self.pending.lock().unwrap().remove(&key);
+
+        match response.and_then(|response| response.ok()).map(|x| x.body) {
+            Some(Body::Response(response)) => {
+                self.routing.lock().unwrap().insert(crate::NodeInfo {
+                    id: response.id,
+                    addr,
+                });
+
+                Ok(response)
+            }
+            Some(Body::Error { code, message }) => Err(anyhow!("Error {code}: {message}")),
+            _ => {
+                let mut routing = self.routing.lock().unwrap();
+
+                if let Some(node) = routing.nodes().into_iter().find(|node| node.addr == addr) {
+                    routing.mark_failed(&node.id);
+                }
+
+                Err(anyhow!("No response from {addr}"))
+            }
+        }
+    }
+
+    /// Answer a query from another node.
+    ///
+    /// The node is marked as seen if it is in the routing table with this address. Returns
+    /// `true` alongside the answer if it isn't, as it must answer a ping before it is added.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query to answer.
+    /// * `from` - address of the querying node.
+    fn handle_query(&self, query: Query, from: SocketAddr) -> (Body, bool) {
+        let mut routing = self.routing.lock().unwrap();
+        let mut response = Response {
+            id: routing.id(),
+            ..Default::default()
+        };
+
+        let sender = match &query {
+            Query::Ping { id }
+            | Query::FindNode { id, .. }
+            | Query::GetPeers { id, .. }
+            | Query::AnnouncePeer { id, .. } => *id,
+        };
+        let unverified = !routing.refresh(&crate::NodeInfo {
+            id: sender,
+            addr: from,
+        });
+
+        match query {
+            Query::Ping { .. } => {}
+            Query::FindNode { target, .. } => {
+                response.nodes = routing.closest(&target, K);
+            }
+            Query::GetPeers { info_hash, .. } => {
+                let mut peers = self.peers.lock().unwrap();
+
+                response.token = Some(self.tokens.lock().unwrap().generate(from.ip()));
+                response.values = match peers.get_mut(&info_hash) {
+                    Some(stored) => {
+                        stored.retain(|_, announced| announced.elapsed() < PEER_LIFETIME);
+                        stored.keys().take(MAX_VALUES).copied().collect()
+                    }
+                    None => vec![],
+                };
+
+                if response.values.is_empty() {
+                    response.nodes = routing.closest(&info_hash, K);
+                }
+            }
+            Query::AnnouncePeer {
+                info_hash,
+                port,
+                token,
+                implied_port,
+                ..
+            } => {
+                if !self.tokens.lock().unwrap().validate(from.ip(), &token) {
+                    let error = Body::Error {
+                        code: 203,
+                        message: "Bad token".to_string(),
+                    };
+
+                    return (error, unverified);
+                }
+
+                let addr =
+                    SocketAddr::new(from.ip(), if implied_port { from.port() } else { port });
+                let mut peers = self.peers.lock().unwrap();
+
+                if !peers.contains_key(&info_hash) && peers.len() >= MAX_TORRENTS {
+                    let error = Body::Error {
+                        code: 202,
+                        message: "Too many torrents".to_string(),
+                    };
+
+                    return (error, unverified);
+                }
+
+                // Make room by forgetting the peer that announced longest ago.
+                let stored = peers.entry(info_hash).or_default();
+                if !stored.contains_key(&addr) && stored.len() >= MAX_PEERS_PER_TORRENT {
+                    let oldest = stored
+                        .iter()
+                        .min_by_key(|(_, announced)| **announced)
+                        .map(|(addr, _)| *addr);
+
+                    if let Some(oldest) = oldest {
+                        stored.remove(&oldest);
+                    }
+                }
+                stored.insert(addr, Instant::now());
+            }
+        }
+
+        (Body::Response(response), unverified)
+    }
+}
+
+/// Forget peers past their lifetime every `EXPIRE_INTERVAL`, until the node is dropped.
+///
+/// # Arguments
+///
+/// * `dht` - the node.
+async fn expire(dht: Weak<Dht>) {
+    loop {
+        async_std::task::sleep(EXPIRE_INTERVAL).await;
+        let Some(dht) = dht.upgrade() else {
+            break;
+        };
+
+        dht.peers.lock().unwrap().retain(|_, stored| {
+            stored.retain(|_, announced| announced.elapsed() < PEER_LIFETIME);
+            !stored.is_empty()
+        });
+    }
+}
+
+/// Read packets from the socket until the node is dropped.
+///
+/// Queries are answered, and responses are passed on to the waiting request.
+///
+/// # Arguments
+///
+/// * `socket` - socket of the node.
+/// * `dht` - the node.
+/// * `stopped` - resolves once the node is dropped.
+async fn receive(socket: Arc<UdpSocket>, dht: Weak<Dht>, mut stopped: oneshot::Receiver<()>) {
+    let mut buf = vec![0; 65536];
+
+    loop {
+        let received =
+            match future::select(Box::pin(socket.recv_from(&mut buf)), &mut stopped).await {
+                Either::Left((received, _)) => received,
+                Either::Right(_) => break,
+            };
+        let Ok((len, from)) = received else {
+            continue;
+        };
+        let Some(dht) = dht.upgrade() else {
+            break;
+        };
+        let Ok(message) = Message::from_bytes(&buf[..len]) else {
+            continue;
+        };
+
+        match message.body {
+            Body::Query(query) => {
+                let (body, unverified) = dht.handle_query(query, from);
+                let response = Message {
+                    transaction_id: message.transaction_id,
+                    body,
+                };
+
+                if let Ok(bytes) = response.into_bytes() {
+                    socket.send_to(&bytes, from).await.ok();
+                }
+
+                // Added to the routing table by `request` if it answers.
+                if unverified {
+                    async_std::task::spawn(async move { dht.ping(from).await.ok() });
+                }
+            }
+            _ => {
+                let key = (message.transaction_id.clone(), from);
+
+                if let Some(sender) = dht.pending.lock().unwrap().remove(&key) {
+                    sender.send(message).ok();
+                }
+            }
+        }
+    }
+}
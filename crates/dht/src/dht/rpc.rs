@@ -0,0 +1,72 @@
+use super::*;
+
+impl Dht {
+    /// Check if a node is alive, returns its id.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the node.
+    pub async fn ping(&self, addr: SocketAddr) -> Result<NodeId> {
+        let response = self.request(addr, Query::Ping { id: self.id() }).await?;
+
+        Ok(response.id)
+    }
+
+    /// Ask a node for the nodes it knows closest to a target.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the node.
+    /// * `target` - id to find nodes close to.
+    pub async fn find_node(&self, addr: SocketAddr, target: NodeId) -> Result<Response> {
+        let query = Query::FindNode {
+            id: self.id(),
+            target,
+        };
+
+        self.request(addr, query).await
+    }
+
+    /// Ask a node for peers of a torrent, or the nodes it knows closest to the info hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the node.
+    /// * `info_hash` - info hash of the torrent.
+    pub async fn get_peers(&self, addr: SocketAddr, info_hash: NodeId) -> Result<Response> {
+        let query = Query::GetPeers {
+            id: self.id(),
+            info_hash,
+        };
+
+        self.request(addr, query).await
+    }
+
+    /// Tell a node we are a peer of a torrent.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the node.
+    /// * `info_hash` - info hash of the torrent.
+    /// * `port` - port peers can connect to, `None` to use the port of the DHT socket.
+    /// * `token` - token from a `get_peers` response of the node.
+    pub async fn announce_peer(
+        &self,
+        addr: SocketAddr,
+        info_hash: NodeId,
+        port: Option<u16>,
+        token: Vec<u8>,
+    ) -> Result<()> {
+        let query = Query::AnnouncePeer {
+            id: self.id(),
+            info_hash,
+            port: port.unwrap_or(0),
+            token,
+            implied_port: port.is_none(),
+        };
+
+        self.request(addr, query).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use crate::NodeId;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+/// A node, as found in the routing table or in responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeInfo {
+    pub id: NodeId,
+    pub addr: SocketAddr,
+}
+
+/// Convert nodes to compact node info, 20 bytes of id, 4 bytes of IPv4 address and 2 bytes of port.
+///
+/// IPv6 nodes are skipped.
+///
+/// # Arguments
+///
+/// * `nodes` - nodes to convert.
+pub fn nodes_to_bytes(nodes: &[NodeInfo]) -> Vec<u8> {
+    let mut out = vec![];
+
+    for node in nodes {
+        if let IpAddr::V4(ip) = node.addr.ip() {
+            out.extend_from_slice(&node.id.0);
+            out.extend_from_slice(&ip.octets());
+            out.extend_from_slice(&node.addr.port().to_be_bytes());
+        }
+    }
+
+    out
+}
+
+/// Convert compact node info, ignoring trailing bytes.
+///
+/// # Arguments
+///
+/// * `bytes` - compact node info.
+pub fn nodes_from_bytes(bytes: &[u8]) -> Vec<NodeInfo> {
+    bytes
+        .chunks_exact(26)
+        .map(|chunk| NodeInfo {
+            id: NodeId::from_bytes(&chunk[..20]).unwrap(),
+            addr: SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::new(chunk[20], chunk[21], chunk[22], chunk[23])),
+                u16::from_be_bytes([chunk[24], chunk[25]]),
+            ),
+        })
+        .collect()
+}
+
+/// Read and write the "values" key, a list of compact peer addresses.
+pub mod values {
+    use super::*;
+    use bcode::BDecode;
+
+    /// Convert peers to a list of compact addresses.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - peers to convert.
+    pub fn bencode(values: &[SocketAddr]) -> bcode::Value {
+        bcode::Value::List(
+            values
+                .iter()
+                .map(|addr| {
+                    let mut out = match addr.ip() {
+                        IpAddr::V4(ip) => ip.octets().to_vec(),
+                        IpAddr::V6(ip) => ip.octets().to_vec(),
+                    };
+                    out.extend_from_slice(&addr.port().to_be_bytes());
+
+                    bcode::Value::ByteString(out)
+                })
+                .collect(),
+        )
+    }
+
+    /// Convert a list of compact addresses to peers, skipping invalid ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - value to convert.
+    pub fn bdecode(value: bcode::Value) -> Result<Vec<SocketAddr>, bcode::Error> {
+        let values = Vec::<bcode::Value>::bdecode(value)?;
+
+        Ok(values
+            .into_iter()
+            .filter_map(|value| {
+                let bytes = bcode::bytes::bdecode(value).ok()?;
+                let (ip, port) = bytes.split_at_checked(bytes.len().checked_sub(2)?)?;
+                let ip = match ip.len() {
+                    4 => IpAddr::from(<[u8; 4]>::try_from(ip).ok()?),
+                    16 => IpAddr::from(<[u8; 16]>::try_from(ip).ok()?),
+                    _ => return None,
+                };
+
+                Some(SocketAddr::new(ip, u16::from_be_bytes([port[0], port[1]])))
+            })
+            .collect())
+    }
+}
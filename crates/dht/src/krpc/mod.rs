@@ -0,0 +1,282 @@
+mod compact;
+
+pub use compact::{nodes_from_bytes, nodes_to_bytes, NodeInfo};
+
+use crate::NodeId;
+use anyhow::{anyhow, Result};
+use bcode::{BDecode, BEncode, DecodeOptions, Mode};
+use std::net::SocketAddr;
+
+/// Limits for decoding messages from other nodes, which fit in a UDP packet.
+const DECODE_LIMITS: DecodeOptions = DecodeOptions {
+    mode: Mode::Lenient,
+    max_depth: 8,
+    max_size: 1 << 16,
+    max_string_length: 1 << 16,
+    max_entries: 1024,
+};
+
+/// A KRPC message: a query, a response or an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Set by the querying node, and copied into the response.
+    pub transaction_id: Vec<u8>,
+    pub body: Body,
+}
+
+/// Contents of a KRPC message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Body {
+    Query(Query),
+    Response(Response),
+    Error { code: i64, message: String },
+}
+
+/// A DHT query, with the id of the querying node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Ping {
+        id: NodeId,
+    },
+    FindNode {
+        id: NodeId,
+        target: NodeId,
+    },
+    GetPeers {
+        id: NodeId,
+        info_hash: NodeId,
+    },
+    AnnouncePeer {
+        id: NodeId,
+        info_hash: NodeId,
+        port: u16,
+        token: Vec<u8>,
+        /// Use the source port of the packet instead of `port`.
+        implied_port: bool,
+    },
+}
+
+/// A response to any query, with the id of the responding node.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Response {
+    pub id: NodeId,
+    /// Nodes closest to the target (`find_node` and `get_peers`).
+    pub nodes: Vec<NodeInfo>,
+    /// Peers of the torrent (`get_peers`).
+    pub values: Vec<SocketAddr>,
+    /// Token needed to announce to the node (`get_peers`).
+    pub token: Option<Vec<u8>>,
+}
+
+/// Bencoded KRPC message.
+#[derive(Debug, Default, BEncode, BDecode)]
+struct RawMessage {
+    #[bcode(bytes)]
+    t: Vec<u8>,
+    y: String,
+    q: Option<String>,
+    a: Option<RawArguments>,
+    r: Option<RawResponse>,
+    e: Option<Vec<bcode::Value>>,
+}
+
+/// Bencoded arguments of a query.
+#[derive(Debug, Default, BEncode, BDecode)]
+struct RawArguments {
+    #[bcode(bytes)]
+    id: Vec<u8>,
+    #[bcode(bytes)]
+    target: Option<Vec<u8>>,
+    #[bcode(bytes)]
+    info_hash: Option<Vec<u8>>,
+    port: Option<i64>,
+    #[bcode(bytes)]
+    token: Option<Vec<u8>>,
+    implied_port: Option<i64>,
+}
+
+/// Bencoded response.
+#[derive(Debug, Default, BEncode, BDecode)]
+struct RawResponse {
+    #[bcode(bytes)]
+    id: Vec<u8>,
+    #[bcode(bytes)]
+    nodes: Option<Vec<u8>>,
+    #[bcode(with = "compact::values")]
+    values: Option<Vec<SocketAddr>>,
+    #[bcode(bytes)]
+    token: Option<Vec<u8>>,
+}
+
+impl Message {
+    /// Converts a byte slice to a `Message`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - a bencoded KRPC message.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Message> {
+        let value = bcode::Decoder::with_options(bytes, DECODE_LIMITS).decode()?;
+        let raw = RawMessage::bdecode(value)?;
+
+        let body = match raw.y.as_str() {
+            "q" => Body::Query(parse_query(
+                raw.q.as_deref().unwrap_or_default(),
+                raw.a.ok_or_else(|| anyhow!("Missing query arguments"))?,
+            )?),
+            "r" => {
+                let r = raw.r.ok_or_else(|| anyhow!("Missing response"))?;
+
+                Body::Response(Response {
+                    id: NodeId::from_bytes(&r.id)?,
+                    nodes: compact::nodes_from_bytes(&r.nodes.unwrap_or_default()),
+                    values: r.values.unwrap_or_default(),
+                    token: r.token,
+                })
+            }
+            "e" => {
+                let e = raw.e.unwrap_or_default();
+
+                Body::Error {
+                    code: e
+                        .first()
+                        .and_then(|x| i64::try_from(x.clone()).ok())
+                        .unwrap_or(0),
+                    message: e
+                        .get(1)
+                        .and_then(|x| String::try_from(x.clone()).ok())
+                        .unwrap_or_default(),
+                }
+            }
+            y => return Err(anyhow!("Unexpected message type {y:?}")),
+        };
+
+        Ok(Message {
+            transaction_id: raw.t,
+            body,
+        })
+    }
+
+    /// Converts a `Message` into a byte vector.
+    pub fn into_bytes(self) -> Result<Vec<u8>> {
+        let mut raw = RawMessage {
+            t: self.transaction_id,
+            ..Default::default()
+        };
+
+        match self.body {
+            Body::Query(query) => {
+                let (q, a) = raw_query(query);
+                raw.y = "q".to_string();
+                raw.q = Some(q.to_string());
+                raw.a = Some(a);
+            }
+            Body::Response(response) => {
+                raw.y = "r".to_string();
+                raw.r = Some(RawResponse {
+                    id: response.id.0.to_vec(),
+                    nodes: (!response.nodes.is_empty())
+                        .then(|| compact::nodes_to_bytes(&response.nodes)),
+                    values: (!response.values.is_empty()).then_some(response.values),
+                    token: response.token,
+                });
+            }
+            Body::Error { code, message } => {
+                raw.y = "e".to_string();
+                raw.e = Some(vec![code.into(), message.into()]);
+            }
+        }
+
+        bcode::encode(raw.bencode())
+    }
+}
+
+/// Convert the arguments of a query.
+///
+/// # Arguments
+///
+/// * `q` - name of the query.
+/// * `a` - arguments of the query.
+fn parse_query(q: &str, a: RawArguments) -> Result<Query> {
+    let id = NodeId::from_bytes(&a.id)?;
+    let info_hash = || {
+        NodeId::from_bytes(
+            a.info_hash
+                .as_deref()
+                .ok_or_else(|| anyhow!("Missing \"info_hash\""))?,
+        )
+    };
+
+    match q {
+        "ping" => Ok(Query::Ping { id }),
+        "find_node" => Ok(Query::FindNode {
+            id,
+            target: NodeId::from_bytes(
+                a.target
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("Missing \"target\""))?,
+            )?,
+        }),
+        "get_peers" => Ok(Query::GetPeers {
+            id,
+            info_hash: info_hash()?,
+        }),
+        "announce_peer" => Ok(Query::AnnouncePeer {
+            id,
+            info_hash: info_hash()?,
+            port: u16::try_from(a.port.unwrap_or(0))?,
+            token: a.token.clone().unwrap_or_default(),
+            implied_port: a.implied_port.unwrap_or(0) != 0,
+        }),
+        q => Err(anyhow!("Unknown query {q:?}")),
+    }
+}
+
+/// Convert a query to its name and arguments.
+///
+/// # Arguments
+///
+/// * `query` - query to convert.
+fn raw_query(query: Query) -> (&'static str, RawArguments) {
+    match query {
+        Query::Ping { id } => (
+            "ping",
+            RawArguments {
+                id: id.0.to_vec(),
+                ..Default::default()
+            },
+        ),
+        Query::FindNode { id, target } => (
+            "find_node",
+            RawArguments {
+                id: id.0.to_vec(),
+                target: Some(target.0.to_vec()),
+                ..Default::default()
+            },
+        ),
+        Query::GetPeers { id, info_hash } => (
+            "get_peers",
+            RawArguments {
+                id: id.0.to_vec(),
+                info_hash: Some(info_hash.0.to_vec()),
+                ..Default::default()
+            },
+        ),
+        Query::AnnouncePeer {
+            id,
+            info_hash,
+            port,
+            token,
+            implied_port,
+        } => (
+            "announce_peer",
+            RawArguments {
+                id: id.0.to_vec(),
+                info_hash: Some(info_hash.0.to_vec()),
+                port: Some(port as i64),
+                token: Some(token),
+                implied_port: implied_port.then_some(1),
+                ..Default::default()
+            },
+        ),
+    }
+}
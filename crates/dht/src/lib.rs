@@ -0,0 +1,18 @@
+//! # DHT
+//!
+//! `dht` is a Mainline DHT (BEP 5) node, speaking KRPC over UDP,
+//! for finding peers of a torrent without a tracker.
+//!
+//! Only IPv4 nodes are supported, IPv6 (BEP 32) nodes are ignored.
+
+mod dht;
+mod krpc;
+mod node_id;
+mod routing;
+mod token;
+
+pub use crate::dht::{Dht, DhtConfig};
+pub use crate::krpc::{Body, Message, NodeInfo, Query, Response};
+pub use crate::node_id::NodeId;
+pub use crate::routing::{RoutingTable, K};
+pub use crate::token::TokenManager;
@@ -0,0 +1,53 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+
+/// Id of a node, or an info hash, in the 160-bit keyspace of the DHT.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(pub [u8; 20]);
+
+impl NodeId {
+    /// Create a random id.
+    pub fn random() -> NodeId {
+        NodeId(rand::random())
+    }
+
+    /// Converts a byte slice to a `NodeId`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - exactly 20 bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<NodeId> {
+        Ok(NodeId(
+            bytes
+                .try_into()
+                .map_err(|_| anyhow!("Node id must be 20 bytes"))?,
+        ))
+    }
+
+    /// Returns the XOR distance to another id.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - id to measure the distance to.
+    pub fn distance(&self, other: &NodeId) -> NodeId {
+        NodeId(std::array::from_fn(|i| self.0[i] ^ other.0[i]))
+    }
+
+    /// Returns the amount of leading bits shared with another id, `None` if they are equal.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - id to compare with.
+    pub fn shared_prefix(&self, other: &NodeId) -> Option<usize> {
+        let distance = self.distance(other);
+        let index = distance.0.iter().position(|byte| *byte != 0)?;
+
+        Some(index * 8 + distance.0[index].leading_zeros() as usize)
+    }
+}
+
+impl fmt::Debug for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&bcode::to_hex(&self.0))
+    }
+}
@@ -0,0 +1,209 @@
+use crate::krpc::{self, NodeInfo};
+use crate::NodeId;
+use anyhow::Result;
+use bcode::{BDecode, BEncode};
+use std::time::{Duration, Instant};
+
+/// Maximum amount of nodes in a bucket.
+pub const K: usize = 8;
+
+/// Nodes not heard from in this long are questionable, and can be replaced.
+const QUESTIONABLE_AFTER: Duration = Duration::from_secs(15 * 60);
+
+/// Nodes failing to respond this many times in a row are removed.
+const MAX_FAILURES: u32 = 3;
+
+/// A node in the routing table.
+#[derive(Debug, Clone)]
+struct Entry {
+    node: NodeInfo,
+    last_seen: Instant,
+    failures: u32,
+}
+
+impl Entry {
+    /// Returns `true` if the node can be replaced by a new one.
+    fn is_questionable(&self) -> bool {
+        self.failures > 0 || self.last_seen.elapsed() >= QUESTIONABLE_AFTER
+    }
+}
+
+/// Routing table of known nodes.
+///
+/// Bucket `i` holds up to `K` nodes sharing exactly `i` leading bits with our own id,
+/// so there are many buckets for nodes close to us, and few for nodes far away.
+#[derive(Debug, Clone)]
+pub struct RoutingTable {
+    id: NodeId,
+    buckets: Vec<Vec<Entry>>,
+}
+
+/// Bencoded routing table, as saved to disk.
+#[derive(Debug, BEncode, BDecode)]
+struct RawRoutingTable {
+    #[bcode(bytes)]
+    id: Vec<u8>,
+    #[bcode(bytes)]
+    nodes: Vec<u8>,
+}
+
+impl RoutingTable {
+    /// Create an empty routing table.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - id of our own node.
+    pub fn new(id: NodeId) -> RoutingTable {
+        RoutingTable {
+            id,
+            buckets: vec![vec![]; 160],
+        }
+    }
+
+    /// Returns the id of our own node.
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Returns the amount of nodes in the table.
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(Vec::len).sum()
+    }
+
+    /// Returns `true` if the table has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns every node in the table.
+    pub fn nodes(&self) -> Vec<NodeInfo> {
+        self.buckets
+            .iter()
+            .flatten()
+            .map(|entry| entry.node)
+            .collect()
+    }
+
+    /// Add a node we heard from, or mark it as seen.
+    ///
+    /// If its bucket is full, the node replaces a questionable one, or is dropped. A node
+    /// with the id of one in the table but another address is dropped too, so it can't take
+    /// over the entry. Returns `true` if the node is in the table afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - node to add.
+    pub fn insert(&mut self, node: NodeInfo) -> bool {
+        let Some(index) = self.id.shared_prefix(&node.id) else {
+            return false;
+        };
+        let bucket = &mut self.buckets[index];
+        let entry = Entry {
+            node,
+            last_seen: Instant::now(),
+            failures: 0,
+        };
+
+        if let Some(existing) = bucket.iter_mut().find(|entry| entry.node.id == node.id) {
+            if existing.node.addr != node.addr {
+                return false;
+            }
+
+            *existing = entry;
+        } else if bucket.len() < K {
+            bucket.push(entry);
+        } else if let Some(existing) = bucket.iter_mut().find(|entry| entry.is_questionable()) {
+            *existing = entry;
+        } else {
+            return false;
+        }
+
+        true
+    }
+
+    /// Mark a node as seen if it is in the table with the same address.
+    ///
+    /// Returns `true` if it is.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - node we heard from.
+    pub fn refresh(&mut self, node: &NodeInfo) -> bool {
+        let Some(index) = self.id.shared_prefix(&node.id) else {
+            return false;
+        };
+
+        match self.buckets[index]
+            .iter_mut()
+            .find(|entry| entry.node == *node)
+        {
+            Some(existing) => {
+                existing.last_seen = Instant::now();
+                existing.failures = 0;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Note that a node didn't respond, removing it if it fails too often.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - id of the node.
+    pub fn mark_failed(&mut self, id: &NodeId) {
+        let Some(index) = self.id.shared_prefix(id) else {
+            return;
+        };
+        let bucket = &mut self.buckets[index];
+
+        if let Some(position) = bucket.iter().position(|entry| entry.node.id == *id) {
+            bucket[position].failures += 1;
+
+            if bucket[position].failures >= MAX_FAILURES {
+                bucket.remove(position);
+            }
+        }
+    }
+
+    /// Returns up to `amount` nodes closest to a target, closest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - id to measure distance to.
+    /// * `amount` - maximum amount of nodes.
+    pub fn closest(&self, target: &NodeId, amount: usize) -> Vec<NodeInfo> {
+        let mut nodes = self.nodes();
+
+        nodes.sort_by_key(|node| node.id.distance(target));
+        nodes.truncate(amount);
+
+        nodes
+    }
+
+    /// Converts the table to bytes, keeping our id and the nodes.
+    pub fn save(&self) -> Result<Vec<u8>> {
+        let raw = RawRoutingTable {
+            id: self.id.0.to_vec(),
+            nodes: krpc::nodes_to_bytes(&self.nodes()),
+        };
+
+        bcode::encode(raw.bencode())
+    }
+
+    /// Converts bytes from `save` to a routing table.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - saved routing table.
+    pub fn load(bytes: &[u8]) -> Result<RoutingTable> {
+        let raw = RawRoutingTable::bdecode(bcode::decode(bytes, &mut 0)?)?;
+        let mut table = RoutingTable::new(NodeId::from_bytes(&raw.id)?);
+
+        for node in krpc::nodes_from_bytes(&raw.nodes) {
+            table.insert(node);
+        }
+
+        Ok(table)
+    }
+}
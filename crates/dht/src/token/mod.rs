@@ -0,0 +1,84 @@
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// How often the secret changes, tokens stay valid for up to twice as long.
+const ROTATION_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Creates and checks the tokens given out in `get_peers` responses.
+///
+/// A token is a hash of the requesting IP address and a secret,
+/// so only nodes that asked for peers can announce themselves.
+#[derive(Debug)]
+pub struct TokenManager {
+    secret: [u8; 20],
+    previous_secret: [u8; 20],
+    rotated_at: Instant,
+}
+
+impl Default for TokenManager {
+    fn default() -> Self {
+        TokenManager::new()
+    }
+}
+
+impl TokenManager {
+    /// Create a token manager with a random secret.
+    pub fn new() -> TokenManager {
+        let secret = rand::random();
+
+        TokenManager {
+            secret,
+            previous_secret: secret,
+            rotated_at: Instant::now(),
+        }
+    }
+
+    /// Create a token for an address.
+    ///
+    /// # Arguments
+    ///
+    /// * `ip` - address of the requesting node.
+    pub fn generate(&mut self, ip: IpAddr) -> Vec<u8> {
+        self.rotate();
+
+        hash(&self.secret, ip)
+    }
+
+    /// Returns `true` if the token was given to the address, with the current or previous secret.
+    ///
+    /// # Arguments
+    ///
+    /// * `ip` - address of the announcing node.
+    /// * `token` - token sent by the node.
+    pub fn validate(&mut self, ip: IpAddr, token: &[u8]) -> bool {
+        self.rotate();
+
+        hash(&self.secret, ip) == token || hash(&self.previous_secret, ip) == token
+    }
+
+    /// Change the secret, if it's old.
+    fn rotate(&mut self) {
+        if self.rotated_at.elapsed() >= ROTATION_INTERVAL {
+            self.previous_secret = self.secret;
+            self.secret = rand::random();
+            self.rotated_at = Instant::now();
+        }
+    }
+}
+
+/// Hash a secret and an address into a token.
+///
+/// # Arguments
+///
+/// * `secret` - secret to hash with.
+/// * `ip` - address to hash.
+fn hash(secret: &[u8], ip: IpAddr) -> Vec<u8> {
+    let mut hasher = sha1_smol::Sha1::from(secret);
+
+    match ip {
+        IpAddr::V4(ip) => hasher.update(&ip.octets()),
+        IpAddr::V6(ip) => hasher.update(&ip.octets()),
+    }
+
+    hasher.digest().bytes()[..8].to_vec()
+}
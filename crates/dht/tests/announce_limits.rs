@@ -0,0 +1,54 @@
+mod common;
+
+use dht::{Dht, DhtConfig, NodeId};
+
+#[async_std::test]
+async fn announce_limits() {
+    let config = || DhtConfig {
+        bind: ([127, 0, 0, 1], 0).into(),
+        bootstrap: vec![],
+        ..Default::default()
+    };
+    let storing = Dht::bind(config()).await.unwrap();
+    let addr = storing.local_addr().unwrap();
+    let announcing = Dht::bind(config()).await.unwrap();
+
+    let info_hash = |index: u32| {
+        let mut info_hash = [0; 20];
+        info_hash[..4].copy_from_slice(&index.to_be_bytes());
+        NodeId(info_hash)
+    };
+    let token = announcing
+        .get_peers(addr, info_hash(0))
+        .await
+        .unwrap()
+        .token
+        .unwrap();
+
+    // Peers are stored for a limited amount of torrents.
+    for index in 0..1000 {
+        announcing
+            .announce_peer(addr, info_hash(index), Some(1), token.clone())
+            .await
+            .unwrap();
+    }
+    assert!(announcing
+        .announce_peer(addr, info_hash(1000), Some(1), token.clone())
+        .await
+        .is_err());
+
+    // Torrents already stored take new peers, up to a limit that drops the oldest.
+    for port in 2..200 {
+        announcing
+            .announce_peer(addr, info_hash(0), Some(port), token.clone())
+            .await
+            .unwrap();
+    }
+    let values = announcing
+        .get_peers(addr, info_hash(0))
+        .await
+        .unwrap()
+        .values;
+    assert!(!values.is_empty());
+    assert!(values.iter().all(|value| value.port() >= 100));
+}
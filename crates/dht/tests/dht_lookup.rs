@@ -0,0 +1,70 @@
+mod common;
+
+use dht::{Dht, DhtConfig};
+use std::time::Duration;
+
+#[async_std::test]
+async fn dht_lookup() {
+    let config = |bootstrap: Vec<String>| DhtConfig {
+        bind: ([127, 0, 0, 1], 0).into(),
+        bootstrap,
+        timeout: Duration::from_secs(2),
+        ..Default::default()
+    };
+
+    // A network of nodes, all joining through the first one.
+    let first = Dht::bind(config(vec![])).await.unwrap();
+    let first_addr = first.local_addr().unwrap().to_string();
+    let mut nodes = vec![];
+    for _ in 0..10 {
+        let node = Dht::bind(config(vec![first_addr.clone()])).await.unwrap();
+        node.bootstrap().await.unwrap();
+        nodes.push(node);
+    }
+    assert!(first.routing_table().len() >= 8);
+
+    // Nothing is found before anyone announces.
+    let info_hash = [7; 20];
+    assert!(nodes[0].lookup_peers(&info_hash).await.unwrap().is_empty());
+
+    // One node announces, with an explicit and an implied port, and another finds it.
+    nodes[1].announce(&info_hash, Some(51413)).await.unwrap();
+    nodes[2].announce(&info_hash, None).await.unwrap();
+    let peers = nodes[3].lookup_peers(&info_hash).await.unwrap();
+    assert!(peers.contains(&([127, 0, 0, 1], 51413).into()));
+    assert!(peers.contains(&nodes[2].local_addr().unwrap()));
+
+    // Announcing with a bad token is rejected.
+    let addr = first.local_addr().unwrap();
+    assert!(nodes[4]
+        .announce_peer(addr, dht::NodeId(info_hash), Some(1), b"bad".to_vec())
+        .await
+        .is_err());
+
+    // A node without reachable bootstrap nodes fails to join.
+    let lonely = Dht::bind(config(vec![])).await.unwrap();
+    assert!(lonely.bootstrap().await.is_err());
+
+    // The routing table is persisted across restarts.
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("dht_lookup.dat");
+    std::fs::remove_file(&path).ok();
+    let saved = Dht::bind(DhtConfig {
+        state: Some(path.clone()),
+        ..config(vec![first_addr.clone()])
+    })
+    .await
+    .unwrap();
+    saved.bootstrap().await.unwrap();
+    saved.save().unwrap();
+    let (id, len) = (saved.id(), saved.routing_table().len());
+    drop(saved);
+    let restored = Dht::bind(DhtConfig {
+        state: Some(path),
+        ..config(vec![])
+    })
+    .await
+    .unwrap();
+    assert_eq!(restored.id(), id);
+    assert_eq!(restored.routing_table().len(), len);
+    restored.bootstrap().await.unwrap();
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use dht::{Dht, DhtConfig};
+use std::time::Duration;
+
+#[async_std::test]
+async fn drop_frees_socket() {
+    let config = DhtConfig {
+        bind: ([127, 0, 0, 1], 0).into(),
+        bootstrap: vec![],
+        ..Default::default()
+    };
+    let dht = Dht::bind(config.clone()).await.unwrap();
+    let addr = dht.local_addr().unwrap();
+
+    // The port is freed once the node is dropped, without waiting for a packet.
+    drop(dht);
+    async_std::task::sleep(Duration::from_millis(100)).await;
+    let config = DhtConfig {
+        bind: addr,
+        ..config
+    };
+    assert!(Dht::bind(config).await.is_ok());
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use dht::{Body, Message, NodeId, NodeInfo, Query, Response};
+
+#[test]
+fn krpc_messages() {
+    let id = NodeId(*b"abcdefghij0123456789");
+    let info_hash = NodeId(*b"mnopqrstuvwxyz123456");
+
+    // Example query from BEP 5.
+    let bytes = b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe";
+    let message = Message::from_bytes(bytes).unwrap();
+    assert_eq!(
+        message,
+        Message {
+            transaction_id: b"aa".to_vec(),
+            body: Body::Query(Query::Ping { id }),
+        }
+    );
+    assert_eq!(message.into_bytes().unwrap(), bytes);
+
+    // Example announce from BEP 5.
+    let bytes = b"d1:ad2:id20:abcdefghij012345678912:implied_porti1e9:info_hash20:mnopqrstuvwxyz1234564:porti6881e5:token8:aoeusnthe1:q13:announce_peer1:t2:aa1:y1:qe";
+    assert_eq!(
+        Message::from_bytes(bytes).unwrap().body,
+        Body::Query(Query::AnnouncePeer {
+            id,
+            info_hash,
+            port: 6881,
+            token: b"aoeusnth".to_vec(),
+            implied_port: true,
+        })
+    );
+
+    // Responses with compact nodes and peers round-trip.
+    let message = Message {
+        transaction_id: b"aa".to_vec(),
+        body: Body::Response(Response {
+            id,
+            nodes: vec![NodeInfo {
+                id: info_hash,
+                addr: "127.0.0.1:6881".parse().unwrap(),
+            }],
+            values: vec![
+                "10.0.0.1:51413".parse().unwrap(),
+                "[2001:db8::1]:6881".parse().unwrap(),
+            ],
+            token: Some(b"aoeusnth".to_vec()),
+        }),
+    };
+    let bytes = message.clone().into_bytes().unwrap();
+    assert!(bytes.starts_with(
+        b"d1:rd2:id20:abcdefghij01234567895:nodes26:mnopqrstuvwxyz123456\x7f\x00\x00\x01\x1a\xe1"
+    ));
+    assert_eq!(Message::from_bytes(&bytes).unwrap(), message);
+
+    // Example error from BEP 5.
+    let bytes = b"d1:eli201e23:A Generic Error Ocurrede1:t2:aa1:y1:ee";
+    let message = Message::from_bytes(bytes).unwrap();
+    assert_eq!(
+        message.body,
+        Body::Error {
+            code: 201,
+            message: "A Generic Error Ocurred".to_string(),
+        }
+    );
+    assert_eq!(message.into_bytes().unwrap(), bytes);
+
+    // Unknown queries are rejected.
+    assert!(
+        Message::from_bytes(b"d1:ad2:id20:abcdefghij0123456789e1:q3:foo1:t2:aa1:y1:qe").is_err()
+    );
+
+    // Unknown keys are ignored, unless they nest too deep.
+    let nested = |depth: usize| {
+        let mut bytes = b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:q1:z".to_vec();
+        bytes.extend(std::iter::repeat_n(b'l', depth));
+        bytes.extend(std::iter::repeat_n(b'e', depth + 1));
+        bytes
+    };
+    assert!(Message::from_bytes(&nested(3)).is_ok());
+    assert!(Message::from_bytes(&nested(32)).is_err());
+}
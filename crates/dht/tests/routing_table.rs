@@ -0,0 +1,52 @@
+mod common;
+
+use dht::{NodeId, NodeInfo, RoutingTable, K};
+
+#[test]
+fn routing_table() {
+    let own = NodeId([0; 20]);
+    let mut table = RoutingTable::new(own);
+    let node = |first: u8, last: u8| NodeInfo {
+        id: NodeId(std::array::from_fn(|i| match i {
+            0 => first,
+            19 => last,
+            _ => 0,
+        })),
+        addr: ([127, 0, 0, 1], 6000 + last as u16).into(),
+    };
+
+    // Our own id is never added, and each bucket holds at most K nodes.
+    assert!(!table.insert(NodeInfo {
+        id: own,
+        addr: ([127, 0, 0, 1], 6881).into(),
+    }));
+    for last in 0..K as u8 + 2 {
+        assert_eq!(table.insert(node(0x80, last)), (last as usize) < K);
+    }
+    assert!(table.insert(node(0x01, 1)));
+    assert_eq!(table.len(), K + 1);
+
+    // Nodes failing three times are removed, making room for new ones.
+    for _ in 0..3 {
+        table.mark_failed(&node(0x80, 0).id);
+    }
+    assert_eq!(table.len(), K);
+    assert!(table.insert(node(0x80, 100)));
+
+    // A node can't move another one with the same id to its address.
+    let mut moved = node(0x80, 100);
+    moved.addr = ([127, 0, 0, 2], 6000).into();
+    assert!(!table.insert(moved));
+    assert!(!table.refresh(&moved));
+    assert!(table.refresh(&node(0x80, 100)));
+    assert!(table.nodes().contains(&node(0x80, 100)));
+
+    // Closest nodes are sorted by XOR distance.
+    let closest = table.closest(&NodeId([0; 20]), 2);
+    assert_eq!(closest, vec![node(0x01, 1), node(0x80, 1)]);
+
+    // The table is saved and loaded with its id and nodes.
+    let loaded = RoutingTable::load(&table.save().unwrap()).unwrap();
+    assert_eq!(loaded.id(), own);
+    assert_eq!(loaded.closest(&own, 100), table.closest(&own, 100));
+}
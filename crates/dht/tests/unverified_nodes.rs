@@ -0,0 +1,59 @@
+mod common;
+
+use async_std::net::UdpSocket;
+use dht::{Body, Dht, DhtConfig, Message, NodeId, NodeInfo, Query};
+use std::time::Duration;
+
+#[async_std::test]
+async fn unverified_nodes() {
+    let config = DhtConfig {
+        bind: ([127, 0, 0, 1], 0).into(),
+        bootstrap: vec![],
+        timeout: Duration::from_millis(200),
+        ..Default::default()
+    };
+    let node = Dht::bind(config.clone()).await.unwrap();
+    let addr = node.local_addr().unwrap();
+
+    // Sends a query claiming an id, and reads the answer, but never answers itself.
+    let spoofer = &UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let spoof = |id: NodeId| async move {
+        let query = Message {
+            transaction_id: b"aa".to_vec(),
+            body: Body::Query(Query::Ping { id }),
+        };
+        spoofer
+            .send_to(&query.into_bytes().unwrap(), addr)
+            .await
+            .unwrap();
+
+        let mut buf = vec![0; 1500];
+        let (len, _) = spoofer.recv_from(&mut buf).await.unwrap();
+        Message::from_bytes(&buf[..len]).unwrap()
+    };
+
+    // A querying node is answered, but only added once it answers our ping.
+    assert!(matches!(
+        spoof(NodeId([1; 20])).await.body,
+        Body::Response(_)
+    ));
+    async_std::task::sleep(Duration::from_millis(400)).await;
+    assert!(node.routing_table().is_empty());
+
+    let other = Dht::bind(config).await.unwrap();
+    let other_addr = other.local_addr().unwrap();
+    other.ping(addr).await.unwrap();
+    async_std::task::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        node.routing_table().nodes(),
+        [NodeInfo {
+            id: other.id(),
+            addr: other_addr,
+        }]
+    );
+
+    // Another address can't take over the id of a node in the table.
+    spoof(other.id()).await;
+    async_std::task::sleep(Duration::from_millis(400)).await;
+    assert_eq!(node.routing_table().nodes()[0].addr, other_addr);
+}
@@ -1,3 +1,3 @@
 mod message;
 
-pub use crate::message::Message;
+pub use crate::message::{Message, MAX_BLOCK_SIZE, MAX_MESSAGE_LENGTH};
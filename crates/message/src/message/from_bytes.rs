@@ -1,4 +1,4 @@
-use crate::Message;
+use crate::{Message, MAX_MESSAGE_LENGTH};
 use anyhow::{anyhow, Result};
 use arrayref::array_ref;
 
@@ -9,18 +9,29 @@ impl Message {
     ///
     /// * `vec` - byte vector.
     pub fn from_bytes(vec: Vec<u8>) -> Result<Message> {
-        let length: u32 = u32::from_be_bytes(*array_ref![vec, 0, 4]);
+        let length = vec
+            .get(0..4)
+            .map(|x| u32::from_be_bytes(*array_ref![x, 0, 4]) as usize)
+            .ok_or_else(|| anyhow!("Missing message length"))?;
 
         if length == 0 {
             return Ok(Message::new_keep_alive());
+        } else if length > MAX_MESSAGE_LENGTH {
+            return Err(anyhow!("Message length {length} is too large"));
         }
 
-        let message_id: u8 = u8::from_be_bytes(*array_ref![vec, 4, 1]);
+        let message_id = *vec.get(4).ok_or_else(|| anyhow!("Missing message id"))?;
         let payload = vec
-            .get(5..5 + (length as usize - 1))
+            .get(5..4 + length)
             .ok_or_else(|| anyhow!("Missing message payload"))?;
 
+        // Payloads come from the peer, so check their length before reading.
         match message_id {
+            4 | 13 | 17 if payload.len() != 4 => Err(anyhow!("Invalid piece index payload")),
+            6 | 8 if payload.len() != 12 => Err(anyhow!("Invalid block payload")),
+            16 if payload.len() != 12 => Err(anyhow!("Invalid reject request payload")),
+            7 if payload.len() < 8 => Err(anyhow!("Invalid piece payload")),
+            9 if payload.len() != 2 => Err(anyhow!("Invalid port payload")),
             0 => Ok(Message::new_choke()),
             1 => Ok(Message::new_unchoke()),
             2 => Ok(Message::new_interested()),
@@ -47,6 +58,25 @@ impl Message {
             9 => Ok(Message::new_port(u16::from_be_bytes(*array_ref![
                 payload, 0, 2
             ]))),
+            13 => Ok(Message::new_suggest_piece(u32::from_be_bytes(*array_ref![
+                payload, 0, 4
+            ]))),
+            14 => Ok(Message::new_have_all()),
+            15 => Ok(Message::new_have_none()),
+            16 => Ok(Message::new_reject_request(
+                u32::from_be_bytes(*array_ref![payload, 0, 4]),
+                u32::from_be_bytes(*array_ref![payload, 4, 4]),
+                u32::from_be_bytes(*array_ref![payload, 8, 4]),
+            )),
+            17 => Ok(Message::new_allowed_fast(u32::from_be_bytes(*array_ref![
+                payload, 0, 4
+            ]))),
+            20 => Ok(Message::new_extended(
+                *payload
+                    .first()
+                    .ok_or_else(|| anyhow!("Missing extended message id"))?,
+                payload[1..].to_vec(),
+            )),
             _ => Err(anyhow!("Unexpected message id")),
         }
     }
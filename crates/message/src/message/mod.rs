@@ -3,6 +3,12 @@ mod into_bytes;
 
 pub type MessageData = (u8, Vec<u8>);
 
+/// Largest block accepted in a piece message, what common clients allow at most.
+pub const MAX_BLOCK_SIZE: usize = 1 << 17;
+
+/// Largest message length accepted from a peer, a block plus the piece message header.
+pub const MAX_MESSAGE_LENGTH: usize = MAX_BLOCK_SIZE + 13;
+
 /// A message used to communicate on the BitTorrent network.
 #[derive(Debug, PartialEq, Eq)]
 pub enum Message {
@@ -17,6 +23,12 @@ pub enum Message {
     Piece(MessageData),
     Cancel(MessageData),
     Port(MessageData),
+    SuggestPiece(MessageData),
+    HaveAll(MessageData),
+    HaveNone(MessageData),
+    RejectRequest(MessageData),
+    AllowedFast(MessageData),
+    Extended(MessageData),
 }
 
 impl Message {
@@ -27,9 +39,9 @@ impl Message {
         match self {
             KeepAlive => None,
             Choke(data) | Unchoke(data) | Interested(data) | NotInterested(data) | Have(data)
-            | Bitfield(data) | Request(data) | Piece(data) | Cancel(data) | Port(data) => {
-                Some(data.0)
-            }
+            | Bitfield(data) | Request(data) | Piece(data) | Cancel(data) | Port(data)
+            | SuggestPiece(data) | HaveAll(data) | HaveNone(data) | RejectRequest(data)
+            | AllowedFast(data) | Extended(data) => Some(data.0),
         }
     }
 
@@ -47,6 +59,12 @@ impl Message {
             Message::Piece(_) => "Piece",
             Message::Cancel(_) => "Cancel",
             Message::Port(_) => "Port",
+            Message::SuggestPiece(_) => "Suggest piece",
+            Message::HaveAll(_) => "Have all",
+            Message::HaveNone(_) => "Have none",
+            Message::RejectRequest(_) => "Reject request",
+            Message::AllowedFast(_) => "Allowed fast",
+            Message::Extended(_) => "Extended",
         }
     }
 
@@ -57,9 +75,9 @@ impl Message {
         match self {
             KeepAlive => vec![],
             Choke(data) | Unchoke(data) | Interested(data) | NotInterested(data) | Have(data)
-            | Bitfield(data) | Request(data) | Piece(data) | Cancel(data) | Port(data) => {
-                data.1.to_owned()
-            }
+            | Bitfield(data) | Request(data) | Piece(data) | Cancel(data) | Port(data)
+            | SuggestPiece(data) | HaveAll(data) | HaveNone(data) | RejectRequest(data)
+            | AllowedFast(data) | Extended(data) => data.1.to_owned(),
         }
     }
 
@@ -173,4 +191,61 @@ impl Message {
     pub fn new_port(port: u16) -> Message {
         Message::Port((9, port.to_be_bytes().to_vec()))
     }
+
+    /// Construct a "suggest piece" message (BEP 6).
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_index` - index of piece.
+    pub fn new_suggest_piece(piece_index: u32) -> Message {
+        Message::SuggestPiece((13, piece_index.to_be_bytes().to_vec()))
+    }
+
+    /// Construct a "have all" message (BEP 6).
+    pub fn new_have_all() -> Message {
+        Message::HaveAll((14, vec![]))
+    }
+
+    /// Construct a "have none" message (BEP 6).
+    pub fn new_have_none() -> Message {
+        Message::HaveNone((15, vec![]))
+    }
+
+    /// Construct a "reject request" message (BEP 6).
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - piece index.
+    /// * `begin` - byte offset within the piece.
+    /// * `length` - length from byte offset.
+    pub fn new_reject_request(index: u32, begin: u32, length: u32) -> Message {
+        let mut buf = vec![];
+        buf.append(&mut index.to_be_bytes().to_vec());
+        buf.append(&mut begin.to_be_bytes().to_vec());
+        buf.append(&mut length.to_be_bytes().to_vec());
+
+        Message::RejectRequest((16, buf))
+    }
+
+    /// Construct an "allowed fast" message (BEP 6).
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_index` - index of piece.
+    pub fn new_allowed_fast(piece_index: u32) -> Message {
+        Message::AllowedFast((17, piece_index.to_be_bytes().to_vec()))
+    }
+
+    /// Construct an "extended" message (BEP 10).
+    ///
+    /// # Arguments
+    ///
+    /// * `extended_id` - id of the extension, `0` for the extended handshake.
+    /// * `payload` - extension specific payload.
+    pub fn new_extended(extended_id: u8, mut payload: Vec<u8>) -> Message {
+        let mut buf = vec![extended_id];
+        buf.append(&mut payload);
+
+        Message::Extended((20, buf))
+    }
 }
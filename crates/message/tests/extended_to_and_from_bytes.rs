@@ -0,0 +1,16 @@
+mod common;
+
+#[test]
+fn extended_to_and_from_bytes() {
+    let original_message = message::Message::new_extended(3, b"d1:ai1ee".to_vec());
+    let message_as_bytes = original_message.into_bytes();
+
+    assert_eq!(message_as_bytes[..6], [0, 0, 0, 10, 20, 3]);
+
+    let message_from_bytes = message::Message::from_bytes(message_as_bytes).unwrap();
+
+    assert_eq!(
+        message::Message::new_extended(3, b"d1:ai1ee".to_vec()),
+        message_from_bytes
+    );
+}
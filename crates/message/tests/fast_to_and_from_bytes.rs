@@ -0,0 +1,43 @@
+mod common;
+
+use message::Message;
+
+#[test]
+fn fast_to_and_from_bytes() {
+    let messages = [
+        (
+            Message::new_suggest_piece(7),
+            vec![0, 0, 0, 5, 13, 0, 0, 0, 7],
+        ),
+        (Message::new_have_all(), vec![0, 0, 0, 1, 14]),
+        (Message::new_have_none(), vec![0, 0, 0, 1, 15]),
+        (
+            Message::new_reject_request(1, 16384, 16384),
+            vec![0, 0, 0, 13, 16, 0, 0, 0, 1, 0, 0, 64, 0, 0, 0, 64, 0],
+        ),
+        (
+            Message::new_allowed_fast(2),
+            vec![0, 0, 0, 5, 17, 0, 0, 0, 2],
+        ),
+    ];
+
+    for (message, bytes) in messages {
+        let name = message.get_name().to_string();
+        let message_as_bytes = message.into_bytes();
+
+        assert_eq!(message_as_bytes, bytes, "{name}");
+        assert_eq!(
+            Message::from_bytes(message_as_bytes).unwrap().get_name(),
+            name
+        );
+    }
+
+    // Malformed payloads are errors, not panics.
+    for bytes in [
+        vec![0, 0, 0, 2, 13, 7],
+        vec![0, 0, 0, 5, 16, 0, 0, 0, 1],
+        vec![0, 0, 0, 1, 17],
+    ] {
+        assert!(Message::from_bytes(bytes).is_err());
+    }
+}
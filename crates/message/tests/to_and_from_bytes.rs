@@ -7,4 +7,18 @@ fn to_and_from_bytes() {
     let message_from_bytes = message::Message::from_bytes(message_as_bytes).unwrap();
 
     assert_eq!(message::Message::new_unchoke(), message_from_bytes);
+
+    // Short payloads and oversized lengths are errors, not panics.
+    for bytes in [
+        vec![0, 0],
+        vec![0, 0, 0, 1],
+        vec![0, 0, 0, 3, 4, 0, 0],
+        vec![0, 0, 0, 5, 6, 0, 0, 0, 1],
+        vec![0, 0, 0, 5, 7, 0, 0, 0, 1],
+        vec![0, 0, 0, 5, 8, 0, 0, 0, 1],
+        vec![0, 0, 0, 2, 9, 1],
+        vec![0, 2, 0, 14, 7, 0, 0, 0, 0, 0, 0, 0, 0],
+    ] {
+        assert!(message::Message::from_bytes(bytes).is_err());
+    }
 }
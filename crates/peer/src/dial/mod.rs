@@ -0,0 +1,80 @@
+use anyhow::{anyhow, Result};
+use async_std::channel;
+use async_std::future::timeout;
+use async_std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// How long a connection attempt gets before the next address is tried alongside it.
+pub const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Connect to the first address that answers, happy eyeballs style (RFC 8305).
+///
+/// Addresses are tried in order, alternating families starting with IPv6, with a new
+/// attempt started whenever the last one fails or takes longer than `CONNECTION_ATTEMPT_DELAY`.
+/// This keeps an unreachable family from holding up the connection.
+///
+/// Returns the address connected to, and the connection.
+///
+/// # Arguments
+///
+/// * `addrs` - addresses of the peer.
+pub async fn dial(addrs: &[SocketAddr]) -> Result<(SocketAddr, TcpStream)> {
+    let (sender, receiver) = channel::unbounded();
+    let mut pending = 0;
+    let mut last_error = anyhow!("No addresses to connect to");
+
+    for addr in interleave(addrs) {
+        let sender = sender.clone();
+        async_std::task::spawn(async move {
+            sender
+                .send((addr, TcpStream::connect(addr).await))
+                .await
+                .ok();
+        });
+        pending += 1;
+
+        // Wait for an answer before starting the next attempt, but not for too long.
+        if let Ok(answer) = timeout(CONNECTION_ATTEMPT_DELAY, receiver.recv()).await {
+            let (addr, result) = answer?;
+            pending -= 1;
+
+            match result {
+                Ok(stream) => return Ok((addr, stream)),
+                Err(e) => last_error = e.into(),
+            }
+        }
+    }
+
+    // Every attempt is started, wait for the rest of them.
+    while pending > 0 {
+        let (addr, result) = receiver.recv().await?;
+        pending -= 1;
+
+        match result {
+            Ok(stream) => return Ok((addr, stream)),
+            Err(e) => last_error = e.into(),
+        }
+    }
+
+    Err(last_error)
+}
+
+/// Order addresses by alternating families, starting with IPv6, keeping the order within a family.
+///
+/// # Arguments
+///
+/// * `addrs` - addresses to order.
+fn interleave(addrs: &[SocketAddr]) -> Vec<SocketAddr> {
+    let (mut ipv6, mut ipv4): (Vec<SocketAddr>, Vec<SocketAddr>) =
+        addrs.iter().partition(|addr| addr.is_ipv6());
+    let mut out = vec![];
+
+    ipv6.reverse();
+    ipv4.reverse();
+    while !ipv6.is_empty() || !ipv4.is_empty() {
+        out.extend(ipv6.pop());
+        out.extend(ipv4.pop());
+    }
+
+    out
+}
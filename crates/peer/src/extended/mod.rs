@@ -0,0 +1,127 @@
+mod registry;
+
+pub use registry::{Extension, ExtensionRegistry};
+
+use super::Peer;
+use crate::Reserved;
+use anyhow::Result;
+use bcode::{BDecode, BEncode, DecodeOptions, Mode};
+use message::Message;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+/// Id of the extended handshake, within extended messages.
+pub const EXTENDED_HANDSHAKE_ID: u8 = 0;
+
+/// Limits for decoding the bencoded part of extended messages, which come from peers.
+pub(crate) const DECODE_LIMITS: DecodeOptions = DecodeOptions {
+    mode: Mode::Lenient,
+    max_depth: 8,
+    max_size: 1 << 16,
+    max_string_length: 1 << 15,
+    max_entries: 1024,
+};
+
+/// The extended handshake (BEP 10), telling the other peer which extensions are supported.
+#[derive(Debug, Clone, Default, BEncode, BDecode)]
+pub struct ExtendedHandshake {
+    /// Extension names, mapped to the ids they should be sent with (`0` disables it).
+    #[bcode(default)]
+    pub m: BTreeMap<String, i64>,
+    /// Client name and version.
+    pub v: Option<String>,
+    /// Port the client listens on.
+    pub p: Option<i64>,
+    /// Amount of outstanding requests the client allows.
+    pub reqq: Option<i64>,
+    /// IP address of the receiving peer, as seen by the client.
+    #[bcode(bytes)]
+    pub yourip: Option<Vec<u8>>,
+    /// Size of the info dictionary, if known.
+    pub metadata_size: Option<i64>,
+    /// IPv4 address of the client, if it has one.
+    #[bcode(bytes)]
+    pub ipv4: Option<Vec<u8>>,
+    /// IPv6 address of the client, if it has one.
+    #[bcode(bytes)]
+    pub ipv6: Option<Vec<u8>>,
+    #[bcode(unknown)]
+    pub extra: BTreeMap<Vec<u8>, bcode::Value>,
+}
+
+impl ExtendedHandshake {
+    /// Returns `yourip` as an address, if it's a valid IPv4 or IPv6 address.
+    pub fn yourip_addr(&self) -> Option<IpAddr> {
+        to_ip(self.yourip.as_deref()?)
+    }
+
+    /// Returns the addresses the client says it has, `ipv4` and `ipv6`.
+    pub fn own_addrs(&self) -> Vec<IpAddr> {
+        [self.ipv4.as_deref(), self.ipv6.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter_map(to_ip)
+            .collect()
+    }
+}
+
+/// Convert a compact IPv4 or IPv6 address.
+///
+/// # Arguments
+///
+/// * `bytes` - 4 or 16 bytes.
+fn to_ip(bytes: &[u8]) -> Option<IpAddr> {
+    match bytes.len() {
+        4 => Some(IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        16 => Some(IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+        _ => None,
+    }
+}
+
+impl Peer {
+    /// Returns `true` if both we and the peer set a bit in the handshake.
+    ///
+    /// # Arguments
+    ///
+    /// * `bit` - reserved bit to check, e.g. `Reserved::EXTENSION_PROTOCOL`.
+    pub fn supports(&self, bit: Reserved) -> bool {
+        (self.reserved & self.peer_reserved).contains(bit)
+    }
+
+    /// Send the extended handshake, advertising the registered extensions.
+    pub async fn send_extended_handshake(&self) -> Result<()> {
+        let mut handshake = self.extensions.handshake();
+        handshake.yourip = Some(match self.ip {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        });
+
+        self.send_message(Message::new_extended(
+            EXTENDED_HANDSHAKE_ID,
+            bcode::encode(handshake.bencode())?,
+        ))
+        .await
+    }
+
+    /// Handle the payload of an extended message, and send the replies of the extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - payload of the extended message, starting with the extended id.
+    pub async fn handle_extended(&mut self, payload: &[u8]) -> Result<()> {
+        for message in self.extensions.handle(payload)? {
+            self.send_message(message).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Send the periodic messages of the extensions.
+    pub async fn tick_extensions(&mut self) -> Result<()> {
+        for message in self.extensions.tick()? {
+            self.send_message(message).await?;
+        }
+
+        Ok(())
+    }
+}
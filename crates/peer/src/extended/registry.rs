@@ -0,0 +1,244 @@
+use super::*;
+use anyhow::anyhow;
+use std::any::Any;
+use std::fmt;
+
+/// An extension of the extension protocol, like `ut_metadata` or `ut_pex`.
+///
+/// Handlers return payloads to send to the peer, which are sent with the id the peer picked.
+pub trait Extension: ExtensionClone + Any + Send + Sync {
+    /// Name of the extension in the `m` dictionary.
+    fn name(&self) -> &'static str;
+
+    /// Add fields to our extended handshake.
+    ///
+    /// # Arguments
+    ///
+    /// * `handshake` - handshake to add to.
+    fn extend_handshake(&self, _handshake: &mut ExtendedHandshake) {}
+
+    /// Handle the extended handshake of a peer supporting the extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `handshake` - the peer's handshake.
+    fn on_handshake(&mut self, _handshake: &ExtendedHandshake) -> Result<Vec<Vec<u8>>> {
+        Ok(vec![])
+    }
+
+    /// Handle a message for the extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - payload of the extended message, without the extended id.
+    fn on_message(&mut self, payload: &[u8]) -> Result<Vec<Vec<u8>>>;
+
+    /// Called regularly for extensions the peer supports, e.g. to send periodic messages.
+    fn on_tick(&mut self) -> Result<Vec<Vec<u8>>> {
+        Ok(vec![])
+    }
+
+    /// Returns the extension as `Any`, so it can be downcast.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Clone a boxed extension, implemented for all extensions that are `Clone`.
+pub trait ExtensionClone {
+    fn clone_box(&self) -> Box<dyn Extension>;
+}
+
+impl<T: Extension + Clone> ExtensionClone for T {
+    fn clone_box(&self) -> Box<dyn Extension> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Extension> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// The extensions of a connection, and the ids both sides picked for them.
+///
+/// Our id of an extension is its position in the registry, starting at `1`.
+#[derive(Clone, Default)]
+pub struct ExtensionRegistry {
+    extensions: Vec<Box<dyn Extension>>,
+    remote_ids: BTreeMap<String, u8>,
+    /// The last extended handshake of the peer.
+    pub remote_handshake: Option<ExtendedHandshake>,
+    /// Port we listen on, sent as `p`.
+    pub listen_port: Option<u16>,
+}
+
+impl ExtensionRegistry {
+    /// Create an empty registry.
+    pub fn new() -> ExtensionRegistry {
+        ExtensionRegistry::default()
+    }
+
+    /// Add an extension, replacing one with the same name, and return our id for it.
+    ///
+    /// # Arguments
+    ///
+    /// * `extension` - extension to add.
+    pub fn register<T: Extension>(&mut self, extension: T) -> u8 {
+        match self.local_id(extension.name()) {
+            Some(id) => {
+                self.extensions[id as usize - 1] = Box::new(extension);
+                id
+            }
+            None => {
+                self.extensions.push(Box::new(extension));
+                self.extensions.len() as u8
+            }
+        }
+    }
+
+    /// Returns our id of an extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name of the extension.
+    pub fn local_id(&self, name: &str) -> Option<u8> {
+        self.extensions
+            .iter()
+            .position(|extension| extension.name() == name)
+            .map(|index| index as u8 + 1)
+    }
+
+    /// Returns the peer's id of an extension, if it supports it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name of the extension.
+    pub fn remote_id(&self, name: &str) -> Option<u8> {
+        self.remote_ids.get(name).copied()
+    }
+
+    /// Get a registered extension by type.
+    pub fn get_mut<T: Extension>(&mut self) -> Option<&mut T> {
+        self.extensions
+            .iter_mut()
+            .find_map(|extension| extension.as_any_mut().downcast_mut::<T>())
+    }
+
+    /// Create our extended handshake.
+    pub fn handshake(&self) -> ExtendedHandshake {
+        let mut handshake = ExtendedHandshake {
+            m: self
+                .extensions
+                .iter()
+                .enumerate()
+                .map(|(index, extension)| (extension.name().to_string(), index as i64 + 1))
+                .collect(),
+            v: Some(format!("riptorrent {}", env!("CARGO_PKG_VERSION"))),
+            p: self.listen_port.map(i64::from),
+            reqq: Some(250),
+            ..Default::default()
+        };
+
+        for extension in &self.extensions {
+            extension.extend_handshake(&mut handshake);
+        }
+
+        handshake
+    }
+
+    /// Handle the payload of an extended message, and return the messages to send back.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - payload of the extended message, starting with the extended id.
+    pub fn handle(&mut self, payload: &[u8]) -> Result<Vec<Message>> {
+        let (extended_id, payload) = payload
+            .split_first()
+            .ok_or_else(|| anyhow!("Missing extended message id"))?;
+
+        if *extended_id == EXTENDED_HANDSHAKE_ID {
+            let value = bcode::Decoder::with_options(payload, DECODE_LIMITS).decode()?;
+            let handshake = ExtendedHandshake::bdecode(value)?;
+
+            // Later handshakes only update the extensions they mention.
+            for (name, id) in &handshake.m {
+                match u8::try_from(*id) {
+                    Ok(0) | Err(_) => self.remote_ids.remove(name),
+                    Ok(id) => self.remote_ids.insert(name.clone(), id),
+                };
+            }
+
+            let mut out = vec![];
+            for index in 0..self.extensions.len() {
+                let name = self.extensions[index].name();
+
+                if let Some(remote_id) = self.remote_id(name) {
+                    let payloads = self.extensions[index].on_handshake(&handshake)?;
+                    out.extend(self.wrap(remote_id, payloads));
+                }
+            }
+
+            self.remote_handshake = Some(handshake);
+
+            return Ok(out);
+        }
+
+        let extension = self
+            .extensions
+            .get_mut(*extended_id as usize - 1)
+            .ok_or_else(|| anyhow!("Unknown extended message id {extended_id}"))?;
+        let name = extension.name();
+        let payloads = extension.on_message(payload)?;
+
+        match self.remote_id(name) {
+            Some(remote_id) => Ok(self.wrap(remote_id, payloads)),
+            None if payloads.is_empty() => Ok(vec![]),
+            None => Err(anyhow!("Peer doesn't support \"{name}\"")),
+        }
+    }
+
+    /// Let the extensions the peer supports send periodic messages.
+    pub fn tick(&mut self) -> Result<Vec<Message>> {
+        let mut out = vec![];
+
+        for index in 0..self.extensions.len() {
+            if let Some(remote_id) = self.remote_id(self.extensions[index].name()) {
+                let payloads = self.extensions[index].on_tick()?;
+                out.extend(self.wrap(remote_id, payloads));
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Wrap payloads in extended messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `remote_id` - the peer's id of the extension.
+    /// * `payloads` - payloads to wrap.
+    fn wrap(&self, remote_id: u8, payloads: Vec<Vec<u8>>) -> Vec<Message> {
+        payloads
+            .into_iter()
+            .map(|payload| Message::new_extended(remote_id, payload))
+            .collect()
+    }
+}
+
+impl fmt::Debug for ExtensionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionRegistry")
+            .field(
+                "extensions",
+                &self
+                    .extensions
+                    .iter()
+                    .map(|extension| extension.name())
+                    .collect::<Vec<_>>(),
+            )
+            .field("remote_ids", &self.remote_ids)
+            .field("remote_handshake", &self.remote_handshake)
+            .field("listen_port", &self.listen_port)
+            .finish()
+    }
+}
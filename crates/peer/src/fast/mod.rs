@@ -0,0 +1,91 @@
+use super::Peer;
+use crate::Reserved;
+use anyhow::Result;
+use message::Message;
+use std::collections::BTreeSet;
+
+impl Peer {
+    /// Send the pieces we have, as "have all" or "have none" if possible (BEP 6), otherwise as a bitfield.
+    ///
+    /// # Arguments
+    ///
+    /// * `bitfield` - pieces we have.
+    /// * `piece_amount` - amount of pieces in the torrent.
+    pub async fn send_pieces(&self, bitfield: &[u8], piece_amount: usize) -> Result<()> {
+        let message = if !self.supports(Reserved::FAST) {
+            Message::new_bitfield(bitfield.to_vec())
+        } else if bitfield.iter().all(|byte| *byte == 0) {
+            Message::new_have_none()
+        } else if bitfield == full_bitfield(piece_amount) {
+            Message::new_have_all()
+        } else {
+            Message::new_bitfield(bitfield.to_vec())
+        };
+
+        self.send_message(message).await
+    }
+
+    /// Tell the peer a request won't be served (BEP 6), without the fast extension it's just ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - piece index.
+    /// * `begin` - byte offset within the piece.
+    /// * `length` - length from byte offset.
+    pub async fn reject_request(&self, index: u32, begin: u32, length: u32) -> Result<()> {
+        if self.supports(Reserved::FAST) {
+            self.send_message(Message::new_reject_request(index, begin, length))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the pieces of a bitfield we may request right now,
+    /// only the allowed fast pieces while the peer chokes us.
+    ///
+    /// # Arguments
+    ///
+    /// * `bitfield` - pieces the peer has.
+    pub fn requestable_pieces(&self, bitfield: &[u8]) -> Vec<u8> {
+        match self.peer_choking {
+            true => mask(bitfield, &self.allowed_fast),
+            false => bitfield.to_vec(),
+        }
+    }
+}
+
+/// Create a bitfield with every piece set.
+///
+/// # Arguments
+///
+/// * `piece_amount` - amount of pieces in the torrent.
+pub fn full_bitfield(piece_amount: usize) -> Vec<u8> {
+    let mut bitfield = vec![0xff; piece_amount.div_ceil(8)];
+
+    if let Some(last) = bitfield.last_mut() {
+        *last <<= (8 - piece_amount % 8) % 8;
+    }
+
+    bitfield
+}
+
+/// Keep only the given pieces of a bitfield.
+///
+/// # Arguments
+///
+/// * `bitfield` - bitfield to mask.
+/// * `pieces` - piece indices to keep.
+pub(crate) fn mask(bitfield: &[u8], pieces: &BTreeSet<u32>) -> Vec<u8> {
+    let mut out = vec![0; bitfield.len()];
+
+    for piece in pieces {
+        let (byte, bit) = (*piece as usize / 8, 7 - *piece as usize % 8);
+
+        if let Some(&have) = bitfield.get(byte) {
+            out[byte] |= have & (1 << bit);
+        }
+    }
+
+    out
+}
@@ -1,4 +1,5 @@
 use super::Peer;
+use crate::Reserved;
 
 use anyhow::{anyhow, Result};
 
@@ -10,9 +11,12 @@ impl Peer {
     /// * `stream` - `TcpStream`.
     /// * `info_hash` - info hash of the torrent.
     /// * `peer_id` - this clients peer id.
-    pub async fn handshake(&self, info_hash: &mut Vec<u8>, id: &mut Vec<u8>) -> Result<()> {
+    pub async fn handshake(&mut self, info_hash: &mut Vec<u8>, id: &mut Vec<u8>) -> Result<()> {
         self.send_handshake(info_hash, id).await?;
-        self.read_handshake().await?;
+        let handshake = self.read_handshake().await?;
+
+        let reserved = &handshake[handshake.len() - 48..handshake.len() - 40];
+        self.peer_reserved = Reserved(reserved.try_into()?);
 
         Ok(())
     }
@@ -28,7 +32,7 @@ impl Peer {
         let mut handshake = vec![];
         handshake.push(19_u8);
         handshake.append(&mut b"BitTorrent protocol".to_vec());
-        handshake.append(&mut vec![0; 8]);
+        handshake.extend_from_slice(&self.reserved.0);
         handshake.append(info_hash);
         handshake.append(id);
 
@@ -1,13 +1,29 @@
+mod dial;
+mod extended;
+mod fast;
 mod handshake;
+mod listener;
+mod metadata;
 mod peer;
+mod pex;
+mod pool;
 mod read;
+mod reserved;
 mod send;
 mod setup;
 mod start;
+mod stats;
 
-pub use handshake::*;
+pub use dial::{dial, CONNECTION_ATTEMPT_DELAY};
+pub use extended::{ExtendedHandshake, Extension, ExtensionRegistry, EXTENDED_HANDSHAKE_ID};
+pub use fast::full_bitfield;
+pub use listener::Listener;
+pub use metadata::{MetadataMessage, UtMetadata, METADATA_PIECE_SIZE};
 pub use peer::*;
-pub use read::*;
-pub use send::*;
-pub use setup::*;
-pub use start::*;
+pub use pex::{
+    PexMessage, UtPex, PEX_PREFERS_ENCRYPTION, PEX_REACHABLE, PEX_SEED, PEX_SUPPORTS_HOLEPUNCH,
+    PEX_SUPPORTS_UTP,
+};
+pub use pool::{PeerPool, MAX_KNOWN_PEERS, MAX_QUEUED_PEERS};
+pub use reserved::Reserved;
+pub use stats::TransferStats;
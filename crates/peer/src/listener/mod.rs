@@ -0,0 +1,112 @@
+use crate::{Peer, Reserved};
+use anyhow::{anyhow, Result};
+use async_std::channel::{self, Receiver, Sender};
+use async_std::future::timeout;
+use async_std::net::{SocketAddr, TcpListener, TcpStream};
+use async_std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How long an incoming connection has to send its handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Accepts incoming connections, and hands them to the torrent they handshake for.
+#[derive(Debug)]
+pub struct Listener {
+    listener: TcpListener,
+    id: Vec<u8>,
+    /// Torrents accepting peers, by info hash.
+    torrents: std::sync::Mutex<HashMap<Vec<u8>, Sender<Peer>>>,
+}
+
+impl Listener {
+    /// Listen for incoming connections.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address to listen on, its port is the one to announce.
+    /// * `id` - this clients peer id.
+    pub async fn bind(addr: SocketAddr, id: Vec<u8>) -> Result<Arc<Listener>> {
+        Ok(Arc::new(Listener {
+            listener: TcpListener::bind(addr).await?,
+            id,
+            torrents: std::sync::Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// Returns the address the listener is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accept peers for a torrent.
+    ///
+    /// Returns the peers connecting to it, handshake done, ready for `Peer::setup_session`.
+    ///
+    /// # Arguments
+    ///
+    /// * `info_hash` - info hash of the torrent.
+    pub fn add_torrent(&self, info_hash: Vec<u8>) -> Receiver<Peer> {
+        let (sender, receiver) = channel::unbounded();
+        self.torrents.lock().unwrap().insert(info_hash, sender);
+
+        receiver
+    }
+
+    /// Stop accepting peers for a torrent.
+    ///
+    /// # Arguments
+    ///
+    /// * `info_hash` - info hash of the torrent.
+    pub fn remove_torrent(&self, info_hash: &[u8]) {
+        self.torrents.lock().unwrap().remove(info_hash);
+    }
+
+    /// Accept connections forever, handshaking with each in its own task.
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            let Ok((stream, addr)) = self.listener.accept().await else {
+                continue;
+            };
+            let listener = self.clone();
+
+            async_std::task::spawn(async move {
+                timeout(HANDSHAKE_TIMEOUT, listener.accept(stream, addr)).await?
+            });
+        }
+    }
+
+    /// Handshake with an incoming connection, and pass it on to its torrent.
+    ///
+    /// Connections for unknown torrents are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - the connection.
+    /// * `addr` - address of the peer.
+    async fn accept(&self, stream: TcpStream, addr: SocketAddr) -> Result<()> {
+        // IPv4 peers reach a dual-stack listener as IPv4-mapped IPv6 addresses.
+        let mut peer = Peer::new(None, addr.ip().to_canonical(), addr.port());
+        peer.stream = Some(Arc::new(Mutex::new(stream)));
+
+        let handshake = peer.read_handshake().await?;
+        let len = handshake.len();
+        let info_hash = handshake[len - 40..len - 20].to_vec();
+        peer.peer_reserved = Reserved(handshake[len - 48..len - 40].try_into()?);
+        peer.id = Some(handshake[len - 20..].to_vec());
+
+        let sender = self
+            .torrents
+            .lock()
+            .unwrap()
+            .get(&info_hash)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown info hash"))?;
+
+        peer.send_handshake(&mut info_hash.clone(), &mut self.id.clone())
+            .await?;
+        sender.send(peer).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use super::*;
+use crate::Reserved;
+use message::Message;
+
+impl Peer {
+    /// Connect to the peer and download the info dictionary of a torrent.
+    ///
+    /// The info dictionary isn't checked against the info hash, see `Magnet::to_torrent`.
+    ///
+    /// # Arguments
+    ///
+    /// * `info_hash` - info hash of the torrent.
+    /// * `id` - this clients peer id.
+    pub async fn fetch_metadata(&mut self, info_hash: &[u8], id: &[u8]) -> Result<Vec<u8>> {
+        self.reserved = self.reserved | Reserved::EXTENSION_PROTOCOL;
+        self.extensions.register(UtMetadata::new(None));
+
+        self.open_stream().await?;
+        self.handshake(&mut info_hash.to_vec(), &mut id.to_vec())
+            .await?;
+
+        if !self.supports(Reserved::EXTENSION_PROTOCOL) {
+            return Err(anyhow!("Peer doesn't support the extension protocol"));
+        }
+
+        self.send_extended_handshake().await?;
+
+        loop {
+            if let Message::Extended((_, payload)) = self.read_message().await? {
+                self.handle_extended(&payload).await?;
+            }
+
+            let ut_metadata = self
+                .extensions
+                .get_mut::<UtMetadata>()
+                .ok_or_else(|| anyhow!("Missing \"ut_metadata\" extension"))?;
+
+            if let Some(metadata) = ut_metadata.metadata() {
+                return Ok(metadata.to_vec());
+            }
+        }
+    }
+}
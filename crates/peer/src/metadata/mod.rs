@@ -0,0 +1,227 @@
+mod fetch;
+
+use super::Peer;
+use crate::extended::DECODE_LIMITS;
+use crate::{ExtendedHandshake, Extension};
+use anyhow::{anyhow, Result};
+use async_std::sync::Arc;
+use bcode::{BDecode, BEncode, StreamDecoder};
+use std::any::Any;
+
+/// Size of a metadata piece, only the last piece may be smaller.
+pub const METADATA_PIECE_SIZE: usize = 1 << 14;
+
+/// Largest info dictionary accepted from peers.
+const MAX_METADATA_SIZE: usize = 1 << 24;
+
+/// A `ut_metadata` message (BEP 9).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataMessage {
+    Request {
+        piece: usize,
+    },
+    Data {
+        piece: usize,
+        total_size: usize,
+        data: Vec<u8>,
+    },
+    Reject {
+        piece: usize,
+    },
+}
+
+/// Bencoded dictionary at the start of a `ut_metadata` message.
+#[derive(Debug, BEncode, BDecode)]
+struct Header {
+    msg_type: i64,
+    piece: i64,
+    total_size: Option<i64>,
+}
+
+impl MetadataMessage {
+    /// Converts a byte slice to a `MetadataMessage`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - payload of the extended message, without the extended id.
+    pub fn from_bytes(bytes: &[u8]) -> Result<MetadataMessage> {
+        // Data follows the dictionary directly.
+        let mut decoder = StreamDecoder::with_options(DECODE_LIMITS);
+        decoder.push(bytes)?;
+        let header = decoder
+            .next_value()?
+            .ok_or_else(|| anyhow!("Incomplete metadata message"))?;
+        let header = Header::bdecode(header)?;
+        let piece = usize::try_from(header.piece)?;
+
+        match header.msg_type {
+            0 => Ok(MetadataMessage::Request { piece }),
+            1 => Ok(MetadataMessage::Data {
+                piece,
+                total_size: usize::try_from(
+                    header
+                        .total_size
+                        .ok_or_else(|| anyhow!("Missing \"total_size\""))?,
+                )?,
+                data: decoder.take_remaining(),
+            }),
+            2 => Ok(MetadataMessage::Reject { piece }),
+            msg_type => Err(anyhow!("Unexpected metadata message type {msg_type}")),
+        }
+    }
+
+    /// Converts a `MetadataMessage` into a byte vector.
+    pub fn into_bytes(self) -> Result<Vec<u8>> {
+        let (header, data) = match self {
+            MetadataMessage::Request { piece } => (Header::new(0, piece, None), vec![]),
+            MetadataMessage::Data {
+                piece,
+                total_size,
+                data,
+            } => (Header::new(1, piece, Some(total_size)), data),
+            MetadataMessage::Reject { piece } => (Header::new(2, piece, None), vec![]),
+        };
+
+        let mut out = bcode::encode(header.bencode())?;
+        out.extend(data);
+
+        Ok(out)
+    }
+}
+
+impl Header {
+    fn new(msg_type: i64, piece: usize, total_size: Option<usize>) -> Header {
+        Header {
+            msg_type,
+            piece: piece as i64,
+            total_size: total_size.map(|x| x as i64),
+        }
+    }
+}
+
+/// The `ut_metadata` extension, downloading the info dictionary or serving ours.
+#[derive(Debug, Clone, Default)]
+pub struct UtMetadata {
+    /// Our info dictionary, served to peers asking for it.
+    metadata: Option<Arc<Vec<u8>>>,
+    /// Info dictionary being downloaded.
+    buffer: Vec<u8>,
+    /// Pieces not yet downloaded.
+    missing: Vec<usize>,
+}
+
+impl UtMetadata {
+    /// Create the extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `metadata` - our info dictionary, or `None` to download it from the peer.
+    pub fn new(metadata: Option<Arc<Vec<u8>>>) -> UtMetadata {
+        UtMetadata {
+            metadata,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the info dictionary, once it's downloaded (or if we had it).
+    pub fn metadata(&self) -> Option<Arc<Vec<u8>>> {
+        self.metadata.clone()
+    }
+
+    /// Answer a request for a piece of our metadata, rejecting it if we don't have it.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece` - index of the requested piece.
+    fn serve(&self, piece: usize) -> MetadataMessage {
+        let data = self.metadata.as_ref().and_then(|metadata| {
+            let start = piece.checked_mul(METADATA_PIECE_SIZE)?;
+            if start >= metadata.len() {
+                return None;
+            }
+            let end = metadata.len().min(start + METADATA_PIECE_SIZE);
+
+            Some((metadata.len(), metadata[start..end].to_vec()))
+        });
+
+        match data {
+            Some((total_size, data)) => MetadataMessage::Data {
+                piece,
+                total_size,
+                data,
+            },
+            None => MetadataMessage::Reject { piece },
+        }
+    }
+}
+
+impl Extension for UtMetadata {
+    fn name(&self) -> &'static str {
+        "ut_metadata"
+    }
+
+    fn extend_handshake(&self, handshake: &mut ExtendedHandshake) {
+        handshake.metadata_size = self.metadata.as_ref().map(|x| x.len() as i64);
+    }
+
+    fn on_handshake(&mut self, handshake: &ExtendedHandshake) -> Result<Vec<Vec<u8>>> {
+        // Request all pieces, unless we have the metadata or are already downloading it.
+        let Some(size) = handshake.metadata_size else {
+            return Ok(vec![]);
+        };
+        if self.metadata.is_some() || !self.buffer.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let size = usize::try_from(size)?;
+        if size == 0 || size > MAX_METADATA_SIZE {
+            return Err(anyhow!("Invalid metadata size {size}"));
+        }
+
+        self.buffer = vec![0; size];
+        self.missing = (0..size.div_ceil(METADATA_PIECE_SIZE)).collect();
+
+        self.missing
+            .iter()
+            .map(|piece| MetadataMessage::Request { piece: *piece }.into_bytes())
+            .collect()
+    }
+
+    fn on_message(&mut self, payload: &[u8]) -> Result<Vec<Vec<u8>>> {
+        match MetadataMessage::from_bytes(payload)? {
+            MetadataMessage::Request { piece } => Ok(vec![self.serve(piece).into_bytes()?]),
+            MetadataMessage::Data {
+                piece,
+                total_size,
+                data,
+            } => {
+                if !self.missing.contains(&piece) {
+                    return Ok(vec![]);
+                }
+
+                let start = piece * METADATA_PIECE_SIZE;
+                let expected = METADATA_PIECE_SIZE.min(total_size.saturating_sub(start));
+
+                if total_size != self.buffer.len() || data.len() != expected {
+                    return Err(anyhow!("Invalid metadata piece {piece}"));
+                }
+
+                self.buffer[start..start + data.len()].copy_from_slice(&data);
+                self.missing.retain(|x| *x != piece);
+
+                if self.missing.is_empty() {
+                    self.metadata = Some(Arc::new(std::mem::take(&mut self.buffer)));
+                }
+
+                Ok(vec![])
+            }
+            MetadataMessage::Reject { piece } => {
+                Err(anyhow!("Peer rejected metadata piece {piece}"))
+            }
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
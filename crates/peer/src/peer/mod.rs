@@ -1,6 +1,8 @@
+use crate::{dial, ExtensionRegistry, Reserved, TransferStats};
 use anyhow::Result;
 use async_std::net::{IpAddr, SocketAddr, TcpStream};
 use async_std::sync::{Arc, Mutex};
+use std::collections::BTreeSet;
 
 /// Struct representing a peer from a tracker response.
 #[derive(Debug, Clone)]
@@ -8,6 +10,8 @@ pub struct Peer {
     pub id: Option<Vec<u8>>,
     pub ip: IpAddr,
     pub port: u16,
+    /// Other addresses of the same peer, usually of the other IP family, raced when connecting.
+    pub alternatives: Vec<SocketAddr>,
 
     pub stream: Option<Arc<Mutex<TcpStream>>>,
     pub am_choking: bool,
@@ -15,6 +19,21 @@ pub struct Peer {
     pub peer_choking: bool,
     pub peer_interested: bool,
     pub bitfield: Option<Vec<u8>>,
+    /// Pieces we may request while the peer chokes us (BEP 6).
+    pub allowed_fast: BTreeSet<u32>,
+    /// Pieces the peer suggests we download first (BEP 6).
+    pub suggested: BTreeSet<u32>,
+    /// Amount of our finished pieces the peer knows about, see `Builder::get_completed`.
+    pub announced: usize,
+
+    /// Reserved bits we send in the handshake.
+    pub reserved: Reserved,
+    /// Reserved bits the peer sent in its handshake.
+    pub peer_reserved: Reserved,
+    /// Extensions of the extension protocol used with this peer.
+    pub extensions: ExtensionRegistry,
+    /// Bytes transferred, usually shared by every peer of the torrent.
+    pub stats: Arc<TransferStats>,
 }
 
 impl Peer {
@@ -23,20 +42,32 @@ impl Peer {
             id,
             ip,
             port,
+            alternatives: vec![],
             stream: None,
             am_choking: true,
             am_interested: false,
             peer_choking: true,
             peer_interested: false,
             bitfield: None,
+            allowed_fast: BTreeSet::new(),
+            suggested: BTreeSet::new(),
+            announced: 0,
+            reserved: Reserved::EXTENSION_PROTOCOL | Reserved::FAST,
+            peer_reserved: Reserved::default(),
+            extensions: ExtensionRegistry::new(),
+            stats: Arc::new(TransferStats::default()),
         }
     }
 
-    /// Open a `TcpStream`.
+    /// Open a `TcpStream`, to whichever address of the peer answers first.
     pub async fn open_stream(&mut self) -> Result<()> {
-        self.stream = Some(Arc::new(Mutex::new(
-            TcpStream::connect(SocketAddr::new(self.ip, self.port)).await?,
-        )));
+        let mut addrs = vec![SocketAddr::new(self.ip, self.port)];
+        addrs.extend_from_slice(&self.alternatives);
+
+        let (addr, stream) = dial(&addrs).await?;
+        self.ip = addr.ip();
+        self.port = addr.port();
+        self.stream = Some(Arc::new(Mutex::new(stream)));
 
         Ok(())
     }
@@ -0,0 +1,262 @@
+use crate::extended::DECODE_LIMITS;
+use crate::{ExtendedHandshake, Extension, PeerPool};
+use anyhow::Result;
+use bcode::{BDecode, BEncode};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Peer prefers encrypted connections.
+pub const PEX_PREFERS_ENCRYPTION: u8 = 0x01;
+/// Peer is a seed.
+pub const PEX_SEED: u8 = 0x02;
+/// Peer supports uTP.
+pub const PEX_SUPPORTS_UTP: u8 = 0x04;
+/// Peer supports holepunching.
+pub const PEX_SUPPORTS_HOLEPUNCH: u8 = 0x08;
+/// Peer is reachable from the outside.
+pub const PEX_REACHABLE: u8 = 0x10;
+
+/// Most peers added or dropped in a single message.
+const MAX_PEERS_PER_MESSAGE: usize = 50;
+
+/// Least time between two messages to the same peer.
+const INTERVAL: Duration = Duration::from_secs(60);
+
+/// A `ut_pex` message (BEP 11), with the peers connected and disconnected since the last one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PexMessage {
+    /// Peers connected to, with their flags.
+    pub added: Vec<(SocketAddr, u8)>,
+    /// Peers disconnected from.
+    pub dropped: Vec<SocketAddr>,
+}
+
+/// Bencoded `ut_pex` message, with compact peer lists.
+#[derive(Debug, Default, BEncode, BDecode)]
+struct RawPexMessage {
+    #[bcode(default, bytes)]
+    added: Vec<u8>,
+    #[bcode(rename = "added.f", default, bytes)]
+    added_f: Vec<u8>,
+    #[bcode(default, bytes)]
+    added6: Vec<u8>,
+    #[bcode(rename = "added6.f", default, bytes)]
+    added6_f: Vec<u8>,
+    #[bcode(default, bytes)]
+    dropped: Vec<u8>,
+    #[bcode(default, bytes)]
+    dropped6: Vec<u8>,
+}
+
+impl PexMessage {
+    /// Converts a byte slice to a `PexMessage`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - payload of the extended message, without the extended id.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PexMessage> {
+        let value = bcode::Decoder::with_options(bytes, DECODE_LIMITS).decode()?;
+        let raw = RawPexMessage::bdecode(value)?;
+
+        let with_flags = |addrs: Vec<SocketAddr>, flags: &[u8]| {
+            addrs
+                .into_iter()
+                .enumerate()
+                .map(|(i, addr)| (addr, flags.get(i).copied().unwrap_or(0)))
+                .collect::<Vec<_>>()
+        };
+
+        let mut added = with_flags(from_compact(&raw.added, 4), &raw.added_f);
+        added.extend(with_flags(from_compact(&raw.added6, 16), &raw.added6_f));
+
+        let mut dropped = from_compact(&raw.dropped, 4);
+        dropped.extend(from_compact(&raw.dropped6, 16));
+
+        Ok(PexMessage { added, dropped })
+    }
+
+    /// Converts a `PexMessage` into a byte vector.
+    pub fn into_bytes(self) -> Result<Vec<u8>> {
+        let mut raw = RawPexMessage::default();
+
+        for (addr, flags) in self.added {
+            match addr.ip() {
+                IpAddr::V4(_) => {
+                    raw.added.extend(to_compact(addr));
+                    raw.added_f.push(flags);
+                }
+                IpAddr::V6(_) => {
+                    raw.added6.extend(to_compact(addr));
+                    raw.added6_f.push(flags);
+                }
+            }
+        }
+
+        for addr in self.dropped {
+            match addr.ip() {
+                IpAddr::V4(_) => raw.dropped.extend(to_compact(addr)),
+                IpAddr::V6(_) => raw.dropped6.extend(to_compact(addr)),
+            }
+        }
+
+        bcode::encode(raw.bencode())
+    }
+}
+
+/// Convert an address to its compact form, the IP address followed by the port.
+///
+/// # Arguments
+///
+/// * `addr` - address to convert.
+fn to_compact(addr: SocketAddr) -> Vec<u8> {
+    let mut out = match addr.ip() {
+        IpAddr::V4(ip) => ip.octets().to_vec(),
+        IpAddr::V6(ip) => ip.octets().to_vec(),
+    };
+    out.extend_from_slice(&addr.port().to_be_bytes());
+
+    out
+}
+
+/// Convert compact addresses, ignoring trailing bytes.
+///
+/// # Arguments
+///
+/// * `bytes` - concatenated compact addresses.
+/// * `ip_length` - `4` for IPv4 or `16` for IPv6.
+fn from_compact(bytes: &[u8], ip_length: usize) -> Vec<SocketAddr> {
+    bytes
+        .chunks_exact(ip_length + 2)
+        .map(|chunk| {
+            let ip = match ip_length {
+                4 => IpAddr::from(<[u8; 4]>::try_from(&chunk[..4]).unwrap()),
+                _ => IpAddr::from(<[u8; 16]>::try_from(&chunk[..16]).unwrap()),
+            };
+            let port = u16::from_be_bytes([chunk[ip_length], chunk[ip_length + 1]]);
+
+            SocketAddr::new(ip, port)
+        })
+        .collect()
+}
+
+/// The `ut_pex` extension, sharing the peers of the pool with a peer and adding the peers it shares.
+///
+/// Must not be used for private torrents.
+#[derive(Debug, Clone)]
+pub struct UtPex {
+    pool: Arc<Mutex<PeerPool>>,
+    /// Address of the peer this connection is with, never sent to itself.
+    addr: SocketAddr,
+    /// Address the peer accepts connections on, from its extended handshake.
+    listen_addr: Option<SocketAddr>,
+    /// Peers the other peer has been told about.
+    sent: BTreeMap<SocketAddr, u8>,
+    last_sent: Option<Instant>,
+}
+
+impl UtPex {
+    /// Create the extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - peers of the torrent.
+    /// * `addr` - address of the peer this connection is with.
+    pub fn new(pool: Arc<Mutex<PeerPool>>, addr: SocketAddr) -> UtPex {
+        UtPex {
+            pool,
+            addr,
+            listen_addr: None,
+            sent: BTreeMap::new(),
+            last_sent: None,
+        }
+    }
+
+    /// Create a message with the changes since the last one, returns `None` if nothing changed.
+    pub fn update(&mut self) -> Option<PexMessage> {
+        let mut connected = self.pool.lock().unwrap().connected();
+        connected.remove(&self.addr);
+        if let Some(listen_addr) = &self.listen_addr {
+            connected.remove(listen_addr);
+        }
+
+        let added = connected
+            .iter()
+            .filter(|(addr, _)| !self.sent.contains_key(addr))
+            .take(MAX_PEERS_PER_MESSAGE)
+            .map(|(addr, flags)| (*addr, *flags))
+            .collect::<Vec<_>>();
+        let dropped = self
+            .sent
+            .keys()
+            .filter(|addr| !connected.contains_key(addr))
+            .take(MAX_PEERS_PER_MESSAGE)
+            .copied()
+            .collect::<Vec<_>>();
+
+        if added.is_empty() && dropped.is_empty() {
+            return None;
+        }
+
+        self.sent.extend(added.iter().copied());
+        for addr in &dropped {
+            self.sent.remove(addr);
+        }
+
+        Some(PexMessage { added, dropped })
+    }
+}
+
+impl Extension for UtPex {
+    fn name(&self) -> &'static str {
+        "ut_pex"
+    }
+
+    fn on_handshake(&mut self, handshake: &ExtendedHandshake) -> Result<Vec<Vec<u8>>> {
+        // Share the port the peer listens on, not the one it connected from.
+        if let Some(port) = handshake.p.and_then(|p| u16::try_from(p).ok()) {
+            let mut pool = self.pool.lock().unwrap();
+            self.listen_addr = pool.set_listen_port(self.addr, port);
+
+            // Its addresses of the other family are raced when connecting again.
+            if let Some(listen_addr) = self.listen_addr {
+                for ip in handshake.own_addrs() {
+                    pool.add_alternative(listen_addr, SocketAddr::new(ip, port));
+                }
+            }
+        }
+
+        Ok(vec![])
+    }
+
+    fn on_message(&mut self, payload: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let message = PexMessage::from_bytes(payload)?;
+        let mut pool = self.pool.lock().unwrap();
+
+        for (addr, flags) in message.added.into_iter().take(MAX_PEERS_PER_MESSAGE) {
+            pool.add(addr, flags);
+        }
+
+        Ok(vec![])
+    }
+
+    fn on_tick(&mut self) -> Result<Vec<Vec<u8>>> {
+        if self.last_sent.is_some_and(|x| x.elapsed() < INTERVAL) {
+            return Ok(vec![]);
+        }
+
+        match self.update() {
+            Some(message) => {
+                self.last_sent = Some(Instant::now());
+                Ok(vec![message.into_bytes()?])
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
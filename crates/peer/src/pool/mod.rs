@@ -0,0 +1,240 @@
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, VecDeque};
+use std::net::SocketAddr;
+
+/// Most peers kept in a pool, connected peers are never dropped.
+pub const MAX_KNOWN_PEERS: usize = 3000;
+
+/// Most peers waiting to be connected to.
+pub const MAX_QUEUED_PEERS: usize = 1000;
+
+/// Peers of a torrent, shared between its connections.
+///
+/// Peers found by trackers or peer exchange are queued until a connection is made to them.
+/// Past the limits the oldest queued peers are dropped, then the oldest unconnected ones.
+#[derive(Debug, Default)]
+pub struct PeerPool {
+    /// Every peer seen, with its peer exchange flags.
+    known: BTreeMap<SocketAddr, u8>,
+    /// Known peers, oldest first.
+    history: VecDeque<SocketAddr>,
+    /// Peers not connected to yet.
+    queue: VecDeque<SocketAddr>,
+    /// Peers with an open connection, with the address they accept connections on if known.
+    connected: BTreeMap<SocketAddr, Option<SocketAddr>>,
+    /// Whether the last peer taken was IPv6.
+    last_ipv6: bool,
+    /// Other addresses of peers, usually of the other IP family, to race when connecting.
+    alternatives: BTreeMap<SocketAddr, Vec<SocketAddr>>,
+}
+
+impl PeerPool {
+    /// Create an empty pool.
+    pub fn new() -> PeerPool {
+        PeerPool::default()
+    }
+
+    /// Add a peer, returns `false` if it was already known.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the peer.
+    /// * `flags` - peer exchange flags, `0` if unknown.
+    pub fn add(&mut self, addr: SocketAddr, flags: u8) -> bool {
+        if addr.port() == 0 || addr.ip().is_unspecified() {
+            return false;
+        }
+
+        if let Some(known) = self.known.get_mut(&addr) {
+            *known = flags;
+            return false;
+        }
+
+        self.remember(addr, flags);
+        self.queue.push_back(addr);
+        self.trim();
+
+        true
+    }
+
+    /// Add another address of a peer, which is then only connected to together with `addr`.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the peer.
+    /// * `alternative` - other address of the same peer.
+    pub fn add_alternative(&mut self, addr: SocketAddr, alternative: SocketAddr) {
+        if alternative == addr || alternative.port() == 0 || alternative.ip().is_unspecified() {
+            return;
+        }
+
+        let alternatives = self.alternatives.entry(addr).or_default();
+        if !alternatives.contains(&alternative) {
+            alternatives.push(alternative);
+        }
+
+        self.remember(alternative, 0);
+        self.queue.retain(|queued| *queued != alternative);
+        self.trim();
+    }
+
+    /// Returns the other addresses of a peer.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the peer.
+    pub fn alternatives(&self, addr: &SocketAddr) -> Vec<SocketAddr> {
+        self.alternatives.get(addr).cloned().unwrap_or_default()
+    }
+
+    /// Take the next peer to connect to.
+    ///
+    /// Families alternate while both are queued, starting with IPv6, so an unreachable
+    /// family only holds up half of the connection attempts.
+    pub fn take_next(&mut self) -> Option<SocketAddr> {
+        let index = self
+            .queue
+            .iter()
+            .position(|addr| addr.is_ipv6() != self.last_ipv6)
+            .unwrap_or(0);
+        let addr = self.queue.remove(index)?;
+        self.last_ipv6 = addr.is_ipv6();
+
+        Some(addr)
+    }
+
+    /// Mark a peer we connected to as connected.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the peer.
+    pub fn connect(&mut self, addr: SocketAddr) {
+        self.remember(addr, 0);
+        self.connected.insert(addr, Some(addr));
+        self.trim();
+    }
+
+    /// Mark a peer that connected to us as connected.
+    ///
+    /// It isn't shared until its listen port is known, as the port it connected from can't be dialed.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address the peer connected from.
+    pub fn connect_incoming(&mut self, addr: SocketAddr) {
+        self.connected.insert(addr, None);
+    }
+
+    /// Set the port a connected peer accepts connections on, e.g. from its extended handshake.
+    ///
+    /// Returns the address to reach the peer on, `None` if it isn't connected.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the connection.
+    /// * `port` - port the peer listens on.
+    pub fn set_listen_port(&mut self, addr: SocketAddr, port: u16) -> Option<SocketAddr> {
+        let listen_addr = SocketAddr::new(addr.ip(), port);
+        *self.connected.get_mut(&addr)? = Some(listen_addr);
+        self.remember(listen_addr, 0);
+        self.trim();
+
+        Some(listen_addr)
+    }
+
+    /// Mark a peer as disconnected.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the peer.
+    pub fn disconnect(&mut self, addr: SocketAddr) {
+        self.connected.remove(&addr);
+    }
+
+    /// Returns the addresses connected peers accept connections on, with their flags.
+    ///
+    /// Peers that connected to us without telling their listen port are left out.
+    pub fn connected(&self) -> BTreeMap<SocketAddr, u8> {
+        self.connected
+            .values()
+            .flatten()
+            .map(|addr| (*addr, self.known.get(addr).copied().unwrap_or(0)))
+            .collect()
+    }
+
+    /// Returns the amount of connected peers.
+    pub fn connected_amount(&self) -> usize {
+        self.connected.len()
+    }
+
+    /// Returns the amount of known peers.
+    pub fn known_amount(&self) -> usize {
+        self.known.len()
+    }
+
+    /// Returns the amount of peers waiting to be connected to.
+    pub fn queued_amount(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Add a peer to the known peers if it isn't yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the peer.
+    /// * `flags` - peer exchange flags.
+    fn remember(&mut self, addr: SocketAddr, flags: u8) {
+        if let Entry::Vacant(entry) = self.known.entry(addr) {
+            entry.insert(flags);
+            self.history.push_back(addr);
+        }
+    }
+
+    /// Drop the oldest queued peers past `MAX_QUEUED_PEERS`, then the oldest unconnected
+    /// peers past `MAX_KNOWN_PEERS`.
+    fn trim(&mut self) {
+        while self.queue.len() > MAX_QUEUED_PEERS {
+            if let Some(addr) = self.queue.pop_front() {
+                self.forget(addr);
+            }
+        }
+
+        let mut kept = 0;
+        while self.known.len() > MAX_KNOWN_PEERS && kept < self.history.len() {
+            let Some(addr) = self.history.pop_front() else {
+                break;
+            };
+
+            if self.is_connected(&addr) {
+                self.history.push_back(addr);
+                kept += 1;
+            } else {
+                self.forget(addr);
+            }
+        }
+    }
+
+    /// Returns whether a peer is connected, by its connection or listen address.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the peer.
+    fn is_connected(&self, addr: &SocketAddr) -> bool {
+        self.connected.contains_key(addr) || self.connected.values().any(|x| *x == Some(*addr))
+    }
+
+    /// Drop everything known about a peer.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the peer.
+    fn forget(&mut self, addr: SocketAddr) {
+        self.known.remove(&addr);
+        self.history.retain(|known| *known != addr);
+        self.queue.retain(|queued| *queued != addr);
+        self.alternatives.remove(&addr);
+        self.alternatives
+            .values_mut()
+            .for_each(|alternatives| alternatives.retain(|x| *x != addr));
+    }
+}
@@ -2,25 +2,24 @@ use super::Peer;
 
 use anyhow::{anyhow, Result};
 use async_std::io::ReadExt;
-use message::Message;
+use message::{Message, MAX_MESSAGE_LENGTH};
 
 impl Peer {
-    /// Read from the stream into the buffer, and return the number of bytes read.
+    /// Fill the buffer from the stream, and return the number of bytes read.
     ///
     /// # Arguments
     ///
     /// * `buf` - buffer to read to.
     pub async fn read_data(&self, buf: &mut [u8]) -> Result<usize> {
-        let bytes_read = self
-            .stream
+        self.stream
             .clone()
             .ok_or_else(|| anyhow!("Missing stream"))?
             .lock()
             .await
-            .read(buf)
+            .read_exact(buf)
             .await?;
 
-        Ok(bytes_read)
+        Ok(buf.len())
     }
 
     /// Read a `Message` from the stream.
@@ -32,6 +31,10 @@ impl Peer {
         entire_byte_message.append(&mut length_buf.to_vec());
 
         let length = u32::from_be_bytes(length_buf) as usize;
+        if length > MAX_MESSAGE_LENGTH {
+            return Err(anyhow!("Message length {length} is too large"));
+        }
+
         let mut buf = vec![0_u8; length];
         self.read_data(&mut buf).await?;
         entire_byte_message.append(&mut buf);
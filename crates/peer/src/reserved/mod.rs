@@ -0,0 +1,52 @@
+use std::ops::{BitAnd, BitOr};
+
+/// The eight reserved bytes of the handshake, where each bit announces support for an extension.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Reserved(pub [u8; 8]);
+
+impl Reserved {
+    /// Extension protocol (BEP 10).
+    pub const EXTENSION_PROTOCOL: Reserved = Reserved::bit(5, 0x10);
+    /// DHT (BEP 5).
+    pub const DHT: Reserved = Reserved::bit(7, 0x01);
+    /// Fast extension (BEP 6).
+    pub const FAST: Reserved = Reserved::bit(7, 0x04);
+
+    /// Create reserved bytes with a single bit set.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte` - index of the byte.
+    /// * `mask` - bit within the byte.
+    const fn bit(byte: usize, mask: u8) -> Reserved {
+        let mut out = [0; 8];
+        out[byte] = mask;
+
+        Reserved(out)
+    }
+
+    /// Returns `true` if all bits of `other` are set.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - bits to check.
+    pub fn contains(&self, other: Reserved) -> bool {
+        *self & other == other
+    }
+}
+
+impl BitOr for Reserved {
+    type Output = Reserved;
+
+    fn bitor(self, rhs: Reserved) -> Reserved {
+        Reserved(std::array::from_fn(|i| self.0[i] | rhs.0[i]))
+    }
+}
+
+impl BitAnd for Reserved {
+    type Output = Reserved;
+
+    fn bitand(self, rhs: Reserved) -> Reserved {
+        Reserved(std::array::from_fn(|i| self.0[i] & rhs.0[i]))
+    }
+}
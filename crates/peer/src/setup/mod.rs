@@ -1,23 +1,50 @@
 use super::Peer;
-use message::Message;
-
+use crate::Reserved;
 use anyhow::Result;
+use async_std::sync::{Arc, Mutex};
+use builder::Builder;
 
 impl Peer {
-    /// Setup connection, handshake and send bitfield.
+    /// Setup connection, handshake and send the pieces we have.
+    ///
+    /// # Arguments
+    ///
+    /// * `info_hash` - info hash of the torrent.
+    /// * `id` - this clients peer id.
+    /// * `builder` - blocks of the torrent.
     pub async fn setup(
         &mut self,
         info_hash: &mut Vec<u8>,
         id: &mut Vec<u8>,
-        piece_amount: usize,
+        builder: Arc<Mutex<Builder>>,
     ) -> Result<()> {
         self.open_stream().await?;
 
         self.handshake(info_hash, id).await?;
 
-        self.bitfield = Some(vec![0; piece_amount / 8]);
-        let bitfield_message = Message::new_bitfield(self.bitfield.clone().unwrap());
-        self.send_message(bitfield_message).await?;
+        self.setup_session(builder).await
+    }
+
+    /// Send the extended handshake and the pieces we have, once the handshake is done.
+    ///
+    /// Used directly for incoming connections, see `Listener`.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - blocks of the torrent.
+    pub async fn setup_session(&mut self, builder: Arc<Mutex<Builder>>) -> Result<()> {
+        if self.supports(Reserved::EXTENSION_PROTOCOL) {
+            self.send_extended_handshake().await?;
+        }
+
+        let (bitfield, piece_amount) = {
+            let builder = builder.lock().await;
+            self.announced = builder.get_completed().len();
+            (builder.get_bitfield(), builder.piece_amount)
+        };
+
+        self.bitfield = Some(vec![0; piece_amount.div_ceil(8)]);
+        self.send_pieces(&bitfield, piece_amount).await?;
 
         Ok(())
     }
@@ -1,9 +1,11 @@
 use super::Peer;
+use crate::fast::{full_bitfield, mask};
 use anyhow::{anyhow, Result};
 use arrayref::array_ref;
 use async_std::sync::{Arc, Mutex};
 use builder::{Block, Builder};
 use message::Message;
+use std::sync::atomic::Ordering;
 
 impl Peer {
     /// Start a communication loop with the peer.
@@ -23,43 +25,96 @@ impl Peer {
                 Message::KeepAlive => {}
                 Message::Choke(_) => self.peer_choking = true,
                 Message::Unchoke(_) => self.peer_choking = false,
-                Message::Interested(_) => self.peer_interested = true,
+                Message::Interested(_) => {
+                    self.peer_interested = true;
+
+                    // Upload to every interested peer.
+                    if self.am_choking {
+                        self.send_message(Message::new_unchoke()).await?;
+                        self.am_choking = false;
+                    }
+                }
                 Message::NotInterested(_) => self.peer_interested = false,
                 Message::Have((_, payload)) => {
-                    let piece_index = u32::from_be_bytes(*array_ref![payload, 0, 4]);
+                    let piece_index = u32::from_be_bytes(*array_ref![payload, 0, 4]) as usize;
+
+                    // The index comes from the peer, so drop it if it's out of range.
+                    if piece_index >= builder.lock().await.piece_amount {
+                        return Err(anyhow!("Invalid piece index {piece_index}"));
+                    }
 
-                    // TODO: Check that this actually works.
-                    let bitfield_y = piece_index as usize / 8;
-                    let bitfield_x = piece_index as usize % 8;
                     let bitfield = self
                         .bitfield
                         .as_mut()
                         .ok_or_else(|| anyhow!("Missing bitfield"))?;
-                    *bitfield.get_mut(bitfield_y).unwrap() =
-                        bitfield.get(bitfield_y).unwrap() ^ (1 << (7 - bitfield_x));
+                    let byte = bitfield
+                        .get_mut(piece_index / 8)
+                        .ok_or_else(|| anyhow!("Invalid piece index {piece_index}"))?;
+                    *byte |= 1 << (7 - piece_index % 8);
                 }
                 Message::Bitfield((_, payload)) => self.bitfield = Some(payload),
+                Message::HaveAll(_) => {
+                    self.bitfield = Some(full_bitfield(builder.lock().await.piece_amount));
+                }
+                Message::HaveNone(_) => {
+                    self.bitfield = Some(vec![0; builder.lock().await.piece_amount.div_ceil(8)]);
+                }
                 Message::Request((_, payload)) => {
-                    if !self.peer_interested || self.am_choking {
-                        //     break;
-                    }
-
                     let piece_index = u32::from_be_bytes(*array_ref![payload, 0, 4]);
+                    let piece_begin = u32::from_be_bytes(*array_ref![payload, 4, 4]);
+                    let piece_length = u32::from_be_bytes(*array_ref![payload, 8, 4]);
+
+                    let piece_block = match self.am_choking {
+                        true => None,
+                        false => builder
+                            .lock()
+                            .await
+                            .get_finished_block(
+                                piece_index as usize,
+                                piece_begin as usize,
+                                piece_length as usize,
+                            )
+                            .ok(),
+                    };
+
+                    match piece_block {
+                        Some(block) => {
+                            self.stats
+                                .uploaded
+                                .fetch_add(block.data.len() as i64, Ordering::Relaxed);
+                            self.send_message(Message::new_piece(
+                                piece_index,
+                                piece_begin,
+                                block.data,
+                            ))
+                            .await?
+                        }
+                        None => {
+                            self.reject_request(piece_index, piece_begin, piece_length)
+                                .await?
+                        }
+                    }
+                }
+                Message::RejectRequest((_, payload)) => {
+                    let piece_index = u32::from_be_bytes(*array_ref![payload, 0, 4]) as usize;
                     let piece_begin = u32::from_be_bytes(*array_ref![payload, 4, 4]) as usize;
-                    let piece_length = u32::from_be_bytes(*array_ref![payload, 4, 4]) as usize;
-
-                    let piece_block = builder
-                        .lock()
-                        .await
-                        .get_finished_block(piece_index as usize, piece_begin, piece_length)?
-                        .data;
-
-                    self.send_message(Message::new_piece(
-                        piece_index,
-                        piece_begin as u32,
-                        piece_block,
-                    ))
-                    .await?;
+
+                    // Return the block, so it can be requested from another peer.
+                    if let Some(index) = wanted_blocks
+                        .iter()
+                        .position(|x| x.index == piece_index && x.begin == piece_begin)
+                    {
+                        let block = wanted_blocks.swap_remove(index);
+                        builder.lock().await.add_missing_block(block)?;
+                    }
+                }
+                Message::SuggestPiece((_, payload)) => {
+                    self.suggested
+                        .insert(u32::from_be_bytes(*array_ref![payload, 0, 4]));
+                }
+                Message::AllowedFast((_, payload)) => {
+                    self.allowed_fast
+                        .insert(u32::from_be_bytes(*array_ref![payload, 0, 4]));
                 }
                 Message::Piece((_, payload)) => {
                     let piece_index = u32::from_be_bytes(*array_ref![payload, 0, 4]);
@@ -70,17 +125,26 @@ impl Peer {
 
                     let block = Block {
                         index: piece_index as usize,
-                        begin: piece_begin as usize,
+                        begin: piece_begin,
                         data: piece_data.to_vec(),
                     };
 
-                    if let Some((index, _)) =
-                        wanted_blocks.iter().enumerate().find(|x| x.1.eq(&block))
+                    if let Some(index) = wanted_blocks
+                        .iter()
+                        .position(|x| x.index == block.index && x.begin == block.begin)
                     {
                         wanted_blocks.swap_remove(index);
                     }
 
-                    builder.lock().await.add_finished_block(block)?;
+                    self.stats
+                        .downloaded
+                        .fetch_add(block.data.len() as i64, Ordering::Relaxed);
+
+                    let mut builder = builder.lock().await;
+                    builder.add_finished_block(block)?;
+                    self.stats
+                        .left
+                        .store(builder.get_left() as i64, Ordering::Relaxed);
 
                     //let finished = builder.lock().await.finished.len();
                     //let total = finished + builder.lock().await.missing.len();
@@ -88,41 +152,81 @@ impl Peer {
                     println!("got piece");
                 }
                 Message::Cancel(_) => {
-                    todo!()
+                    // Requests are answered as they arrive, so there's no queued upload to drop.
                 }
                 Message::Port(_) => {
                     // todo!()
                 }
+                Message::Extended((_, payload)) => {
+                    self.handle_extended(&payload).await?;
+                }
             };
 
+            self.tick_extensions().await?;
+            self.send_haves(&builder).await?;
+
             if let Some(bitfield) = &self.bitfield {
                 if wanted_blocks.len() < request_limit {
-                    if let Ok(t) = builder.lock().await.take_missing_relevant_block(bitfield) {
+                    // Suggested pieces first, and allowed fast pieces while choked.
+                    let requestable = self.requestable_pieces(bitfield);
+                    let suggested = mask(&requestable, &self.suggested);
+                    let mut builder = builder.lock().await;
+
+                    if let Ok(t) = builder
+                        .take_missing_relevant_block(&suggested)
+                        .or_else(|_| builder.take_missing_relevant_block(&requestable))
+                        .or_else(|_| builder.take_missing_relevant_block(bitfield))
+                    {
                         wanted_blocks.push(t);
                     }
+                }
 
-                    if !self.am_interested {
-                        self.send_message(Message::new_interested()).await?;
-                        self.send_message(Message::new_unchoke()).await?;
-                        self.am_interested = true;
-                    }
+                // Only ask to be unchoked if the peer has something we want.
+                if !wanted_blocks.is_empty() && !self.am_interested {
+                    self.send_message(Message::new_interested()).await?;
+                    self.am_interested = true;
                 }
 
-                if !wanted_blocks.is_empty() && !self.peer_choking {
+                let requestable = wanted_blocks.iter().find(|block| {
+                    !self.peer_choking || self.allowed_fast.contains(&(block.index as u32))
+                });
+
+                if let Some(block) = requestable {
                     self.send_message(Message::new_request(
-                        wanted_blocks.get(0).unwrap().index as u32,
-                        wanted_blocks.get(0).unwrap().begin as u32,
-                        wanted_blocks.get(0).unwrap().data.len() as u32,
+                        block.index as u32,
+                        block.begin as u32,
+                        block.data.len() as u32,
                     ))
                     .await?;
 
                     // TODO: Return block to pool if not found!
-                    // TODO: Send "have" when recieving a piece!
-                } else {
+                } else if !wanted_blocks.is_empty() {
                     async_std::task::sleep(std::time::Duration::from_secs(3)).await;
                     self.send_message(Message::new_keep_alive()).await?;
                 }
             }
         }
     }
+
+    /// Tell the peer about the pieces we finished since it last heard from us.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - blocks of the torrent.
+    async fn send_haves(&mut self, builder: &Arc<Mutex<Builder>>) -> Result<()> {
+        let completed = builder
+            .lock()
+            .await
+            .get_completed()
+            .get(self.announced..)
+            .unwrap_or_default()
+            .to_vec();
+
+        for index in completed {
+            self.send_message(Message::new_have(index as u32)).await?;
+            self.announced += 1;
+        }
+
+        Ok(())
+    }
 }
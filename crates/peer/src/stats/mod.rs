@@ -0,0 +1,12 @@
+use std::sync::atomic::AtomicI64;
+
+/// Bytes transferred for a torrent, shared by its connections and reported to trackers.
+#[derive(Debug, Default)]
+pub struct TransferStats {
+    /// Bytes of pieces sent to peers.
+    pub uploaded: AtomicI64,
+    /// Bytes of pieces received from peers.
+    pub downloaded: AtomicI64,
+    /// Bytes still missing.
+    pub left: AtomicI64,
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use async_std::future::timeout;
+use async_std::sync::{Arc, Mutex};
+use builder::Builder;
+use message::Message;
+use peer::{Listener, Peer};
+use std::time::Duration;
+
+#[async_std::test]
+async fn cancel_request() {
+    let info_hash = vec![b'i'; 20];
+    let listener = Listener::bind(([127, 0, 0, 1], 0).into(), vec![b's'; 20])
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+    let incoming = listener.add_torrent(info_hash.clone());
+    async_std::task::spawn(listener.run());
+
+    // Seed a completed torrent.
+    let mut seed = Builder::new(1, 1 << 14, 1 << 14);
+    seed.missing[0].data = vec![7; 1 << 14];
+    for block in std::mem::take(&mut seed.missing) {
+        seed.add_finished_block(block).unwrap();
+    }
+    let seed = Arc::new(Mutex::new(seed));
+    async_std::task::spawn(async move {
+        let mut peer = incoming.recv().await.unwrap();
+        peer.setup_session(seed.clone()).await?;
+        peer.start(seed).await
+    });
+
+    let builder = Arc::new(Mutex::new(Builder::new(1, 1 << 14, 1 << 14)));
+    let mut leecher = Peer::new(None, addr.ip(), addr.port());
+    leecher
+        .setup(&mut info_hash.clone(), &mut vec![b'c'; 20], builder)
+        .await
+        .unwrap();
+
+    leecher
+        .send_message(Message::new_interested())
+        .await
+        .unwrap();
+    let read_until = |wanted: &'static str| {
+        let leecher = &leecher;
+        async move {
+            loop {
+                let message = leecher.read_message().await.unwrap();
+                if message.get_name() == wanted {
+                    return message;
+                }
+            }
+        }
+    };
+    timeout(Duration::from_secs(5), read_until("Unchoke"))
+        .await
+        .unwrap();
+
+    // A cancel keeps the connection open, and later requests are still answered.
+    leecher
+        .send_message(Message::new_cancel(0, 0, 1 << 14))
+        .await
+        .unwrap();
+    leecher
+        .send_message(Message::new_request(0, 0, 4))
+        .await
+        .unwrap();
+    let piece = timeout(Duration::from_secs(5), read_until("Piece"))
+        .await
+        .unwrap();
+    assert_eq!(piece, Message::new_piece(0, 0, vec![7; 4]));
+}
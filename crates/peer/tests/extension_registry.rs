@@ -0,0 +1,91 @@
+mod common;
+
+use async_std::sync::Arc;
+use bcode::BEncode;
+use message::Message;
+use peer::{ExtendedHandshake, Extension, ExtensionRegistry, Peer, Reserved, UtMetadata};
+use std::any::Any;
+use std::collections::BTreeMap;
+
+/// Extension replying to every message with the same payload.
+#[derive(Clone)]
+struct Echo;
+
+impl Extension for Echo {
+    fn name(&self) -> &'static str {
+        "echo"
+    }
+
+    fn on_message(&mut self, payload: &[u8]) -> anyhow::Result<Vec<Vec<u8>>> {
+        Ok(vec![payload.to_vec()])
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[test]
+fn extension_registry() {
+    let mut registry = ExtensionRegistry::new();
+    registry.listen_port = Some(6881);
+    assert_eq!(
+        registry.register(UtMetadata::new(Some(Arc::new(vec![0; 100])))),
+        1
+    );
+    assert_eq!(registry.register(Echo), 2);
+    assert_eq!(registry.register(Echo), 2);
+
+    // Our handshake lists the extensions with our ids.
+    let handshake = registry.handshake();
+    assert_eq!(handshake.m.get("ut_metadata"), Some(&1));
+    assert_eq!(handshake.m.get("echo"), Some(&2));
+    assert_eq!(handshake.p, Some(6881));
+    assert_eq!(handshake.metadata_size, Some(100));
+    assert!(handshake.v.unwrap().starts_with("riptorrent"));
+
+    // Messages before the peer's handshake can't be answered.
+    assert!(registry.handle(&[2, b'x']).is_err());
+
+    // The peer picks its own ids.
+    let remote = ExtendedHandshake {
+        m: BTreeMap::from([("echo".to_string(), 7), ("ut_pex".to_string(), 3)]),
+        yourip: Some(vec![10, 0, 0, 1]),
+        ..Default::default()
+    };
+    let mut payload = vec![0];
+    payload.extend(bcode::encode(remote.bencode()).unwrap());
+    assert!(registry.handle(&payload).unwrap().is_empty());
+    assert_eq!(registry.remote_id("echo"), Some(7));
+    assert_eq!(registry.remote_id("ut_metadata"), None);
+    assert_eq!(
+        registry.remote_handshake.as_ref().unwrap().yourip_addr(),
+        Some([10, 0, 0, 1].into())
+    );
+
+    // Replies are sent with the peer's id.
+    assert_eq!(
+        registry.handle(&[2, b'h', b'i']).unwrap(),
+        vec![Message::new_extended(7, b"hi".to_vec())]
+    );
+
+    // A later handshake can disable an extension.
+    let mut payload = vec![0];
+    payload.extend_from_slice(b"d1:md4:echoi0eee");
+    registry.handle(&payload).unwrap();
+    assert_eq!(registry.remote_id("echo"), None);
+
+    // Handshakes with too many entries are rejected.
+    let mut payload = b"\x00d1:md".to_vec();
+    for index in 0..2000 {
+        payload.extend(format!("{}:x{index:05}i1e", 6).as_bytes());
+    }
+    payload.extend(b"ee");
+    assert!(registry.handle(&payload).is_err());
+
+    // Reserved bits are only used when both sides set them.
+    let mut peer = Peer::new(None, [127, 0, 0, 1].into(), 6881);
+    peer.peer_reserved = Reserved::EXTENSION_PROTOCOL | Reserved::DHT;
+    assert!(peer.supports(Reserved::EXTENSION_PROTOCOL));
+    assert!(!peer.supports(Reserved::DHT));
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use async_std::net::TcpListener;
+use async_std::sync::{Arc, Mutex};
+use builder::Builder;
+use message::Message;
+use peer::{full_bitfield, Peer, Reserved};
+
+#[async_std::test]
+async fn fast_extension() {
+    assert_eq!(full_bitfield(10), [0xff, 0xc0]);
+    assert_eq!(full_bitfield(16), [0xff, 0xff]);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Download from the remote peer in another task.
+    async_std::task::spawn(async move {
+        let builder = Arc::new(Mutex::new(Builder::new(2, 1 << 15, 1 << 14)));
+        let mut peer = Peer::new(None, addr.ip(), addr.port());
+
+        peer.setup(&mut vec![b'i'; 20], &mut vec![b'c'; 20], builder.clone())
+            .await?;
+        peer.start(builder).await
+    });
+
+    let (stream, _) = listener.accept().await.unwrap();
+    let mut remote = Peer::new(None, addr.ip(), addr.port());
+    remote.stream = Some(Arc::new(Mutex::new(stream)));
+    remote.reserved = Reserved::FAST;
+
+    let mut info_hash = remote.read_handshake().await.unwrap()[28..48].to_vec();
+    remote
+        .send_handshake(&mut info_hash, &mut vec![b'r'; 20])
+        .await
+        .unwrap();
+
+    // Nothing downloaded yet is sent as "have none" instead of a bitfield.
+    assert_eq!(
+        remote.read_message().await.unwrap(),
+        Message::new_have_none()
+    );
+
+    // Allowed fast pieces are requested while choked.
+    remote
+        .send_message(Message::new_allowed_fast(0))
+        .await
+        .unwrap();
+    remote.send_message(Message::new_have_all()).await.unwrap();
+    loop {
+        match remote.read_message().await.unwrap() {
+            Message::Request((_, payload)) => {
+                assert_eq!(payload, Message::new_request(0, 0, 1 << 14).get_payload());
+                break;
+            }
+            Message::Interested(_) | Message::Unchoke(_) | Message::KeepAlive => {}
+            message => panic!("unexpected {}", message.get_name()),
+        }
+    }
+
+    // Requests for pieces we don't have are rejected.
+    remote
+        .send_message(Message::new_request(1, 0, 1 << 14))
+        .await
+        .unwrap();
+    loop {
+        match remote.read_message().await.unwrap() {
+            Message::RejectRequest((_, payload)) => {
+                assert_eq!(
+                    payload,
+                    Message::new_reject_request(1, 0, 1 << 14).get_payload()
+                );
+                break;
+            }
+            Message::Request(_) | Message::KeepAlive => {}
+            message => panic!("unexpected {}", message.get_name()),
+        }
+    }
+}
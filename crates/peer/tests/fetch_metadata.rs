@@ -0,0 +1,81 @@
+mod common;
+
+use async_std::net::TcpListener;
+use async_std::sync::{Arc, Mutex};
+use message::Message;
+use peer::{Extension, MetadataMessage, Peer, UtMetadata};
+
+#[async_std::test]
+async fn fetch_metadata() {
+    // Metadata messages round-trip, with data after the dictionary.
+    let data = MetadataMessage::Data {
+        piece: 1,
+        total_size: 20000,
+        data: vec![1, 2, 3],
+    };
+    let bytes = data.clone().into_bytes().unwrap();
+    assert!(bytes.starts_with(b"d8:msg_typei1e5:piecei1e10:total_sizei20000ee"));
+    assert_eq!(MetadataMessage::from_bytes(&bytes).unwrap(), data);
+
+    // An info dictionary spanning three metadata pieces.
+    let mut info = b"d6:lengthi42e4:name4:test12:piece lengthi16384e6:pieces40000:".to_vec();
+    info.extend(vec![b'a'; 40000]);
+    info.push(b'e');
+    let info_hash = sha1_smol::Sha1::from(&info).digest().bytes().to_vec();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let metadata = Arc::new(info.clone());
+
+    // Requests past the end of the metadata are rejected, however large.
+    let mut served = UtMetadata::new(Some(metadata.clone()));
+    for piece in [2, 3, (1 << 50) - 1, i64::MAX as usize] {
+        let request = MetadataMessage::Request { piece }.into_bytes().unwrap();
+        let reply = served.on_message(&request).unwrap().remove(0);
+        let reply = MetadataMessage::from_bytes(&reply).unwrap();
+        assert_eq!(matches!(reply, MetadataMessage::Data { .. }), piece == 2);
+    }
+
+    // Serve the metadata from another task.
+    async_std::task::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut seeder = Peer::new(None, addr.ip(), addr.port());
+        seeder.stream = Some(Arc::new(Mutex::new(stream)));
+        seeder.extensions.register(UtMetadata::new(Some(metadata)));
+
+        let mut info_hash = seeder.read_handshake().await.unwrap()[28..48].to_vec();
+        seeder
+            .send_handshake(&mut info_hash, &mut vec![b's'; 20])
+            .await
+            .unwrap();
+        seeder.send_extended_handshake().await.unwrap();
+
+        while let Ok(message) = seeder.read_message().await {
+            if let Message::Extended((_, payload)) = message {
+                seeder.handle_extended(&payload).await.unwrap();
+            }
+        }
+    });
+
+    let mut peer = Peer::new(None, addr.ip(), addr.port());
+    let fetched = peer.fetch_metadata(&info_hash, &[b'c'; 20]).await.unwrap();
+    assert_eq!(fetched, info);
+
+    let magnet = format!(
+        "magnet:?xt=urn:btih:{}&tr=http%3A%2F%2Ftracker.example%2Fannounce",
+        bcode::to_hex(&info_hash)
+    )
+    .parse::<torrent::Magnet>()
+    .unwrap();
+    let torrent = magnet.to_torrent(&fetched).await.unwrap();
+    assert_eq!(torrent.info_hash, info_hash);
+    assert_eq!(
+        torrent.announce.as_deref(),
+        Some("http://tracker.example/announce")
+    );
+
+    // Metadata not matching the info hash is rejected.
+    let mut tampered = fetched;
+    tampered[10] = b'9';
+    assert!(magnet.to_torrent(&tampered).await.is_err());
+}
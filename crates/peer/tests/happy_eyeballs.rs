@@ -0,0 +1,69 @@
+mod common;
+
+use async_std::net::{SocketAddr, TcpListener};
+use peer::{ExtendedHandshake, Extension, Peer, PeerPool, UtPex};
+use std::net::Ipv6Addr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[async_std::test]
+async fn happy_eyeballs() {
+    let ipv4 = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let ipv4_addr = ipv4.local_addr().unwrap();
+
+    // A peer whose IPv6 address refuses connections is reached over IPv4 instead.
+    let closed = TcpListener::bind("[::1]:0").await.unwrap();
+    let closed_addr = closed.local_addr().unwrap();
+    drop(closed);
+
+    let mut peer = Peer::new(None, closed_addr.ip(), closed_addr.port());
+    peer.alternatives.push(ipv4_addr);
+    peer.open_stream().await.unwrap();
+    assert_eq!(SocketAddr::new(peer.ip, peer.port), ipv4_addr);
+
+    // IPv6 is preferred when both answer, without waiting on IPv4.
+    let ipv6 = TcpListener::bind("[::1]:0").await.unwrap();
+    let ipv6_addr = ipv6.local_addr().unwrap();
+
+    let (addr, _) = peer::dial(&[ipv4_addr, ipv6_addr]).await.unwrap();
+    assert_eq!(addr, ipv6_addr);
+
+    // Nothing to connect to.
+    let start = Instant::now();
+    assert!(peer::dial(&[closed_addr]).await.is_err());
+    assert!(peer::dial(&[]).await.is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // The pool alternates families too, starting with IPv6.
+    let mut pool = PeerPool::new();
+    for addr in ["1.1.1.1:1", "1.1.1.2:1", "[2001:db8::1]:1", "1.1.1.3:1"] {
+        pool.add(addr.parse().unwrap(), 0);
+    }
+
+    let order: Vec<String> = std::iter::from_fn(|| pool.take_next())
+        .map(|addr| addr.to_string())
+        .collect();
+    assert_eq!(
+        order,
+        ["[2001:db8::1]:1", "1.1.1.1:1", "1.1.1.2:1", "1.1.1.3:1"]
+    );
+
+    // Addresses a peer tells about in its extended handshake are raced with the one it listens on.
+    let pool = Arc::new(Mutex::new(PeerPool::new()));
+    let v4: SocketAddr = "10.0.0.1:6881".parse().unwrap();
+    let v6: SocketAddr = "[2001:db8::2]:6881".parse().unwrap();
+    pool.lock().unwrap().add(v6, 0);
+    pool.lock().unwrap().connect(v4);
+
+    let mut pex = UtPex::new(pool.clone(), v4);
+    let handshake = ExtendedHandshake {
+        p: Some(6881),
+        ipv6: Some("2001:db8::2".parse::<Ipv6Addr>().unwrap().octets().to_vec()),
+        ..Default::default()
+    };
+    pex.on_handshake(&handshake).unwrap();
+
+    let mut pool = pool.lock().unwrap();
+    assert_eq!(pool.alternatives(&v4), [v6]);
+    assert_eq!(pool.take_next(), None);
+}
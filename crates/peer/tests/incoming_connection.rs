@@ -0,0 +1,65 @@
+mod common;
+
+use async_std::sync::{Arc, Mutex};
+use builder::Builder;
+use peer::{full_bitfield, Listener, Peer};
+use std::time::Duration;
+
+#[async_std::test]
+async fn incoming_connection() {
+    let info_hash = vec![b'i'; 20];
+    let listener = Listener::bind(([127, 0, 0, 1], 0).into(), vec![b's'; 20])
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+    let incoming = listener.add_torrent(info_hash.clone());
+    async_std::task::spawn(listener.run());
+
+    // Seed a completed torrent to every incoming peer.
+    let mut seed = Builder::new(2, 1 << 15, 1 << 14);
+    for block in &mut seed.missing {
+        block.data = vec![block.index as u8 + 1; block.data.len()];
+    }
+    for block in std::mem::take(&mut seed.missing) {
+        seed.add_finished_block(block).unwrap();
+    }
+    let seed = Arc::new(Mutex::new(seed));
+    async_std::task::spawn(async move {
+        while let Ok(mut peer) = incoming.recv().await {
+            let seed = seed.clone();
+
+            async_std::task::spawn(async move {
+                peer.setup_session(seed.clone()).await?;
+                peer.start(seed).await
+            });
+        }
+    });
+
+    // Connections for other torrents are dropped.
+    let mut stranger = Peer::new(None, addr.ip(), addr.port());
+    let other = Arc::new(Mutex::new(Builder::new(2, 1 << 15, 1 << 14)));
+    assert!(stranger
+        .setup(&mut vec![b'x'; 20], &mut vec![b'c'; 20], other)
+        .await
+        .is_err());
+
+    // A peer connecting for the torrent downloads all of it.
+    let builder = Arc::new(Mutex::new(Builder::new(2, 1 << 15, 1 << 14)));
+    let mut leecher = Peer::new(None, addr.ip(), addr.port());
+    leecher
+        .setup(&mut info_hash.clone(), &mut vec![b'c'; 20], builder.clone())
+        .await
+        .unwrap();
+    let downloading = builder.clone();
+    async_std::task::spawn(async move { leecher.start(downloading).await });
+
+    for _ in 0..100 {
+        if builder.lock().await.get_bitfield() == full_bitfield(2) {
+            break;
+        }
+        async_std::task::sleep(Duration::from_millis(100)).await;
+    }
+    let builder = builder.lock().await;
+    assert_eq!(builder.get_bitfield(), full_bitfield(2));
+    assert_eq!(builder.get_finished_block(1, 0, 4).unwrap().data, [2; 4]);
+}
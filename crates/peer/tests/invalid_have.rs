@@ -0,0 +1,47 @@
+mod common;
+
+use async_std::future::timeout;
+use async_std::sync::{Arc, Mutex};
+use builder::Builder;
+use message::Message;
+use peer::{Listener, Peer};
+use std::time::Duration;
+
+#[async_std::test]
+async fn invalid_have() {
+    let info_hash = vec![b'i'; 20];
+    let listener = Listener::bind(([127, 0, 0, 1], 0).into(), vec![b's'; 20])
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+    let incoming = listener.add_torrent(info_hash.clone());
+    async_std::task::spawn(listener.run());
+
+    let seed = Arc::new(Mutex::new(Builder::new(1, 1 << 14, 1 << 14)));
+    let session = async_std::task::spawn(async move {
+        let mut peer = incoming.recv().await.unwrap();
+        peer.setup_session(seed.clone()).await?;
+        peer.start(seed).await
+    });
+
+    let builder = Arc::new(Mutex::new(Builder::new(1, 1 << 14, 1 << 14)));
+    let mut leecher = Peer::new(None, addr.ip(), addr.port());
+    leecher
+        .setup(&mut info_hash.clone(), &mut vec![b'c'; 20], builder)
+        .await
+        .unwrap();
+
+    // A valid index keeps the connection open.
+    leecher.send_message(Message::new_have(0)).await.unwrap();
+    leecher
+        .send_message(Message::new_interested())
+        .await
+        .unwrap();
+    let unchoked = async { while leecher.read_message().await.unwrap().get_name() != "Unchoke" {} };
+    timeout(Duration::from_secs(5), unchoked).await.unwrap();
+
+    // Indexes past the last piece drop the peer, instead of panicking.
+    leecher.send_message(Message::new_have(1)).await.unwrap();
+    let result = timeout(Duration::from_secs(5), session).await.unwrap();
+    assert_eq!(result.unwrap_err().to_string(), "Invalid piece index 1");
+}
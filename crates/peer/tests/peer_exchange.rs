@@ -0,0 +1,74 @@
+mod common;
+
+use peer::{ExtendedHandshake, Extension, PeerPool, PexMessage, UtPex, PEX_SEED};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn peer_exchange() {
+    let v4 = "10.0.0.1:6881".parse::<SocketAddr>().unwrap();
+    let v6 = "[2001:db8::1]:51413".parse::<SocketAddr>().unwrap();
+    let other = "10.0.0.2:6882".parse::<SocketAddr>().unwrap();
+
+    // IPv4 and IPv6 peers round-trip, with their flags.
+    let message = PexMessage {
+        added: vec![(v4, PEX_SEED), (v6, 0)],
+        dropped: vec![other],
+    };
+    let bytes = message.clone().into_bytes().unwrap();
+    assert!(bytes.starts_with(b"d5:added6:\x0a\x00\x00\x01\x1a\xe17:added.f1:\x02"));
+    assert_eq!(PexMessage::from_bytes(&bytes).unwrap(), message);
+
+    // Peers from a message are added to the pool, to be connected to.
+    let pool = Arc::new(Mutex::new(PeerPool::new()));
+    let mut pex = UtPex::new(pool.clone(), other);
+    pex.on_message(&bytes).unwrap();
+    assert_eq!(pool.lock().unwrap().take_next(), Some(v6));
+    assert_eq!(pool.lock().unwrap().take_next(), Some(v4));
+    assert_eq!(pool.lock().unwrap().take_next(), None);
+
+    // Only changes are sent, and never the peer itself.
+    assert_eq!(pex.update(), None);
+    pool.lock().unwrap().connect(v4);
+    pool.lock().unwrap().connect(other);
+    assert_eq!(
+        pex.update(),
+        Some(PexMessage {
+            added: vec![(v4, PEX_SEED)],
+            dropped: vec![],
+        })
+    );
+    assert_eq!(pex.update(), None);
+
+    pool.lock().unwrap().disconnect(v4);
+    assert_eq!(
+        pex.update(),
+        Some(PexMessage {
+            added: vec![],
+            dropped: vec![v4],
+        })
+    );
+
+    // Ticks are rate limited.
+    pool.lock().unwrap().connect(v6);
+    assert_eq!(pex.on_tick().unwrap().len(), 1);
+    pool.lock().unwrap().disconnect(v6);
+    assert!(pex.on_tick().unwrap().is_empty());
+
+    // Peers that connected to us are shared on the port from their handshake, never the one they
+    // connected from.
+    let incoming = "10.0.0.3:50000".parse::<SocketAddr>().unwrap();
+    let listening = "10.0.0.3:6881".parse::<SocketAddr>().unwrap();
+    let mut watcher = UtPex::new(pool.clone(), "10.0.0.4:6881".parse().unwrap());
+    let mut incoming_pex = UtPex::new(pool.clone(), incoming);
+    pool.lock().unwrap().connect_incoming(incoming);
+    assert_eq!(watcher.update().unwrap().added, [(other, 0)]);
+
+    let handshake = ExtendedHandshake {
+        p: Some(6881),
+        ..Default::default()
+    };
+    incoming_pex.on_handshake(&handshake).unwrap();
+    assert_eq!(watcher.update().unwrap().added, [(listening, 0)]);
+    assert_eq!(incoming_pex.update().unwrap().added, [(other, 0)]);
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use peer::{PeerPool, MAX_KNOWN_PEERS, MAX_QUEUED_PEERS};
+use std::net::{Ipv4Addr, SocketAddr};
+
+/// Returns a distinct address for every number.
+fn addr(n: usize) -> SocketAddr {
+    SocketAddr::new(Ipv4Addr::from(0x0a00_0000 + n as u32).into(), 6881)
+}
+
+#[async_std::test]
+async fn pool_limits() {
+    let mut pool = PeerPool::new();
+
+    // The oldest queued peers are dropped first.
+    for n in 0..MAX_QUEUED_PEERS + 10 {
+        assert!(pool.add(addr(n), 0));
+    }
+    assert_eq!(pool.queued_amount(), MAX_QUEUED_PEERS);
+    assert_eq!(pool.known_amount(), MAX_QUEUED_PEERS);
+    assert_eq!(pool.take_next(), Some(addr(10)));
+
+    // Dropped peers can be added again.
+    assert!(pool.add(addr(0), 0));
+
+    // Peers taken but not connected to are dropped past the limit, connected ones are kept.
+    let connected = pool.take_next().unwrap();
+    pool.connect(connected);
+    for n in MAX_QUEUED_PEERS + 10..MAX_KNOWN_PEERS * 2 {
+        pool.add(addr(n), 0);
+        if n % 2 == 0 {
+            pool.take_next();
+        }
+    }
+    assert_eq!(pool.known_amount(), MAX_KNOWN_PEERS);
+    assert!(pool.queued_amount() <= MAX_QUEUED_PEERS);
+    assert!(pool.connected().contains_key(&connected));
+    assert!(!pool.add(connected, 0));
+    assert!(pool.add(addr(10), 0));
+}
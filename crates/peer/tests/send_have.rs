@@ -0,0 +1,59 @@
+mod common;
+
+use async_std::future::timeout;
+use async_std::net::TcpListener;
+use async_std::sync::{Arc, Mutex};
+use builder::Builder;
+use message::Message;
+use peer::{Peer, Reserved};
+use std::time::Duration;
+
+#[async_std::test]
+async fn send_have() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Download a single piece in another task.
+    async_std::task::spawn(async move {
+        let builder = Arc::new(Mutex::new(Builder::new(1, 1 << 15, 1 << 14)));
+        let mut peer = Peer::new(None, addr.ip(), addr.port());
+
+        peer.setup(&mut vec![b'i'; 20], &mut vec![b'c'; 20], builder.clone())
+            .await?;
+        peer.start(builder).await
+    });
+
+    let (stream, _) = listener.accept().await.unwrap();
+    let mut remote = Peer::new(None, addr.ip(), addr.port());
+    remote.stream = Some(Arc::new(Mutex::new(stream)));
+    remote.reserved = Reserved::FAST;
+
+    let mut info_hash = remote.read_handshake().await.unwrap()[28..48].to_vec();
+    remote
+        .send_handshake(&mut info_hash, &mut vec![b'r'; 20])
+        .await
+        .unwrap();
+    remote.send_message(Message::new_have_all()).await.unwrap();
+    remote.send_message(Message::new_unchoke()).await.unwrap();
+
+    // Serve every request, until the finished piece is announced back.
+    let served = async {
+        loop {
+            match remote.read_message().await.unwrap() {
+                Message::Request((_, payload)) => {
+                    let index = u32::from_be_bytes(payload[0..4].try_into().unwrap());
+                    let begin = u32::from_be_bytes(payload[4..8].try_into().unwrap());
+                    let length = u32::from_be_bytes(payload[8..12].try_into().unwrap());
+                    remote
+                        .send_message(Message::new_piece(index, begin, vec![1; length as usize]))
+                        .await
+                        .unwrap();
+                }
+                Message::Have((_, payload)) => return payload,
+                _ => {}
+            }
+        }
+    };
+    let have = timeout(Duration::from_secs(10), served).await.unwrap();
+    assert_eq!(have, 0_u32.to_be_bytes());
+}
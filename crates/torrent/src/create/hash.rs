@@ -0,0 +1,133 @@
+use super::walk::WalkedFile;
+use anyhow::Result;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+/// Hash the pieces of the files, as if they were concatenated, using multiple threads.
+///
+/// Returns the concatenated SHA-1 hashes of all pieces.
+///
+/// # Arguments
+///
+/// * `files` - files in torrent order.
+/// * `size` - total amount of bytes in the files.
+/// * `piece_length` - bytes per piece.
+/// * `threads` - amount of threads to hash with.
+pub(super) fn hash_pieces(
+    files: &[WalkedFile],
+    size: u64,
+    piece_length: u64,
+    threads: usize,
+) -> Result<Vec<u8>> {
+    let piece_amount = size.div_ceil(piece_length);
+    let per_thread = piece_amount.div_ceil(threads.max(1) as u64).max(1);
+
+    // Each thread hashes a contiguous range of pieces, so it reads its files sequentially.
+    let results = std::thread::scope(|scope| {
+        let handles = (0..piece_amount)
+            .step_by(per_thread as usize)
+            .map(|start| {
+                let end = (start + per_thread).min(piece_amount);
+                scope.spawn(move || hash_range(files, size, piece_length, start..end))
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("hashing thread panicked"))
+            .collect::<Vec<_>>()
+    });
+
+    let mut pieces = Vec::with_capacity(piece_amount as usize * 20);
+    for result in results {
+        pieces.extend(result?);
+    }
+
+    Ok(pieces)
+}
+
+/// Hash a range of pieces.
+///
+/// # Arguments
+///
+/// * `files` - files in torrent order.
+/// * `size` - total amount of bytes in the files.
+/// * `piece_length` - bytes per piece.
+/// * `range` - indices of the pieces to hash.
+fn hash_range(
+    files: &[WalkedFile],
+    size: u64,
+    piece_length: u64,
+    range: std::ops::Range<u64>,
+) -> Result<Vec<u8>> {
+    let mut reader = Reader::new(files, range.start * piece_length)?;
+    let mut buffer = vec![0; piece_length as usize];
+    let mut hashes = Vec::with_capacity((range.end - range.start) as usize * 20);
+
+    for index in range {
+        let length = piece_length.min(size - index * piece_length) as usize;
+        reader.read_exact(&mut buffer[..length])?;
+        hashes.extend(sha1_smol::Sha1::from(&buffer[..length]).digest().bytes());
+    }
+
+    Ok(hashes)
+}
+
+/// Reads files one after another, as if they were concatenated.
+struct Reader<'a> {
+    files: &'a [WalkedFile],
+    index: usize,
+    current: Option<File>,
+}
+
+impl<'a> Reader<'a> {
+    /// Create a reader starting at an offset.
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - files in torrent order.
+    /// * `offset` - offset into the concatenated files.
+    fn new(files: &'a [WalkedFile], mut offset: u64) -> Result<Reader<'a>> {
+        let mut index = 0;
+
+        while index < files.len() && offset >= files[index].length {
+            offset -= files[index].length;
+            index += 1;
+        }
+
+        let current = match files.get(index) {
+            Some(file) => {
+                let mut current = File::open(&file.full_path)?;
+                current.seek(SeekFrom::Start(offset))?;
+                Some(current)
+            }
+            None => None,
+        };
+
+        Ok(Reader {
+            files,
+            index,
+            current,
+        })
+    }
+}
+
+impl Read for Reader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while let Some(current) = &mut self.current {
+            let read = current.read(buf)?;
+
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+
+            self.index += 1;
+            self.current = match self.files.get(self.index) {
+                Some(file) => Some(File::open(&file.full_path)?),
+                None => None,
+            };
+        }
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,246 @@
+mod hash;
+mod walk;
+
+use crate::torrent::{File, MultiFileInfo, SingleFileInfo, Torrent, TorrentInfo};
+use anyhow::{anyhow, Result};
+use bcode::BEncode;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Smallest piece length picked automatically (16 KiB).
+const MIN_PIECE_LENGTH: u64 = 1 << 14;
+/// Largest piece length picked automatically (16 MiB).
+const MAX_PIECE_LENGTH: u64 = 1 << 24;
+/// Amount of pieces aimed for when picking the piece length automatically.
+const TARGET_PIECE_AMOUNT: u64 = 1500;
+
+/// Builder for creating a `Torrent` from a file or directory.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// let torrent = torrent::TorrentBuilder::new("release/")
+///     .announce("http://tracker.example.com/announce")
+///     .comment("Release build")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TorrentBuilder {
+    path: PathBuf,
+    announce_list: Vec<Vec<String>>,
+    comment: Option<String>,
+    created_by: Option<String>,
+    creation_date: Option<i64>,
+    private: bool,
+    piece_length: Option<u64>,
+    threads: Option<usize>,
+}
+
+impl TorrentBuilder {
+    /// Create a new torrent builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - file or directory to create a torrent of.
+    pub fn new<P: AsRef<Path>>(path: P) -> TorrentBuilder {
+        TorrentBuilder {
+            path: path.as_ref().to_path_buf(),
+            announce_list: vec![],
+            comment: None,
+            created_by: None,
+            creation_date: None,
+            private: false,
+            piece_length: None,
+            threads: None,
+        }
+    }
+
+    /// Add a tracker in a tier of its own.
+    ///
+    /// The first tracker added becomes the `announce` URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - tracker announce URL.
+    pub fn announce<S: Into<String>>(mut self, url: S) -> TorrentBuilder {
+        self.announce_list.push(vec![url.into()]);
+        self
+    }
+
+    /// Add a tier of trackers.
+    ///
+    /// # Arguments
+    ///
+    /// * `tier` - tracker announce URLs that are tried in random order.
+    pub fn announce_tier<S: Into<String>>(mut self, tier: Vec<S>) -> TorrentBuilder {
+        self.announce_list
+            .push(tier.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the free-form comment.
+    ///
+    /// # Arguments
+    ///
+    /// * `comment` - comment.
+    pub fn comment<S: Into<String>>(mut self, comment: S) -> TorrentBuilder {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Set the name and version of the program creating the torrent.
+    ///
+    /// # Arguments
+    ///
+    /// * `created_by` - name and version, e.g. `riptorrent/0.1.0`.
+    pub fn created_by<S: Into<String>>(mut self, created_by: S) -> TorrentBuilder {
+        self.created_by = Some(created_by.into());
+        self
+    }
+
+    /// Set the creation date, defaults to the current time.
+    ///
+    /// # Arguments
+    ///
+    /// * `creation_date` - seconds since the UNIX epoch.
+    pub fn creation_date(mut self, creation_date: i64) -> TorrentBuilder {
+        self.creation_date = Some(creation_date);
+        self
+    }
+
+    /// Set whether the torrent is private, so peers are only found through its trackers.
+    ///
+    /// # Arguments
+    ///
+    /// * `private` - whether the torrent is private.
+    pub fn private(mut self, private: bool) -> TorrentBuilder {
+        self.private = private;
+        self
+    }
+
+    /// Set the piece length, picked automatically from the total size by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_length` - bytes per piece, must be a power of two and at least 16 KiB.
+    pub fn piece_length(mut self, piece_length: u64) -> TorrentBuilder {
+        self.piece_length = Some(piece_length);
+        self
+    }
+
+    /// Set the amount of threads used for hashing, defaults to the available parallelism.
+    ///
+    /// # Arguments
+    ///
+    /// * `threads` - amount of threads.
+    pub fn threads(mut self, threads: usize) -> TorrentBuilder {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Walk the files, hash the pieces and create the torrent.
+    pub fn build(self) -> Result<Torrent> {
+        let announce = self.announce_list.iter().flatten().next().cloned();
+
+        let name = self
+            .path
+            .canonicalize()?
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(String::from)
+            .ok_or_else(|| anyhow!("Invalid torrent name"))?;
+
+        let files = walk::walk(&self.path)?;
+        let size = files.iter().map(|file| file.length).sum::<u64>();
+
+        let piece_length = match self.piece_length {
+            Some(piece_length) if !piece_length.is_power_of_two() => {
+                return Err(anyhow!("Piece length must be a power of two"))
+            }
+            Some(piece_length) if piece_length < MIN_PIECE_LENGTH => {
+                return Err(anyhow!("Piece length must be at least {MIN_PIECE_LENGTH}"))
+            }
+            Some(piece_length) => piece_length,
+            None => pick_piece_length(size),
+        };
+
+        let threads = self.threads.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|threads| threads.get())
+                .unwrap_or(1)
+        });
+        let pieces = hash::hash_pieces(&files, size, piece_length, threads)?;
+
+        let private = self.private.then_some(true);
+        let info = if self.path.is_dir() {
+            TorrentInfo::MultiFileInfo(MultiFileInfo {
+                piece_length: piece_length as i64,
+                pieces,
+                private,
+                name,
+                extra: BTreeMap::new(),
+                files: files
+                    .into_iter()
+                    .map(|file| File {
+                        length: file.length as i64,
+                        md5sum: None,
+                        path: file.path,
+                        extra: BTreeMap::new(),
+                    })
+                    .collect(),
+            })
+        } else {
+            TorrentInfo::SingleFileInfo(SingleFileInfo {
+                piece_length: piece_length as i64,
+                pieces,
+                private,
+                name,
+                length: size as i64,
+                md5sum: None,
+                extra: BTreeMap::new(),
+            })
+        };
+
+        let info_bytes = bcode::encode(info.bencode())?;
+        let info_hash = sha1_smol::Sha1::from(&info_bytes).digest().bytes().to_vec();
+
+        let creation_date = match self.creation_date {
+            Some(creation_date) => creation_date,
+            None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+        };
+
+        // A single tracker is described by `announce` alone.
+        let announce_list =
+            (self.announce_list.iter().flatten().count() > 1).then_some(self.announce_list);
+
+        Ok(Torrent {
+            info,
+            announce,
+            announce_list,
+            creation_date: Some(creation_date),
+            comment: self.comment,
+            created_by: self.created_by,
+            encoding: None,
+            piece_layers: None,
+            extra: BTreeMap::new(),
+            info_hash,
+            info_hash_v2: None,
+            info_bytes,
+        })
+    }
+}
+
+/// Pick a piece length giving roughly `TARGET_PIECE_AMOUNT` pieces.
+///
+/// # Arguments
+///
+/// * `size` - total amount of bytes in the torrent.
+fn pick_piece_length(size: u64) -> u64 {
+    (size / TARGET_PIECE_AMOUNT)
+        .next_power_of_two()
+        .clamp(MIN_PIECE_LENGTH, MAX_PIECE_LENGTH)
+}
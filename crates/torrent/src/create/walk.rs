@@ -0,0 +1,83 @@
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+/// A file to include in a torrent.
+#[derive(Debug, Clone)]
+pub(super) struct WalkedFile {
+    /// Where the file is on disk.
+    pub full_path: PathBuf,
+    /// Path components relative to the torrent root, empty for single-file torrents.
+    pub path: Vec<String>,
+    /// Amount of bytes in the file.
+    pub length: u64,
+}
+
+/// Find the files to include in a torrent, sorted by path.
+///
+/// # Arguments
+///
+/// * `root` - file or directory to walk.
+pub(super) fn walk(root: &Path) -> Result<Vec<WalkedFile>> {
+    let metadata = std::fs::metadata(root)?;
+
+    if metadata.is_file() {
+        return Ok(vec![WalkedFile {
+            full_path: root.to_path_buf(),
+            path: vec![],
+            length: metadata.len(),
+        }]);
+    }
+
+    let mut files = vec![];
+    walk_dir(root, &mut vec![], &mut files)?;
+
+    if files.is_empty() {
+        return Err(anyhow!("No files found in {}", root.display()));
+    }
+
+    Ok(files)
+}
+
+/// Recursively add the files in a directory.
+///
+/// # Arguments
+///
+/// * `dir` - directory to walk.
+/// * `prefix` - path components from the torrent root to `dir`.
+/// * `files` - where to add the files.
+fn walk_dir(dir: &Path, prefix: &mut Vec<String>, files: &mut Vec<WalkedFile>) -> Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|name| anyhow!("File name is not valid UTF-8: {name:?}"))?;
+        let full_path = entry.path();
+        let is_link = std::fs::symlink_metadata(&full_path)?.is_symlink();
+        let metadata = match std::fs::metadata(&full_path) {
+            Ok(metadata) => metadata,
+            // Broken symlinks are skipped.
+            Err(_) if is_link => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        prefix.push(name);
+
+        // Symlinked directories aren't followed, they could loop back on themselves.
+        if metadata.is_dir() && !is_link {
+            walk_dir(&full_path, prefix, files)?;
+        } else if metadata.is_file() {
+            files.push(WalkedFile {
+                full_path,
+                path: prefix.clone(),
+                length: metadata.len(),
+            });
+        }
+
+        prefix.pop();
+    }
+
+    Ok(())
+}
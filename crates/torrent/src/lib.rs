@@ -1,8 +1,16 @@
 //! # Torrent
 //!
 //! `torrent` is a library for decoding a `.torrent` file and
-//! transforming it into a data struct.
+//! transforming it into a data struct, or creating one from files.
 
+mod create;
+mod magnet;
+pub mod merkle;
 mod torrent;
 
-pub use crate::torrent::Torrent;
+pub use crate::create::TorrentBuilder;
+pub use crate::magnet::Magnet;
+pub use crate::torrent::{
+    File, FileTree, FileTreeFile, FileTreeNode, HybridInfo, LayoutFile, MultiFileInfo,
+    SingleFileInfo, Torrent, TorrentInfo, V2Info,
+};
@@ -0,0 +1,32 @@
+use anyhow::{anyhow, Result};
+
+/// Alphabet of base32 (RFC 4648), as used by old magnet links.
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Decode unpadded base32, in either case.
+///
+/// # Arguments
+///
+/// * `base32` - base32 to decode.
+pub fn from_base32(base32: &str) -> Result<Vec<u8>> {
+    let mut out = vec![];
+    let mut buffer = 0_u64;
+    let mut bits = 0;
+
+    for c in base32.bytes() {
+        let digit = BASE32_ALPHABET
+            .iter()
+            .position(|x| *x == c.to_ascii_uppercase())
+            .ok_or_else(|| anyhow!("Invalid base32 {base32:?}"))?;
+
+        buffer = (buffer << 5) | digit as u64;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+
+    Ok(out)
+}
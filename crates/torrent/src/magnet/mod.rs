@@ -0,0 +1,180 @@
+mod encoding;
+mod to_torrent;
+
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// Multihash prefix of a SHA-256 hash: the function code and the digest length.
+const MULTIHASH_SHA256: [u8; 2] = [0x12, 0x20];
+
+/// Struct representing a magnet link.
+///
+/// # Example
+///
+/// ```
+/// let magnet = "magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056&dn=Example"
+///     .parse::<torrent::Magnet>()
+///     .unwrap();
+///
+/// assert_eq!(magnet.display_name.as_deref(), Some("Example"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Magnet {
+    /// SHA-1 info hash of a v1 or hybrid torrent (`xt=urn:btih:`).
+    pub info_hash: Option<Vec<u8>>,
+    /// SHA-256 info hash of a v2 or hybrid torrent (`xt=urn:btmh:`).
+    pub info_hash_v2: Option<Vec<u8>>,
+    /// Name to show while the metadata is missing (`dn`).
+    pub display_name: Option<String>,
+    /// Tracker announce URLs (`tr`).
+    pub trackers: Vec<String>,
+    /// Web seed URLs (`ws`).
+    pub web_seeds: Vec<String>,
+    /// Peer addresses as `host:port` (`x.pe`).
+    pub peers: Vec<String>,
+    /// Indices of the files to download, all files if empty (`so`).
+    pub select_only: Vec<RangeInclusive<usize>>,
+}
+
+impl Magnet {
+    /// Returns the info hash used to find peers, the truncated v2 hash for v2-only magnets.
+    pub fn swarm_hash(&self) -> Option<Vec<u8>> {
+        self.info_hash.clone().or_else(|| {
+            self.info_hash_v2
+                .as_ref()
+                .map(|info_hash_v2| info_hash_v2[..20].to_vec())
+        })
+    }
+}
+
+impl FromStr for Magnet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Magnet> {
+        let query = s
+            .strip_prefix("magnet:?")
+            .ok_or_else(|| anyhow!("Magnet link must start with \"magnet:?\""))?;
+        let mut magnet = Magnet::default();
+
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+            match key {
+                "xt" => parse_exact_topic(&mut magnet, value)?,
+                "dn" => magnet.display_name = Some(decode(&value.replace('+', " "))?),
+                "tr" => magnet.trackers.push(decode(value)?),
+                "ws" => magnet.web_seeds.push(decode(value)?),
+                "x.pe" => magnet.peers.push(decode(value)?),
+                "so" => magnet.select_only.extend(parse_select_only(value)?),
+                // Unknown parameters are ignored, as they are optional.
+                _ => {}
+            }
+        }
+
+        if magnet.info_hash.is_none() && magnet.info_hash_v2.is_none() {
+            return Err(anyhow!("Magnet link has no BitTorrent info hash"));
+        }
+
+        Ok(magnet)
+    }
+}
+
+impl fmt::Display for Magnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut params = vec![];
+
+        if let Some(info_hash) = &self.info_hash {
+            params.push(format!("xt=urn:btih:{}", bcode::to_hex(info_hash)));
+        }
+        if let Some(info_hash_v2) = &self.info_hash_v2 {
+            let multihash = [MULTIHASH_SHA256.as_slice(), info_hash_v2].concat();
+            params.push(format!("xt=urn:btmh:{}", bcode::to_hex(&multihash)));
+        }
+        if let Some(display_name) = &self.display_name {
+            params.push(format!("dn={}", urlencoding::encode(display_name)));
+        }
+        for tracker in &self.trackers {
+            params.push(format!("tr={}", urlencoding::encode(tracker)));
+        }
+        for web_seed in &self.web_seeds {
+            params.push(format!("ws={}", urlencoding::encode(web_seed)));
+        }
+        for peer in &self.peers {
+            params.push(format!("x.pe={}", urlencoding::encode(peer)));
+        }
+        if !self.select_only.is_empty() {
+            let ranges = self
+                .select_only
+                .iter()
+                .map(|range| match range.start() == range.end() {
+                    true => range.start().to_string(),
+                    false => format!("{}-{}", range.start(), range.end()),
+                })
+                .collect::<Vec<_>>();
+            params.push(format!("so={}", ranges.join(",")));
+        }
+
+        write!(f, "magnet:?{}", params.join("&"))
+    }
+}
+
+/// Percent-decode a parameter value.
+///
+/// # Arguments
+///
+/// * `value` - percent-encoded value.
+fn decode(value: &str) -> Result<String> {
+    Ok(urlencoding::decode(value)?.into_owned())
+}
+
+/// Parse an exact topic (`xt`), ignoring topics that aren't BitTorrent info hashes.
+///
+/// # Arguments
+///
+/// * `magnet` - where to store the info hash.
+/// * `value` - value of the `xt` parameter.
+fn parse_exact_topic(magnet: &mut Magnet, value: &str) -> Result<()> {
+    if let Some(hash) = value.strip_prefix("urn:btih:") {
+        let info_hash = match hash.len() {
+            40 => bcode::from_hex(hash)?,
+            32 => encoding::from_base32(hash)?,
+            _ => return Err(anyhow!("Invalid btih info hash {hash:?}")),
+        };
+
+        magnet.info_hash = Some(info_hash);
+    } else if let Some(hash) = value.strip_prefix("urn:btmh:") {
+        let multihash = bcode::from_hex(hash)?;
+
+        match multihash.strip_prefix(MULTIHASH_SHA256.as_slice()) {
+            Some(info_hash_v2) if info_hash_v2.len() == 32 => {
+                magnet.info_hash_v2 = Some(info_hash_v2.to_vec())
+            }
+            _ => return Err(anyhow!("Invalid btmh info hash {hash:?}")),
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse the file indices of a select-only (`so`) parameter, like `0,2,4-6`.
+///
+/// # Arguments
+///
+/// * `value` - value of the `so` parameter.
+fn parse_select_only(value: &str) -> Result<Vec<RangeInclusive<usize>>> {
+    value
+        .split(',')
+        .map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let (start, end) = (start.parse::<usize>()?, end.parse::<usize>()?);
+
+            if start > end {
+                return Err(anyhow!("Invalid select-only range {range:?}"));
+            }
+
+            Ok(start..=end)
+        })
+        .collect()
+}
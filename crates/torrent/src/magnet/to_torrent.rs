@@ -0,0 +1,62 @@
+use super::*;
+use crate::Torrent;
+use bcode::Value;
+use sha2::{Digest, Sha256};
+
+impl Magnet {
+    /// Create a `Torrent` from the info dictionary downloaded from peers.
+    ///
+    /// The trackers and web seeds of the magnet link are added to the torrent.
+    ///
+    /// # Arguments
+    ///
+    /// * `info` - bencoded info dictionary, must match the info hash.
+    pub async fn to_torrent(&self, info: &[u8]) -> Result<Torrent> {
+        let matches = match (&self.info_hash, &self.info_hash_v2) {
+            (Some(info_hash), _) => sha1_smol::Sha1::from(info).digest().bytes() == **info_hash,
+            (None, Some(info_hash_v2)) => Sha256::digest(info).as_slice() == info_hash_v2,
+            (None, None) => false,
+        };
+
+        if !matches {
+            return Err(anyhow!("Metadata doesn't match the info hash"));
+        }
+
+        // Keys are written in sorted order, and the info dictionary exactly as downloaded.
+        let mut out = b"d".to_vec();
+
+        if let Some(tracker) = self.trackers.first() {
+            out.extend_from_slice(b"8:announce");
+            bcode::encode_to(&Value::ByteString(tracker.clone().into()), &mut out)?;
+        }
+
+        if self.trackers.len() > 1 {
+            let tiers = self
+                .trackers
+                .iter()
+                .map(|tracker| Value::List(vec![Value::ByteString(tracker.clone().into())]))
+                .collect();
+
+            out.extend_from_slice(b"13:announce-list");
+            bcode::encode_to(&Value::List(tiers), &mut out)?;
+        }
+
+        out.extend_from_slice(b"4:info");
+        out.extend_from_slice(info);
+
+        if !self.web_seeds.is_empty() {
+            let web_seeds = self
+                .web_seeds
+                .iter()
+                .map(|web_seed| Value::ByteString(web_seed.clone().into()))
+                .collect();
+
+            out.extend_from_slice(b"8:url-list");
+            bcode::encode_to(&Value::List(web_seeds), &mut out)?;
+        }
+
+        out.push(b'e');
+
+        Torrent::from_bytes(out).await
+    }
+}
@@ -0,0 +1,60 @@
+//! SHA-256 merkle trees used by v2 torrents (BEP 52).
+
+use sha2::{Digest, Sha256};
+
+/// Size of the blocks hashed as the leaves of a merkle tree.
+pub const BLOCK_SIZE: u64 = 1 << 14;
+
+/// Hash two nodes into their parent.
+///
+/// # Arguments
+///
+/// * `left` - left child.
+/// * `right` - right child.
+fn parent(left: &[u8], right: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+
+    hasher.finalize().into()
+}
+
+/// Returns the hash of a subtree with only padding, covering one piece.
+///
+/// # Arguments
+///
+/// * `piece_length` - bytes per piece, a power of two of at least `BLOCK_SIZE`.
+pub fn pad_hash(piece_length: u64) -> [u8; 32] {
+    let mut hash = [0; 32];
+
+    for _ in 0..(piece_length / BLOCK_SIZE).trailing_zeros() {
+        hash = parent(&hash, &hash);
+    }
+
+    hash
+}
+
+/// Returns the root of a merkle tree.
+///
+/// The layer is padded with `pad` to a power of two amount of nodes.
+///
+/// # Arguments
+///
+/// * `layer` - concatenated 32-byte hashes of the bottom layer.
+/// * `pad` - hash to pad the bottom layer with.
+pub fn root(layer: &[u8], pad: [u8; 32]) -> [u8; 32] {
+    let mut nodes = layer
+        .chunks_exact(32)
+        .map(|chunk| <[u8; 32]>::try_from(chunk).unwrap())
+        .collect::<Vec<_>>();
+    nodes.resize(nodes.len().next_power_of_two(), pad);
+
+    while nodes.len() > 1 {
+        nodes = nodes
+            .chunks_exact(2)
+            .map(|pair| parent(&pair[0], &pair[1]))
+            .collect();
+    }
+
+    nodes.first().copied().unwrap_or(pad)
+}
@@ -0,0 +1,51 @@
+use super::*;
+
+/// Flatten a file tree to its files and their paths, in path order.
+///
+/// # Arguments
+///
+/// * `file_tree` - file tree to flatten.
+pub fn flatten(file_tree: &FileTree) -> Vec<(Vec<String>, &FileTreeFile)> {
+    let mut out = vec![];
+    flatten_into(file_tree, &mut vec![], &mut out);
+
+    out
+}
+
+/// Recursively add the files in a file tree.
+///
+/// # Arguments
+///
+/// * `file_tree` - file tree to walk.
+/// * `prefix` - path components leading to `file_tree`.
+/// * `out` - where to add the files.
+fn flatten_into<'a>(
+    file_tree: &'a FileTree,
+    prefix: &mut Vec<String>,
+    out: &mut Vec<(Vec<String>, &'a FileTreeFile)>,
+) {
+    for (name, node) in file_tree {
+        prefix.push(name.clone());
+
+        match node {
+            FileTreeNode::File(file) => out.push((prefix.clone(), file)),
+            FileTreeNode::Directory(directory) => flatten_into(directory, prefix, out),
+        }
+
+        prefix.pop();
+    }
+}
+
+impl V2Info {
+    /// Get the files and their paths, in path order.
+    pub fn files(&self) -> Vec<(Vec<String>, &FileTreeFile)> {
+        flatten(&self.file_tree)
+    }
+}
+
+impl HybridInfo {
+    /// Get the files and their paths from the v2 file tree, in path order.
+    pub fn files(&self) -> Vec<(Vec<String>, &FileTreeFile)> {
+        flatten(&self.file_tree)
+    }
+}
@@ -1,7 +1,6 @@
 use super::*;
-use anyhow::Result;
-use bcode::map_get;
-use std::collections::BTreeMap;
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
 
 impl Torrent {
     /// Converts a byte vector to a `Torrent`.
@@ -10,119 +9,38 @@ impl Torrent {
     ///
     /// * `vec` - byte vector.
     pub async fn from_bytes(vec: Vec<u8>) -> Result<Torrent> {
-        let main_map: BTreeMap<Vec<u8>, bcode::Value> = bcode::decode(&vec, &mut 0)?.try_into()?; // ?
-        let info_map: BTreeMap<Vec<u8>, bcode::Value> = map_get(&main_map, "info")?.try_into()?;
-
-        let piece_length: i64 = map_get(&info_map, "piece length")?.try_into()?;
-        let pieces: Vec<u8> = map_get(&info_map, "pieces")?.try_into()?;
-        let private: Option<bool> = map_get(&info_map, "private")
-            .ok()
-            .map(|x| -> Result<bool> {
-                let temp: i64 = x.try_into()?;
-                Ok(temp != 0)
-            })
-            .transpose()?;
-
-        let info: TorrentInfo = if info_map.contains_key(&b"files".to_vec()) {
-            let name: String = map_get(&info_map, "name")?.try_into()?;
-
-            let files_list: Vec<bcode::Value> = map_get(&info_map, "files")?.try_into()?;
-            let files = files_list
-                .into_iter()
-                .map(|file_map| -> Result<File> {
-                    let file_map: BTreeMap<Vec<u8>, bcode::Value> = file_map.try_into()?;
-
-                    let length: i64 = map_get(&file_map, "length")?.try_into()?;
-                    let md5sum: Option<String> = map_get(&file_map, "md5sum")
-                        .ok()
-                        .map(|x| -> Result<String> { x.try_into() })
-                        .transpose()?;
-                    let path: Vec<bcode::Value> = map_get(&file_map, "path")?.try_into()?;
-                    let path: Vec<String> = path
-                        .into_iter()
-                        .map(|x| -> Result<String> { x.try_into() })
-                        .collect::<Result<Vec<String>>>()?;
-
-                    Ok(File {
-                        length,
-                        md5sum,
-                        path,
-                    })
-                })
-                .collect::<Result<Vec<File>>>()?;
-
-            TorrentInfo::MultiFileInfo(MultiFileInfo {
-                piece_length,
-                pieces,
-                private,
-                name,
-                files,
-            })
-        } else {
-            let name: String = map_get(&info_map, "name")?.try_into()?;
-            let length: i64 = map_get(&info_map, "length")?.try_into()?;
-            let md5sum: Option<String> = map_get(&info_map, "md5sum")
-                .ok()
-                .map(|x| -> Result<String> { x.try_into() })
-                .transpose()?;
-
-            TorrentInfo::SingleFileInfo(SingleFileInfo {
-                piece_length,
-                pieces,
-                private,
-                name,
-                length,
-                md5sum,
-            })
-        };
-
-        let announce: String = map_get(&main_map, "announce")?.try_into()?;
-        let announce_list: Option<Vec<Vec<String>>> = map_get(&main_map, "announce-list")
-            .ok()
-            .map(|val| -> Result<Vec<Vec<String>>> {
-                let outer_list: Vec<bcode::Value> = val.try_into()?;
-                outer_list
-                    .into_iter()
-                    .map(|x| -> Result<Vec<String>> {
-                        let inner_list: Vec<bcode::Value> = x.try_into()?;
-                        inner_list
-                            .into_iter()
-                            .map(|x| -> Result<String> { x.try_into() })
-                            .collect::<Result<Vec<String>>>()
-                    })
-                    .collect::<Result<Vec<Vec<String>>>>()
-            })
-            .transpose()?;
-        let creation_date: Option<i64> = map_get(&main_map, "creation date")
-            .ok()
-            .map(|x| -> Result<i64> { x.try_into() })
-            .transpose()?;
-        let comment: Option<String> = map_get(&main_map, "comment")
-            .ok()
-            .map(|x| -> Result<String> { x.try_into() })
-            .transpose()?;
-        let created_by: Option<String> = map_get(&main_map, "created by")
-            .ok()
-            .map(|x| -> Result<String> { x.try_into() })
-            .transpose()?;
-        let encoding: Option<String> = map_get(&main_map, "encoding")
-            .ok()
-            .map(|x| -> Result<String> { x.try_into() })
-            .transpose()?;
-
-        Ok(Torrent {
-            info,
-            announce,
-            announce_list,
-            creation_date,
-            comment,
-            created_by,
-            encoding,
-
-            info_hash: sha1_smol::Sha1::from(bcode::encode(map_get(&main_map, "info")?)?)
-                .digest()
-                .bytes()
-                .to_vec(),
-        })
+        // Torrents in the wild aren't always canonical, their info hash is still valid.
+        let root = bcode::Decoder::new(&vec, bcode::Mode::Lenient).decode_spanned()?;
+
+        // Hash the info dictionary exactly as it appears in the file, since re-encoding
+        // it would change the hash of torrents that aren't canonically encoded.
+        let info = root
+            .get("info")
+            .ok_or_else(|| anyhow!("Could not find \"info\" in map"))?
+            .raw(&vec);
+        let info_hash = sha1_smol::Sha1::from(info).digest().bytes().to_vec();
+        let info_hash_v2 = Sha256::digest(info).to_vec();
+
+        let mut torrent = Torrent::bdecode(bcode::Value::from(root))?;
+        torrent.info_bytes = info.to_vec();
+
+        match torrent.info {
+            TorrentInfo::SingleFileInfo(_) | TorrentInfo::MultiFileInfo(_) => {
+                torrent.info_hash = info_hash;
+            }
+            TorrentInfo::HybridInfo(_) => {
+                torrent.info_hash = info_hash;
+                torrent.info_hash_v2 = Some(info_hash_v2);
+            }
+            // The v2 swarm is found with the SHA-256 hash truncated to 20 bytes.
+            TorrentInfo::V2Info(_) => {
+                torrent.info_hash = info_hash_v2[..20].to_vec();
+                torrent.info_hash_v2 = Some(info_hash_v2);
+            }
+        }
+
+        torrent.verify_piece_layers()?;
+
+        Ok(torrent)
     }
 }
@@ -0,0 +1,10 @@
+use super::*;
+
+impl Torrent {
+    /// Get the bencoded info dictionary, e.g. to send to peers asking for metadata.
+    ///
+    /// These are the bytes the info hash was taken from, even if they aren't canonically encoded.
+    pub fn get_info_bytes(&self) -> &[u8] {
+        &self.info_bytes
+    }
+}
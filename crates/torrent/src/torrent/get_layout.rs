@@ -0,0 +1,105 @@
+use super::*;
+
+/// File of a torrent, where its data is in the pieces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutFile {
+    /// Path from the download directory, starting with the torrent name for multi-file torrents.
+    pub path: Vec<String>,
+    /// Length in bytes.
+    pub length: u64,
+    /// Whether the file only pads the next file to the start of a piece (BEP 47).
+    pub padding: bool,
+}
+
+impl Torrent {
+    /// Get the files in the order their data is laid out in the pieces, padding included.
+    ///
+    /// Hybrid torrents are laid out like their v1 files, which already hold the padding.
+    /// Files of v2 torrents start at a piece, so they're padded up to the next one.
+    pub fn get_layout(&self) -> Vec<LayoutFile> {
+        match &self.info {
+            TorrentInfo::SingleFileInfo(info) => vec![single_file(&info.name, info.length)],
+            TorrentInfo::MultiFileInfo(info) => multi_file(&info.name, &info.files),
+            TorrentInfo::HybridInfo(info) => match (&info.files, info.length) {
+                (Some(files), _) => multi_file(&info.name, files),
+                (None, length) => vec![single_file(&info.name, length.unwrap_or(0))],
+            },
+            TorrentInfo::V2Info(info) => {
+                let piece_length = info.piece_length as u64;
+                let files = info.files();
+                let single = files.len() == 1 && files[0].0.len() == 1;
+                let mut out = vec![];
+
+                for (index, (path, file)) in files.iter().enumerate() {
+                    let length = file.length as u64;
+                    let path = match single {
+                        true => path.clone(),
+                        false => [vec![info.name.clone()], path.clone()].concat(),
+                    };
+                    let rest = length % piece_length;
+
+                    out.push(LayoutFile {
+                        path: path.clone(),
+                        length,
+                        padding: false,
+                    });
+                    if rest > 0 && index + 1 < files.len() {
+                        out.push(LayoutFile {
+                            path: [path, vec![".pad".to_string()]].concat(),
+                            length: piece_length - rest,
+                            padding: true,
+                        });
+                    }
+                }
+
+                out
+            }
+        }
+    }
+
+    /// Get amount of bytes in all pieces, with padding.
+    pub fn get_total_length(&self) -> i64 {
+        self.get_layout().iter().map(|x| x.length as i64).sum()
+    }
+}
+
+/// Lay out the file of a single-file torrent.
+///
+/// # Arguments
+///
+/// * `name` - name of the file.
+/// * `length` - length of the file.
+fn single_file(name: &str, length: i64) -> LayoutFile {
+    LayoutFile {
+        path: vec![name.to_string()],
+        length: length as u64,
+        padding: false,
+    }
+}
+
+/// Lay out the files of a multi-file torrent, in the directory named after the torrent.
+///
+/// # Arguments
+///
+/// * `name` - name of the directory.
+/// * `files` - files, padding files included.
+fn multi_file(name: &str, files: &[File]) -> Vec<LayoutFile> {
+    files
+        .iter()
+        .map(|file| LayoutFile {
+            path: [vec![name.to_string()], file.path.clone()].concat(),
+            length: file.length as u64,
+            padding: file.is_padding(),
+        })
+        .collect()
+}
+
+impl File {
+    /// Whether this is a padding file, which has a `p` in its `attr` (BEP 47).
+    pub fn is_padding(&self) -> bool {
+        match self.extra.get(b"attr".as_slice()) {
+            Some(bcode::Value::ByteString(attr)) => attr.contains(&b'p'),
+            _ => false,
+        }
+    }
+}
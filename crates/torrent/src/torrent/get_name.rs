@@ -0,0 +1,13 @@
+use super::*;
+
+impl Torrent {
+    /// Get name of torrent, the file name or directory name.
+    pub fn get_name(&self) -> &str {
+        match &self.info {
+            TorrentInfo::SingleFileInfo(info) => &info.name,
+            TorrentInfo::MultiFileInfo(info) => &info.name,
+            TorrentInfo::V2Info(info) => &info.name,
+            TorrentInfo::HybridInfo(info) => &info.name,
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use super::*;
+
+impl Torrent {
+    /// Get the DHT nodes of a trackerless torrent, as `host:port` (BEP 5).
+    pub fn get_nodes(&self) -> Vec<String> {
+        let Some(bcode::Value::List(nodes)) = self.extra.get(b"nodes".as_slice()) else {
+            return vec![];
+        };
+
+        nodes
+            .iter()
+            .filter_map(|node| {
+                let node = Vec::<bcode::Value>::bdecode(node.clone()).ok()?;
+                let host = String::bdecode(node.first()?.clone()).ok()?;
+                let port = u16::bdecode(node.get(1)?.clone()).ok()?;
+
+                Some(format!("{host}:{port}"))
+            })
+            .collect()
+    }
+}
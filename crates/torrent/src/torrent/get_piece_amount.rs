@@ -6,6 +6,13 @@ impl Torrent {
         match &self.info {
             TorrentInfo::SingleFileInfo(info) => info.pieces.len() / 20,
             TorrentInfo::MultiFileInfo(info) => info.pieces.len() / 20,
+            TorrentInfo::HybridInfo(info) => info.pieces.len() / 20,
+            // Pieces of v2 torrents are aligned to the start of each file.
+            TorrentInfo::V2Info(info) => info
+                .files()
+                .iter()
+                .map(|(_, x)| (x.length as usize).div_ceil(info.piece_length as usize))
+                .sum(),
         }
     }
 }
@@ -6,6 +6,8 @@ impl Torrent {
         match &self.info {
             TorrentInfo::SingleFileInfo(info) => info.piece_length,
             TorrentInfo::MultiFileInfo(info) => info.piece_length,
+            TorrentInfo::V2Info(info) => info.piece_length,
+            TorrentInfo::HybridInfo(info) => info.piece_length,
         }
     }
 }
@@ -1,11 +1,12 @@
 use super::*;
 
 impl Torrent {
-    /// Get amount of bytes in torrent.
+    /// Get amount of bytes in torrent, without padding files.
     pub fn get_size(&self) -> i64 {
-        match &self.info {
-            TorrentInfo::SingleFileInfo(info) => info.length,
-            TorrentInfo::MultiFileInfo(info) => info.files.iter().map(|x| x.length).sum(),
-        }
+        self.get_layout()
+            .iter()
+            .filter(|x| !x.padding)
+            .map(|x| x.length as i64)
+            .sum()
     }
 }
@@ -0,0 +1,15 @@
+use super::*;
+
+impl Torrent {
+    /// Returns `true` if peers may only be found through the trackers (BEP 27).
+    pub fn is_private(&self) -> bool {
+        let private = match &self.info {
+            TorrentInfo::SingleFileInfo(info) => info.private,
+            TorrentInfo::MultiFileInfo(info) => info.private,
+            TorrentInfo::V2Info(info) => info.private,
+            TorrentInfo::HybridInfo(info) => info.private,
+        };
+
+        private.unwrap_or(false)
+    }
+}
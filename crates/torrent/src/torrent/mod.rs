@@ -1,20 +1,52 @@
+mod file_tree;
 mod from_bytes;
+mod get_info_bytes;
+mod get_layout;
+mod get_name;
+mod get_nodes;
 mod get_piece_amount;
 mod get_piece_length;
 mod get_size;
+mod is_private;
+mod to_bytes;
+mod to_magnet;
+mod verify_piece_layers;
+
+use bcode::{BDecode, BEncode};
+use std::collections::BTreeMap;
+
+pub use get_layout::LayoutFile;
 
 /// Struct representing a torrent file.
-#[derive(Debug)]
+#[derive(Debug, BEncode, BDecode)]
 pub struct Torrent {
     pub info: TorrentInfo,
-    pub announce: String,
+    /// Tracker announce URL, missing for trackerless torrents.
+    pub announce: Option<String>,
+    #[bcode(rename = "announce-list")]
     pub announce_list: Option<Vec<Vec<String>>>,
+    #[bcode(rename = "creation date")]
     pub creation_date: Option<i64>,
     pub comment: Option<String>,
+    #[bcode(rename = "created by")]
     pub created_by: Option<String>,
     pub encoding: Option<String>,
+    /// Piece hashes of v2 files, keyed by their `pieces root`.
+    #[bcode(rename = "piece layers", with = "piece_layers")]
+    pub piece_layers: Option<BTreeMap<Vec<u8>, Vec<u8>>>,
+    /// Keys without a field, e.g. `url-list` or `nodes`, kept so they are written back.
+    #[bcode(unknown)]
+    pub extra: BTreeMap<Vec<u8>, bcode::Value>,
 
+    /// SHA-1 of the info dictionary, or the truncated SHA-256 for v2-only torrents.
+    #[bcode(skip)]
     pub info_hash: Vec<u8>,
+    /// Full SHA-256 of the info dictionary, for v2 and hybrid torrents.
+    #[bcode(skip)]
+    pub info_hash_v2: Option<Vec<u8>>,
+    /// Info dictionary exactly as it was read, the bytes the info hash is taken from.
+    #[bcode(skip)]
+    pub info_bytes: Vec<u8>,
 }
 
 /// Info part of a torrent.
@@ -22,33 +54,238 @@ pub struct Torrent {
 pub enum TorrentInfo {
     SingleFileInfo(SingleFileInfo),
     MultiFileInfo(MultiFileInfo),
+    V2Info(V2Info),
+    HybridInfo(HybridInfo),
 }
 
 /// Info for a single-file torrent.
-#[derive(Debug)]
+#[derive(Debug, BEncode, BDecode)]
 pub struct SingleFileInfo {
+    #[bcode(rename = "piece length")]
     pub piece_length: i64,
+    #[bcode(bytes)]
     pub pieces: Vec<u8>,
     pub private: Option<bool>,
     pub name: String,
     pub length: i64,
     pub md5sum: Option<String>,
+    #[bcode(unknown)]
+    pub extra: BTreeMap<Vec<u8>, bcode::Value>,
 }
 
 /// Info for a multi-file torrent.
-#[derive(Debug)]
+#[derive(Debug, BEncode, BDecode)]
 pub struct MultiFileInfo {
+    #[bcode(rename = "piece length")]
     pub piece_length: i64,
+    #[bcode(bytes)]
     pub pieces: Vec<u8>,
     pub private: Option<bool>,
     pub name: String,
     pub files: Vec<File>,
+    #[bcode(unknown)]
+    pub extra: BTreeMap<Vec<u8>, bcode::Value>,
 }
 
 /// Struct representing a file in a multi-file torrent.
-#[derive(Debug)]
+#[derive(Debug, BEncode, BDecode)]
 pub struct File {
     pub length: i64,
     pub md5sum: Option<String>,
     pub path: Vec<String>,
+    #[bcode(unknown)]
+    pub extra: BTreeMap<Vec<u8>, bcode::Value>,
+}
+
+/// Info for a v2 torrent (BEP 52).
+#[derive(Debug, BEncode, BDecode)]
+pub struct V2Info {
+    #[bcode(rename = "meta version")]
+    pub meta_version: i64,
+    #[bcode(rename = "piece length")]
+    pub piece_length: i64,
+    pub private: Option<bool>,
+    pub name: String,
+    #[bcode(rename = "file tree")]
+    pub file_tree: FileTree,
+    #[bcode(unknown)]
+    pub extra: BTreeMap<Vec<u8>, bcode::Value>,
+}
+
+/// Info for a hybrid torrent, with both the v1 and the v2 layout of the same files.
+#[derive(Debug, BEncode, BDecode)]
+pub struct HybridInfo {
+    #[bcode(rename = "meta version")]
+    pub meta_version: i64,
+    #[bcode(rename = "piece length")]
+    pub piece_length: i64,
+    #[bcode(bytes)]
+    pub pieces: Vec<u8>,
+    pub private: Option<bool>,
+    pub name: String,
+    #[bcode(rename = "file tree")]
+    pub file_tree: FileTree,
+    /// Length of the file, if the v1 layout is single-file.
+    pub length: Option<i64>,
+    /// Files including padding files, if the v1 layout is multi-file.
+    pub files: Option<Vec<File>>,
+    #[bcode(unknown)]
+    pub extra: BTreeMap<Vec<u8>, bcode::Value>,
+}
+
+/// Files and directories of a v2 torrent, by name.
+pub type FileTree = BTreeMap<String, FileTreeNode>;
+
+/// Entry in a v2 file tree.
+#[derive(Debug)]
+pub enum FileTreeNode {
+    File(FileTreeFile),
+    Directory(FileTree),
+}
+
+/// Struct representing a file in a v2 file tree.
+#[derive(Debug, BEncode, BDecode)]
+pub struct FileTreeFile {
+    pub length: i64,
+    /// Root of the SHA-256 merkle tree of the file, missing for empty files.
+    #[bcode(rename = "pieces root", bytes)]
+    pub pieces_root: Option<Vec<u8>>,
+    #[bcode(unknown)]
+    pub extra: BTreeMap<Vec<u8>, bcode::Value>,
+}
+
+impl BEncode for TorrentInfo {
+    fn bencode(&self) -> bcode::Value {
+        match self {
+            TorrentInfo::SingleFileInfo(info) => info.bencode(),
+            TorrentInfo::MultiFileInfo(info) => info.bencode(),
+            TorrentInfo::V2Info(info) => info.bencode(),
+            TorrentInfo::HybridInfo(info) => info.bencode(),
+        }
+    }
+}
+
+impl BDecode for TorrentInfo {
+    fn bdecode(value: bcode::Value) -> Result<Self, bcode::Error> {
+        let meta_version = match value.get("meta version") {
+            Some(meta_version) => i64::bdecode(meta_version.clone())?,
+            None => 1,
+        };
+        let has_pieces = value.get("pieces").is_some();
+        let is_multi_file = value.get("files").is_some();
+
+        // BEP 52 pieces are a power of two, and at least one 16 KiB block.
+        if let (2, Some(piece_length)) = (meta_version, value.get("piece length")) {
+            let piece_length = i64::bdecode(piece_length.clone())?;
+            if piece_length < 1 << 14 || piece_length.count_ones() != 1 {
+                return Err(bcode::Error::Custom(format!(
+                    "Invalid v2 piece length {piece_length}"
+                )));
+            }
+        }
+
+        let info = if meta_version > 2 {
+            return Err(bcode::Error::Custom(format!(
+                "Unsupported meta version {meta_version}"
+            )));
+        } else if meta_version == 2 && has_pieces {
+            TorrentInfo::HybridInfo(HybridInfo::bdecode(value)?)
+        } else if meta_version == 2 {
+            TorrentInfo::V2Info(V2Info::bdecode(value)?)
+        } else if is_multi_file {
+            TorrentInfo::MultiFileInfo(MultiFileInfo::bdecode(value)?)
+        } else {
+            TorrentInfo::SingleFileInfo(SingleFileInfo::bdecode(value)?)
+        };
+
+        // Lengths are used as sizes and offsets, so they can't be negative.
+        if let Some(length) = info.lengths().into_iter().find(|x| *x < 0) {
+            return Err(bcode::Error::Custom(format!("Invalid length {length}")));
+        }
+
+        Ok(info)
+    }
+}
+
+impl TorrentInfo {
+    /// Returns the piece length and the length of every file, for validation.
+    fn lengths(&self) -> Vec<i64> {
+        match self {
+            TorrentInfo::SingleFileInfo(info) => vec![info.piece_length, info.length],
+            TorrentInfo::MultiFileInfo(info) => [info.piece_length]
+                .into_iter()
+                .chain(info.files.iter().map(|x| x.length))
+                .collect(),
+            TorrentInfo::V2Info(info) => [info.piece_length]
+                .into_iter()
+                .chain(info.files().iter().map(|(_, x)| x.length))
+                .collect(),
+            TorrentInfo::HybridInfo(info) => [info.piece_length]
+                .into_iter()
+                .chain(info.length)
+                .chain(info.files.iter().flatten().map(|x| x.length))
+                .chain(info.files().iter().map(|(_, x)| x.length))
+                .collect(),
+        }
+    }
+}
+
+impl BEncode for FileTreeNode {
+    fn bencode(&self) -> bcode::Value {
+        match self {
+            FileTreeNode::File(file) => {
+                bcode::Value::Dictionary(BTreeMap::from([(vec![], file.bencode())]))
+            }
+            FileTreeNode::Directory(directory) => directory.bencode(),
+        }
+    }
+}
+
+impl BDecode for FileTreeNode {
+    fn bdecode(value: bcode::Value) -> Result<Self, bcode::Error> {
+        // A file is a dictionary with a single empty key, holding its properties.
+        match value {
+            bcode::Value::Dictionary(mut map) if map.contains_key(b"".as_slice()) => {
+                let file = map.remove(b"".as_slice()).unwrap();
+                Ok(FileTreeNode::File(FileTreeFile::bdecode(file)?))
+            }
+            value => Ok(FileTreeNode::Directory(FileTree::bdecode(value)?)),
+        }
+    }
+}
+
+/// Read and write the "piece layers" key, a dictionary of byte strings keyed by byte strings.
+mod piece_layers {
+    use super::*;
+
+    /// Convert piece layers to a dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_layers` - piece layers to convert.
+    pub fn bencode(piece_layers: &BTreeMap<Vec<u8>, Vec<u8>>) -> bcode::Value {
+        bcode::Value::Dictionary(
+            piece_layers
+                .iter()
+                .map(|(k, v)| (k.clone(), bcode::bytes::bencode(v)))
+                .collect(),
+        )
+    }
+
+    /// Convert a dictionary to piece layers.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - value to convert.
+    pub fn bdecode(value: bcode::Value) -> Result<BTreeMap<Vec<u8>, Vec<u8>>, bcode::Error> {
+        match value {
+            bcode::Value::Dictionary(map) => map
+                .into_iter()
+                .map(|(k, v)| Ok((k, bcode::bytes::bdecode(v)?)))
+                .collect(),
+            _ => Err(bcode::Error::Custom(
+                "Value is not a dictionary".to_string(),
+            )),
+        }
+    }
 }
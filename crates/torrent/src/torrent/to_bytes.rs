@@ -0,0 +1,35 @@
+use super::*;
+use anyhow::Result;
+
+impl Torrent {
+    /// Converts a `Torrent` to a byte vector.
+    ///
+    /// Keys without a field are written back, and the info dictionary is written exactly as it
+    /// was read unless it changed, so editing e.g. the trackers keeps the same info hash.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let root = match self.bencode() {
+            bcode::Value::Dictionary(root) => root,
+            value => return bcode::encode(value),
+        };
+
+        let read = bcode::Decoder::new(&self.info_bytes, bcode::Mode::Lenient)
+            .decode()
+            .ok();
+        if read.as_ref() != root.get(b"info".as_slice()) {
+            return bcode::encode(bcode::Value::Dictionary(root));
+        }
+
+        let mut out = vec![b'd'];
+        for (key, value) in &root {
+            bcode::encode_to(&bcode::Value::ByteString(key.clone()), &mut out)?;
+
+            match key.as_slice() {
+                b"info" => out.extend_from_slice(&self.info_bytes),
+                _ => bcode::encode_to(value, &mut out)?,
+            }
+        }
+        out.push(b'e');
+
+        Ok(out)
+    }
+}
@@ -0,0 +1,39 @@
+use super::*;
+use crate::Magnet;
+
+impl Torrent {
+    /// Create a magnet link for the torrent, with its trackers and web seeds.
+    pub fn to_magnet(&self) -> Magnet {
+        let mut trackers: Vec<String> = self.announce.iter().cloned().collect();
+        for tracker in self.announce_list.iter().flatten().flatten() {
+            if !trackers.contains(tracker) {
+                trackers.push(tracker.clone());
+            }
+        }
+
+        // BEP 19 allows a single URL, or a list of them.
+        let web_seeds = match self.extra.get(b"url-list".as_slice()) {
+            Some(bcode::Value::List(urls)) => urls.iter().collect(),
+            Some(url) => vec![url],
+            None => vec![],
+        };
+
+        let info_hash = match self.info {
+            TorrentInfo::V2Info(_) => None,
+            _ => Some(self.info_hash.clone()),
+        };
+
+        Magnet {
+            info_hash,
+            info_hash_v2: self.info_hash_v2.clone(),
+            display_name: Some(self.get_name().to_string()),
+            trackers,
+            web_seeds: web_seeds
+                .into_iter()
+                .filter_map(|url| String::bdecode(url.clone()).ok())
+                .filter(|url| !url.is_empty())
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
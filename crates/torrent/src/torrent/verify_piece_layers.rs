@@ -0,0 +1,52 @@
+use super::*;
+use anyhow::{anyhow, Result};
+
+impl Torrent {
+    /// Check that the piece layers match the `pieces root` of every file.
+    ///
+    /// Files no longer than a piece have no piece layer, and are skipped.
+    /// Torrents without piece layers (e.g. from a magnet link) are always valid.
+    pub fn verify_piece_layers(&self) -> Result<()> {
+        let (Some(piece_layers), Some(files)) = (&self.piece_layers, self.get_v2_files()) else {
+            return Ok(());
+        };
+
+        let piece_length = self.get_piece_length() as u64;
+        let pad = crate::merkle::pad_hash(piece_length);
+
+        for (path, file) in files {
+            let length = file.length as u64;
+            let Some(pieces_root) = &file.pieces_root else {
+                continue;
+            };
+            if length <= piece_length {
+                continue;
+            }
+
+            let layer = piece_layers
+                .get(pieces_root)
+                .ok_or_else(|| anyhow!("Missing piece layer for {}", path.join("/")))?;
+
+            if layer.len() as u64 != length.div_ceil(piece_length) * 32 {
+                return Err(anyhow!("Invalid piece layer length for {}", path.join("/")));
+            }
+            if crate::merkle::root(layer, pad).as_slice() != pieces_root {
+                return Err(anyhow!(
+                    "Piece layer doesn't match pieces root of {}",
+                    path.join("/")
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the files of a v2 or hybrid torrent, from the file tree.
+    pub fn get_v2_files(&self) -> Option<Vec<(Vec<String>, &FileTreeFile)>> {
+        match &self.info {
+            TorrentInfo::V2Info(info) => Some(info.files()),
+            TorrentInfo::HybridInfo(info) => Some(info.files()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use bcode::BEncode;
+use std::path::Path;
+use torrent::TorrentBuilder;
+
+#[async_std::test]
+async fn create_torrent() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("create_torrent");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("release/sub")).unwrap();
+
+    // Files cross piece boundaries, and the last piece is short.
+    let a = (0..40_000u32).map(|x| x as u8).collect::<Vec<_>>();
+    let b = vec![7; 10_000];
+    std::fs::write(root.join("release/b.bin"), &b).unwrap();
+    std::fs::write(root.join("release/sub/a.bin"), &a).unwrap();
+
+    // Symlinked directories are skipped, so a loop doesn't recurse forever.
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("..", root.join("release/sub/loop")).unwrap();
+
+    // Broken symlinks are skipped too.
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("missing.bin", root.join("release/dangling")).unwrap();
+
+    // Multi-file, hashed by several threads.
+    let torrent = TorrentBuilder::new(root.join("release"))
+        .announce("http://one.example/announce")
+        .announce_tier(vec![
+            "http://two.example/announce",
+            "http://three.example/announce",
+        ])
+        .comment("test")
+        .created_by("riptorrent")
+        .creation_date(1_000_000)
+        .private(true)
+        .piece_length(1 << 14)
+        .threads(3)
+        .build()
+        .unwrap();
+
+    let data = [b.as_slice(), a.as_slice()].concat();
+    let expected = data
+        .chunks(1 << 14)
+        .flat_map(|chunk| sha1_smol::Sha1::from(chunk).digest().bytes())
+        .collect::<Vec<_>>();
+
+    assert_eq!(torrent.get_name(), "release");
+    assert_eq!(torrent.get_size(), 50_000);
+    assert_eq!(torrent.get_piece_amount(), 4);
+    assert_eq!(
+        torrent.announce.as_deref(),
+        Some("http://one.example/announce")
+    );
+    assert_eq!(torrent.announce_list.as_ref().unwrap().len(), 2);
+
+    let bytes = bcode::encode(torrent.bencode()).unwrap();
+    let decoded = torrent::Torrent::from_bytes(bytes).await.unwrap();
+    assert_eq!(decoded.info_hash, torrent.info_hash);
+    assert_eq!(decoded.comment.as_deref(), Some("test"));
+    assert_eq!(decoded.creation_date, Some(1_000_000));
+
+    match decoded.info {
+        torrent::TorrentInfo::MultiFileInfo(info) => {
+            assert_eq!(info.pieces, expected);
+            assert_eq!(info.private, Some(true));
+            assert_eq!(info.files[0].path, vec!["b.bin"]);
+            assert_eq!(info.files[1].path, vec!["sub", "a.bin"]);
+            assert_eq!(info.files.len(), 2);
+        }
+        _ => panic!("expected a multi-file torrent"),
+    }
+
+    // Single file, with the piece length picked automatically.
+    let torrent = TorrentBuilder::new(root.join("release/sub/a.bin"))
+        .announce("http://one.example/announce")
+        .build()
+        .unwrap();
+
+    assert_eq!(torrent.get_name(), "a.bin");
+    assert_eq!(torrent.get_piece_length(), 1 << 14);
+    assert!(torrent.announce_list.is_none());
+    assert!(torrent.creation_date.is_some());
+
+    // Trackerless torrents are found through the DHT.
+    let torrent = TorrentBuilder::new(root.join("release/sub/a.bin"))
+        .build()
+        .unwrap();
+    assert!(torrent.announce.is_none());
+    assert!(torrent.to_magnet().trackers.is_empty());
+}
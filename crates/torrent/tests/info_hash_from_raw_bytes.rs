@@ -0,0 +1,16 @@
+#[async_std::test]
+async fn info_hash_from_raw_bytes() {
+    // "name" and "length" are in the wrong order, re-encoding would sort them.
+    let info = b"d4:name4:test6:lengthi42e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae";
+    let mut data = b"d8:announce9:localhost4:info".to_vec();
+    data.extend_from_slice(info);
+    data.push(b'e');
+
+    let torrent = torrent::Torrent::from_bytes(data).await.unwrap();
+    let expected = sha1_smol::Sha1::from(info).digest().bytes().to_vec();
+
+    assert_eq!(torrent.info_hash, expected);
+
+    // Peers get the same bytes over ut_metadata, so they hash to the info hash too.
+    assert_eq!(torrent.get_info_bytes(), info);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use torrent::Magnet;
+
+#[async_std::test]
+async fn magnet_link() {
+    // Hex info hash, with every supported parameter.
+    let link = "magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056&dn=Cosmos+Laundromat%20(2015)&tr=udp%3A%2F%2Ftracker.example%3A1337&tr=http%3A%2F%2Fother.example%2Fannounce&ws=https%3A%2F%2Fseed.example%2F&x.pe=10.0.0.1%3A6881&so=0,2,4-6";
+    let magnet = link.parse::<Magnet>().unwrap();
+
+    assert_eq!(magnet.info_hash.as_ref().unwrap()[..2], [0xc9, 0xe1]);
+    assert_eq!(
+        magnet.display_name.as_deref(),
+        Some("Cosmos Laundromat (2015)")
+    );
+    assert_eq!(
+        magnet.trackers,
+        vec![
+            "udp://tracker.example:1337",
+            "http://other.example/announce"
+        ]
+    );
+    assert_eq!(magnet.web_seeds, vec!["https://seed.example/"]);
+    assert_eq!(magnet.peers, vec!["10.0.0.1:6881"]);
+    assert_eq!(magnet.select_only, vec![0..=0, 2..=2, 4..=6]);
+
+    // Emitted links parse back to the same magnet.
+    assert_eq!(magnet.to_string().parse::<Magnet>().unwrap(), magnet);
+
+    // Base32 info hash.
+    let magnet = "magnet:?xt=urn:btih:ZHQVOY7XELZD5GFCTXWN7LRUDOMNKMCW"
+        .parse::<Magnet>()
+        .unwrap();
+    assert_eq!(
+        magnet.info_hash,
+        "magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056"
+            .parse::<Magnet>()
+            .unwrap()
+            .info_hash
+    );
+
+    // v2 multihash.
+    let link = format!("magnet:?xt=urn:btmh:1220{}", "ab".repeat(32));
+    let magnet = link.parse::<Magnet>().unwrap();
+    assert_eq!(magnet.info_hash_v2, Some(vec![0xab; 32]));
+    assert_eq!(magnet.swarm_hash(), Some(vec![0xab; 20]));
+    assert_eq!(magnet.to_string(), link);
+
+    // Links without a BitTorrent info hash are rejected.
+    assert!("magnet:?dn=test".parse::<Magnet>().is_err());
+    assert!("magnet:?xt=urn:btmh:1114abcd".parse::<Magnet>().is_err());
+    assert!("http://example.com".parse::<Magnet>().is_err());
+
+    // Magnet of a torrent file.
+    let data = common::read_torrent().unwrap();
+    let torrent = torrent::Torrent::from_bytes(data).await.unwrap();
+    let magnet = torrent.to_magnet();
+    assert_eq!(magnet.info_hash, Some(torrent.info_hash.clone()));
+    assert_eq!(magnet.display_name.as_deref(), Some(torrent.get_name()));
+    assert_eq!(Some(&magnet.trackers[0]), torrent.announce.as_ref());
+}
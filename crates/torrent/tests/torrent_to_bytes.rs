@@ -0,0 +1,45 @@
+mod common;
+
+#[async_std::test]
+async fn torrent_to_bytes() {
+    // A real torrent round-trips byte-for-byte.
+    let data = common::read_torrent().unwrap();
+    let torrent = torrent::Torrent::from_bytes(data.clone()).await.unwrap();
+    assert_eq!(torrent.to_bytes().unwrap(), data);
+
+    // Unknown keys are kept, both at the top level and in the info dictionary.
+    let data = b"d8:announce9:localhost7:comment3:old4:infod6:lengthi42e4:name4:test12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa6:source3:abce5:nodesli1ee8:url-list4:httpe".to_vec();
+    let mut torrent = torrent::Torrent::from_bytes(data.clone()).await.unwrap();
+    assert_eq!(torrent.to_bytes().unwrap(), data);
+    assert!(torrent.extra.contains_key(b"url-list".as_slice()));
+
+    // Editing the comment doesn't change the info hash.
+    torrent.comment = Some("new".to_string());
+    let edited = torrent::Torrent::from_bytes(torrent.to_bytes().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(edited.comment.as_deref(), Some("new"));
+    assert_eq!(edited.info_hash, torrent.info_hash);
+
+    // The same goes for info dictionaries that aren't canonically encoded.
+    let data = b"d8:announce9:localhost4:infod4:name4:test6:lengthi42e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee".to_vec();
+    let mut torrent = torrent::Torrent::from_bytes(data.clone()).await.unwrap();
+    assert_eq!(torrent.to_bytes().unwrap(), data);
+
+    torrent.announce = Some("http://new.example/announce".to_string());
+    let edited = torrent::Torrent::from_bytes(torrent.to_bytes().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(edited.info_hash, torrent.info_hash);
+    assert_eq!(edited.get_info_bytes(), torrent.get_info_bytes());
+
+    // Changes to the info dictionary itself are written, with a new info hash.
+    if let torrent::TorrentInfo::SingleFileInfo(info) = &mut torrent.info {
+        info.name = "renamed".to_string();
+    }
+    let renamed = torrent::Torrent::from_bytes(torrent.to_bytes().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(renamed.get_name(), "renamed");
+    assert_ne!(renamed.info_hash, torrent.info_hash);
+}
@@ -0,0 +1,202 @@
+use bcode::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+fn dict<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::Dictionary(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.as_bytes().to_vec(), v))
+            .collect(),
+    )
+}
+
+fn hash(data: &[u8]) -> Vec<u8> {
+    Sha256::digest(data).to_vec()
+}
+
+#[async_std::test]
+async fn v2_torrent() {
+    // 9 blocks of 16 KiB, so 5 pieces of 32 KiB, with the last ones partial.
+    let data = (0..140_000u32).map(|x| (x % 251) as u8).collect::<Vec<_>>();
+    let piece_length = 1 << 15;
+
+    // Build the whole merkle tree from the blocks, padded with zero hashes.
+    let mut layer = data.chunks(1 << 14).map(hash).collect::<Vec<_>>();
+    layer.resize(16, vec![0; 32]);
+    let mut layers = vec![layer];
+    while layers.last().unwrap().len() > 1 {
+        let next = layers
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| hash(&pair.concat()))
+            .collect::<Vec<_>>();
+        layers.push(next);
+    }
+    let pieces_root = layers.last().unwrap()[0].clone();
+    let piece_layer = layers[1][..5].concat();
+
+    let file_tree = dict([(
+        "dir",
+        dict([(
+            "a.bin",
+            dict([(
+                "",
+                dict([
+                    ("length", Value::Integer(140_000)),
+                    ("pieces root", Value::ByteString(pieces_root.clone())),
+                ]),
+            )]),
+        )]),
+    )]);
+    let v1_pieces = data
+        .chunks(piece_length)
+        .flat_map(|chunk| sha1_smol::Sha1::from(chunk).digest().bytes())
+        .collect::<Vec<_>>();
+
+    let encode_torrent = |info: Value, piece_layer: Vec<u8>| {
+        bcode::encode(dict([
+            (
+                "announce",
+                Value::ByteString(b"http://tracker.example/announce".to_vec()),
+            ),
+            ("info", info),
+            (
+                "piece layers",
+                Value::Dictionary(BTreeMap::from([(
+                    pieces_root.clone(),
+                    Value::ByteString(piece_layer),
+                )])),
+            ),
+        ]))
+        .unwrap()
+    };
+
+    // Hybrid torrent.
+    let info = dict([
+        ("file tree", file_tree),
+        ("length", Value::Integer(140_000)),
+        ("meta version", Value::Integer(2)),
+        ("name", Value::ByteString(b"a.bin".to_vec())),
+        ("piece length", Value::Integer(piece_length as i64)),
+        ("pieces", Value::ByteString(v1_pieces)),
+    ]);
+    let info_bytes = bcode::encode(info.clone()).unwrap();
+    let bytes = encode_torrent(info.clone(), piece_layer.clone());
+    let torrent = torrent::Torrent::from_bytes(bytes.clone()).await.unwrap();
+
+    assert!(matches!(torrent.info, torrent::TorrentInfo::HybridInfo(_)));
+    assert_eq!(
+        torrent.info_hash,
+        sha1_smol::Sha1::from(&info_bytes).digest().bytes().to_vec()
+    );
+    assert_eq!(torrent.info_hash_v2, Some(hash(&info_bytes)));
+    assert_eq!(torrent.get_size(), 140_000);
+    assert_eq!(torrent.get_piece_amount(), 5);
+    assert_eq!(torrent.get_v2_files().unwrap()[0].0, vec!["dir", "a.bin"]);
+    assert_eq!(torrent.to_bytes().unwrap(), bytes);
+
+    // v2-only torrent.
+    let Value::Dictionary(mut map) = info else {
+        unreachable!()
+    };
+    map.remove(b"pieces".as_slice());
+    map.remove(b"length".as_slice());
+    let info = Value::Dictionary(map);
+    let info_bytes = bcode::encode(info.clone()).unwrap();
+    let torrent = torrent::Torrent::from_bytes(encode_torrent(info.clone(), piece_layer.clone()))
+        .await
+        .unwrap();
+
+    assert!(matches!(torrent.info, torrent::TorrentInfo::V2Info(_)));
+    assert_eq!(torrent.info_hash, hash(&info_bytes)[..20].to_vec());
+    assert_eq!(torrent.get_piece_amount(), 5);
+
+    // Piece lengths that aren't a power of two of at least 16 KiB are rejected.
+    for piece_length in [0, 1 << 13, 3 << 14] {
+        let Value::Dictionary(mut map) = info.clone() else {
+            unreachable!()
+        };
+        map.insert(b"piece length".to_vec(), Value::Integer(piece_length));
+        let bytes = encode_torrent(Value::Dictionary(map), piece_layer.clone());
+        assert!(torrent::Torrent::from_bytes(bytes).await.is_err());
+    }
+
+    // Negative lengths are rejected.
+    let Value::Dictionary(mut map) = info.clone() else {
+        unreachable!()
+    };
+    map.insert(b"piece length".to_vec(), Value::Integer(-1));
+    let bytes = encode_torrent(Value::Dictionary(map), piece_layer.clone());
+    assert!(torrent::Torrent::from_bytes(bytes).await.is_err());
+
+    // Multi-file torrents lay out the pieces the same way, whether hybrid or v2-only.
+    let file = |length| {
+        dict([(
+            "",
+            dict([
+                ("length", Value::Integer(length)),
+                ("pieces root", Value::ByteString(vec![0; 32])),
+            ]),
+        )])
+    };
+    let v1_file = |length, path: &str, attr: Option<&str>| {
+        let mut file = dict([
+            ("length", Value::Integer(length)),
+            (
+                "path",
+                Value::List(vec![Value::ByteString(path.as_bytes().to_vec())]),
+            ),
+        ]);
+        if let (Value::Dictionary(map), Some(attr)) = (&mut file, attr) {
+            map.insert(
+                b"attr".to_vec(),
+                Value::ByteString(attr.as_bytes().to_vec()),
+            );
+        }
+        file
+    };
+    let hybrid = dict([
+        ("file tree", dict([("x", file(20_000)), ("y", file(1_000))])),
+        (
+            "files",
+            Value::List(vec![
+                v1_file(20_000, "x", None),
+                v1_file(12_768, ".pad", Some("p")),
+                v1_file(1_000, "y", None),
+            ]),
+        ),
+        ("meta version", Value::Integer(2)),
+        ("name", Value::ByteString(b"multi".to_vec())),
+        ("piece length", Value::Integer(piece_length as i64)),
+        ("pieces", Value::ByteString(vec![0; 40])),
+    ]);
+    let Value::Dictionary(mut map) = hybrid.clone() else {
+        unreachable!()
+    };
+    map.remove(b"pieces".as_slice());
+    map.remove(b"files".as_slice());
+    let v2_only = Value::Dictionary(map);
+
+    for info in [hybrid, v2_only] {
+        let bytes = encode_torrent(info, piece_layer.clone());
+        let torrent = torrent::Torrent::from_bytes(bytes).await.unwrap();
+        let layout = torrent.get_layout();
+
+        assert_eq!(torrent.get_size(), 21_000);
+        assert_eq!(torrent.get_total_length(), 33_768);
+        assert_eq!(torrent.get_piece_amount(), 2);
+        assert_eq!(layout.len(), 3);
+        assert_eq!(layout[0].path, vec!["multi", "x"]);
+        assert!(layout[1].padding);
+        assert_eq!(layout[2].path, vec!["multi", "y"]);
+    }
+
+    // A piece layer that doesn't match the pieces root is rejected.
+    let mut tampered = piece_layer;
+    tampered[0] ^= 1;
+    assert!(torrent::Torrent::from_bytes(encode_torrent(info, tampered))
+        .await
+        .is_err());
+}
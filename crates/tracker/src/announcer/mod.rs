@@ -0,0 +1,129 @@
+use crate::{Request, Response, TrackerManager};
+use anyhow::Result;
+use async_std::channel::Receiver;
+use async_std::future::timeout;
+use async_std::sync::Arc;
+use peer::TransferStats;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+/// Events to send to trackers outside the regular schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnounceEvent {
+    /// The download finished.
+    Completed,
+    /// The client is shutting down, after this the announcer stops.
+    Stopped,
+}
+
+/// Announces a torrent to its trackers in the background, as often as they ask for.
+#[derive(Debug)]
+pub struct Announcer {
+    manager: TrackerManager,
+    request: Request,
+    stats: Arc<TransferStats>,
+    /// Time between announces if the tracker doesn't say.
+    pub default_interval: Duration,
+    /// Time to wait after the first failed announce, doubled after each failure in a row.
+    pub retry_interval: Duration,
+    /// Longest time to wait after failed announces.
+    pub max_retry_interval: Duration,
+}
+
+impl Announcer {
+    /// Create an announcer.
+    ///
+    /// # Arguments
+    ///
+    /// * `manager` - trackers of the torrent.
+    /// * `request` - announce request, its event and transfer counts are filled in for each announce.
+    /// * `stats` - bytes transferred for the torrent.
+    pub fn new(manager: TrackerManager, request: Request, stats: Arc<TransferStats>) -> Announcer {
+        Announcer {
+            manager,
+            request,
+            stats,
+            default_interval: Duration::from_secs(30 * 60),
+            retry_interval: Duration::from_secs(15),
+            max_retry_interval: Duration::from_secs(30 * 60),
+        }
+    }
+
+    /// Announce `started`, then again every interval, until told to stop.
+    ///
+    /// Returns the result of the `stopped` announce.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - events to announce, the announcer stops if it's closed.
+    /// * `on_response` - called with every successful response, e.g. to add its peers.
+    pub async fn run(
+        mut self,
+        events: Receiver<AnnounceEvent>,
+        mut on_response: impl FnMut(Response),
+    ) -> Result<()> {
+        // The event is repeated until a tracker gets it.
+        let mut event = "started";
+        let mut failures = 0;
+        let mut min_interval = Duration::ZERO;
+
+        loop {
+            let announced_at = Instant::now();
+            let wait = match self.announce(event).await {
+                Ok(response) => {
+                    event = "";
+                    failures = 0;
+                    min_interval = seconds(response.min_interval).unwrap_or(Duration::ZERO);
+                    let interval = seconds(response.interval).unwrap_or(self.default_interval);
+                    on_response(response);
+
+                    interval.max(min_interval)
+                }
+                Err(_) => {
+                    let wait = self.retry_interval * 2_u32.saturating_pow(failures);
+                    failures += 1;
+
+                    wait.min(self.max_retry_interval)
+                }
+            };
+
+            match timeout(wait, events.recv()).await {
+                Err(_) => {}
+                Ok(Ok(AnnounceEvent::Completed)) => {
+                    // Trackers don't want announces more often than `min interval`.
+                    let early = min_interval.saturating_sub(announced_at.elapsed());
+                    async_std::task::sleep(early).await;
+                    event = "completed";
+                }
+                Ok(Ok(AnnounceEvent::Stopped)) | Ok(Err(_)) => {
+                    return self.announce("stopped").await.map(|_| ());
+                }
+            }
+        }
+    }
+
+    /// Announce with the current transfer counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - event of the announce, empty for regular ones.
+    async fn announce(&mut self, event: &str) -> Result<Response> {
+        self.request.event = event.to_string();
+        self.request.uploaded = self.stats.uploaded.load(Ordering::Relaxed);
+        self.request.downloaded = self.stats.downloaded.load(Ordering::Relaxed);
+        self.request.left = self.stats.left.load(Ordering::Relaxed);
+
+        let (_, response) = self.manager.announce(&self.request).await?;
+
+        Ok(response)
+    }
+}
+
+/// Convert an interval from a tracker response.
+///
+/// # Arguments
+///
+/// * `interval` - interval in seconds, if any.
+fn seconds(interval: Option<i64>) -> Option<Duration> {
+    interval.map(|x| Duration::from_secs(x.max(0) as u64))
+}
@@ -1,7 +1,15 @@
 //! # Tracker
 //!
-//! `tracker` is a library for communicating with torrent trackers.
+//! `tracker` is a library for communicating with torrent trackers, over HTTP or UDP (BEP 15).
 
+mod announcer;
+mod manager;
 mod request;
+mod scrape;
+mod udp;
 
-pub use request::Request;
+pub use announcer::{AnnounceEvent, Announcer};
+pub use manager::TrackerManager;
+pub use request::{Request, Response};
+pub use scrape::{scrape, scrape_url, ScrapeInfo, ScrapeResponse};
+pub use udp::UdpTracker;
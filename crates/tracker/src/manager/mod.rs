@@ -0,0 +1,115 @@
+use crate::{Request, Response, UdpTracker};
+use anyhow::{anyhow, Result};
+use rand::seq::SliceRandom;
+use torrent::Torrent;
+
+/// Trackers of a torrent in tiers (BEP 12), tried in order until one answers.
+#[derive(Debug, Clone)]
+pub struct TrackerManager {
+    tiers: Vec<Vec<String>>,
+    /// Times to retransmit to a UDP tracker before trying the next one.
+    ///
+    /// BEP 15 allows up to 8, which would take hours before failing over.
+    pub udp_retries: u32,
+}
+
+impl TrackerManager {
+    /// Create a manager, shuffling the trackers within each tier.
+    ///
+    /// # Arguments
+    ///
+    /// * `tiers` - tracker announce URLs, in tiers.
+    pub fn new(tiers: Vec<Vec<String>>) -> TrackerManager {
+        let mut tiers: Vec<Vec<String>> =
+            tiers.into_iter().filter(|tier| !tier.is_empty()).collect();
+
+        for tier in &mut tiers {
+            tier.shuffle(&mut rand::thread_rng());
+        }
+
+        TrackerManager {
+            tiers,
+            udp_retries: 2,
+        }
+    }
+
+    /// Create a manager for the trackers of a torrent.
+    ///
+    /// Uses `announce-list` if present, otherwise `announce` alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `torrent` - reference to a `Torrent` struct.
+    pub fn from_torrent(torrent: &Torrent) -> TrackerManager {
+        match &torrent.announce_list {
+            Some(announce_list) => TrackerManager::new(announce_list.clone()),
+            None => TrackerManager::new(torrent.announce.iter().map(|x| vec![x.clone()]).collect()),
+        }
+    }
+
+    /// Returns the trackers, in the order they will be tried.
+    pub fn tiers(&self) -> &[Vec<String>] {
+        &self.tiers
+    }
+
+    /// Returns `true` if there are no trackers.
+    pub fn is_empty(&self) -> bool {
+        self.tiers.is_empty()
+    }
+
+    /// Announce to the first tracker that answers, trying each tier in order.
+    ///
+    /// The tracker that answered is moved to the front of its tier, so it's tried first next time.
+    /// Returns its announce URL and the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the announce request, its URL is replaced by each tracker's.
+    pub async fn announce(&mut self, request: &Request) -> Result<(String, Response)> {
+        let mut last_error = anyhow!("No trackers");
+
+        for tier_index in 0..self.tiers.len() {
+            for index in 0..self.tiers[tier_index].len() {
+                let tracker = self.tiers[tier_index][index].clone();
+
+                match self.announce_to(&tracker, request).await {
+                    Ok(response) => {
+                        let tier = &mut self.tiers[tier_index];
+                        tier.remove(index);
+                        tier.insert(0, tracker.clone());
+
+                        return Ok((tracker, response));
+                    }
+                    Err(e) => last_error = e.context(format!("Tracker {tracker} failed")),
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Announce to a single tracker, treating a failure reason as an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `announce` - announce URL of the tracker.
+    /// * `request` - the announce request.
+    async fn announce_to(&self, announce: &str, request: &Request) -> Result<Response> {
+        let response = if announce.starts_with("udp://") {
+            let mut udp_tracker = UdpTracker::new(announce).await?;
+            udp_tracker.retries = self.udp_retries;
+
+            udp_tracker.announce(request).await?
+        } else {
+            let mut request = request.clone();
+            request.announce = announce.to_string();
+
+            request.send_request().await?
+        };
+
+        match response.failure_reason {
+            Some(reason) => Err(anyhow!("Tracker error: {reason}")),
+            None => Ok(response),
+        }
+    }
+}
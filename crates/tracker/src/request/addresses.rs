@@ -0,0 +1,45 @@
+use super::*;
+use async_std::net::UdpSocket;
+use std::net::{IpAddr, SocketAddr};
+
+impl Request {
+    /// Fill in our public addresses of both families, for `ipv4` and `ipv6`.
+    ///
+    /// Addresses that peers can't reach, like private ones behind a NAT, are left out.
+    pub async fn find_addresses(&mut self) {
+        if let Some(IpAddr::V4(ip)) = route_source(([8, 8, 8, 8], 53).into()).await {
+            let [a, b, ..] = ip.octets();
+            let shared = a == 100 && (64..128).contains(&b);
+
+            if !(ip.is_private() || ip.is_loopback() || ip.is_link_local() || shared) {
+                self.ipv4 = Some(ip);
+            }
+        }
+
+        let google_dns = [0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888];
+        if let Some(IpAddr::V6(ip)) = route_source((google_dns, 53).into()).await {
+            let link_local = ip.segments()[0] & 0xffc0 == 0xfe80;
+            let unique_local = ip.segments()[0] & 0xfe00 == 0xfc00;
+
+            if !(ip.is_loopback() || link_local || unique_local) {
+                self.ipv6 = Some(ip);
+            }
+        }
+    }
+}
+
+/// Returns the local address used to reach an address, without sending anything.
+///
+/// # Arguments
+///
+/// * `addr` - address to reach.
+async fn route_source(addr: SocketAddr) -> Option<IpAddr> {
+    let bind = match addr {
+        SocketAddr::V4(_) => SocketAddr::from(([0; 4], 0)),
+        SocketAddr::V6(_) => SocketAddr::from(([0; 16], 0)),
+    };
+    let socket = UdpSocket::bind(bind).await.ok()?;
+    socket.connect(addr).await.ok()?;
+
+    Some(socket.local_addr().ok()?.ip()).filter(|ip| !ip.is_unspecified())
+}
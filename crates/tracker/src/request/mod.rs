@@ -1,11 +1,16 @@
+mod addresses;
 mod response;
 
-use anyhow::Result;
-use response::Response;
+use crate::udp::SINGLE_REQUEST_RETRIES;
+use crate::UdpTracker;
+use anyhow::{anyhow, Result};
+pub use response::Response;
+pub(crate) use response::DECODE_LIMITS;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use torrent::Torrent;
 
 /// Struct representing a tracker request.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Request {
     pub announce: String,
     pub info_hash: Vec<u8>,
@@ -14,7 +19,11 @@ pub struct Request {
     pub uploaded: i64,
     pub downloaded: i64,
     pub left: i64,
-    pub event: String, // "started" | "stopped" | "completed"
+    pub event: String, // "started" | "stopped" | "completed" | "" for regular announces
+    /// Our IPv4 address, for trackers reached over IPv6 (BEP 7).
+    pub ipv4: Option<Ipv4Addr>,
+    /// Our IPv6 address, for trackers reached over IPv4 (BEP 7).
+    pub ipv6: Option<Ipv6Addr>,
 }
 
 //pub compact: i64,
@@ -46,6 +55,8 @@ impl Request {
             downloaded,
             left,
             event,
+            ipv4: None,
+            ipv6: None,
         }
     }
 
@@ -55,24 +66,40 @@ impl Request {
     ///
     /// * `torrent` - reference to a `Torrent` struct.
     /// * `peer_id` - the peer id generated by the client.
-    pub async fn from_torrent(torrent: &Torrent, peer_id: &[u8]) -> Request {
-        Request::new(
-            torrent.announce.clone(),
+    /// * `port` - port the client is listening on.
+    pub async fn from_torrent(torrent: &Torrent, peer_id: &[u8], port: u16) -> Result<Request> {
+        let announce = torrent
+            .announce
+            .iter()
+            .chain(torrent.announce_list.iter().flatten().flatten())
+            .next()
+            .cloned()
+            .ok_or_else(|| anyhow!("Torrent has no tracker"))?;
+
+        Ok(Request::new(
+            announce,
             torrent.info_hash.clone(),
             peer_id.to_vec(),
-            6881_u16,
+            port,
             0,
             0,
-            torrent.get_size(),
+            torrent.get_total_length(),
             "started".to_string(),
         )
-        .await
+        .await)
     }
 
-    /// Send a tracker request.
+    /// Send a tracker request, over UDP for `udp://` URLs and HTTP otherwise.
     pub async fn send_request(&self) -> Result<Response> {
-        let final_url = format!(
-            "{}?info_hash={}&peer_id={}&port={}&uploaded={}&downloaded={}&left={}&event={}",
+        if self.announce.starts_with("udp://") {
+            let mut udp_tracker = UdpTracker::new(&self.announce).await?;
+            udp_tracker.retries = SINGLE_REQUEST_RETRIES;
+
+            return udp_tracker.announce(self).await;
+        }
+
+        let mut final_url = format!(
+            "{}?info_hash={}&peer_id={}&port={}&uploaded={}&downloaded={}&left={}",
             self.announce,
             urlencoding::encode_binary(&self.info_hash),
             urlencoding::encode_binary(&self.peer_id),
@@ -80,9 +107,19 @@ impl Request {
             urlencoding::encode(&self.uploaded.to_string()),
             urlencoding::encode(&self.downloaded.to_string()),
             urlencoding::encode(&self.left.to_string()),
-            urlencoding::encode(&self.event.to_string()),
         );
 
+        // Regular announces have no event.
+        if !self.event.is_empty() {
+            final_url.push_str(&format!("&event={}", urlencoding::encode(&self.event)));
+        }
+        if let Some(ipv4) = self.ipv4 {
+            final_url.push_str(&format!("&ipv4={ipv4}"));
+        }
+        if let Some(ipv6) = self.ipv6 {
+            final_url.push_str(&format!("&ipv6={}", urlencoding::encode(&ipv6.to_string())));
+        }
+
         let bytes = reqwest::get(final_url).await?.bytes().await?;
         Response::from_bytes(bytes.to_vec())
     }
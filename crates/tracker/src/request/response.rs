@@ -1,21 +1,37 @@
-use anyhow::{anyhow, Result};
 use arrayref::array_ref;
-use bcode::map_get;
+use bcode::{BDecode, BEncode, DecodeOptions, Mode};
 use peer::Peer;
 use std::collections::BTreeMap;
 use std::net::IpAddr;
 
+/// Limits for decoding tracker responses.
+pub(crate) const DECODE_LIMITS: DecodeOptions = DecodeOptions {
+    mode: Mode::Lenient,
+    max_depth: 8,
+    max_size: 1 << 22,
+    max_string_length: 1 << 20,
+    max_entries: 1 << 16,
+};
+
 /// Struct representing a response from a tracker request.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, BEncode, BDecode)]
 pub struct Response {
+    #[bcode(rename = "failure reason")]
     pub failure_reason: Option<String>,
+    #[bcode(rename = "warning message")]
     pub warning_message: Option<String>,
     pub interval: Option<i64>,
+    #[bcode(rename = "min interval")]
     pub min_interval: Option<i64>,
+    #[bcode(rename = "tracker id")]
     pub tracker_id: Option<String>,
     pub complete: Option<i64>,
     pub incomplete: Option<i64>,
+    #[bcode(default, with = "peers")]
     pub peers: Vec<Peer>,
+    /// Compact IPv6 peers (BEP 7).
+    #[bcode(default, with = "peers6")]
+    pub peers6: Vec<Peer>,
 }
 
 impl Response {
@@ -24,40 +40,84 @@ impl Response {
     /// # Arguments
     ///
     /// * `vec` - byte vector.
-    pub fn from_bytes(vec: Vec<u8>) -> Result<Response> {
-        let main_map: BTreeMap<Vec<u8>, bcode::Value> = bcode::decode(&vec, &mut 0)?.try_into()?;
-        let failure_reason: Option<String> = map_get(&main_map, "failure reason")
-            .ok()
-            .map(|x| -> Result<String> { x.try_into() })
-            .transpose()?;
-        let warning_message: Option<String> = map_get(&main_map, "warning message")
-            .ok()
-            .map(|x| -> Result<String> { x.try_into() })
-            .transpose()?;
-        let interval: Option<i64> = Some(map_get(&main_map, "interval")?.try_into()?);
-        let min_interval: Option<i64> = map_get(&main_map, "min interval")
-            .ok()
-            .map(|x| -> Result<i64> { x.try_into() })
-            .transpose()?;
-        let tracker_id: Option<String> = map_get(&main_map, "tracker id")
-            .ok()
-            .map(|x| -> Result<String> { x.try_into() })
-            .transpose()?;
-        let complete: Option<i64> = Some(map_get(&main_map, "complete")?.try_into()?);
-        let incomplete: Option<i64> = Some(map_get(&main_map, "incomplete")?.try_into()?);
-
-        let peers = match map_get(&main_map, "peers")? {
+    pub fn from_bytes(vec: Vec<u8>) -> anyhow::Result<Response> {
+        let value = bcode::Decoder::with_options(&vec, DECODE_LIMITS).decode()?;
+        Ok(Response::bdecode(value)?)
+    }
+
+    /// Returns the peers of both families.
+    pub fn all_peers(&self) -> impl Iterator<Item = &Peer> {
+        self.peers.iter().chain(&self.peers6)
+    }
+}
+
+/// Read and write the "peers" key, which is either a list of dictionaries or compact.
+mod peers {
+    use super::*;
+
+    /// Convert peers to the compact model, or a list of dictionaries if any peer is IPv6.
+    ///
+    /// # Arguments
+    ///
+    /// * `peers` - peers to convert.
+    pub fn bencode(peers: &[Peer]) -> bcode::Value {
+        if peers.iter().all(|peer| peer.ip.is_ipv4()) {
+            let mut out = vec![];
+
+            for peer in peers {
+                if let IpAddr::V4(ip) = peer.ip {
+                    out.extend_from_slice(&ip.octets());
+                    out.extend_from_slice(&peer.port.to_be_bytes());
+                }
+            }
+
+            bcode::Value::ByteString(out)
+        } else {
+            bcode::Value::List(
+                peers
+                    .iter()
+                    .map(|peer| {
+                        let mut peer_map = BTreeMap::new();
+
+                        if let Some(id) = &peer.id {
+                            peer_map.insert(b"peer id".to_vec(), bcode::bytes::bencode(id));
+                        }
+                        peer_map.insert(b"ip".to_vec(), peer.ip.to_string().bencode());
+                        peer_map.insert(b"port".to_vec(), peer.port.bencode());
+
+                        bcode::Value::Dictionary(peer_map)
+                    })
+                    .collect(),
+            )
+        }
+    }
+
+    /// Convert either peers model to peers.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - value to convert.
+    pub fn bdecode(value: bcode::Value) -> Result<Vec<Peer>, bcode::Error> {
+        match value {
             bcode::Value::List(peers_list) => {
                 let mut out = vec![];
 
                 for peer_map in peers_list {
-                    let peer_map: BTreeMap<Vec<u8>, bcode::Value> = peer_map.try_into()?;
+                    let peer_map: BTreeMap<String, bcode::Value> = BDecode::bdecode(peer_map)?;
+                    let field = |key: &str| {
+                        peer_map.get(key).cloned().ok_or_else(|| {
+                            bcode::Error::Custom(format!("Could not find \"{key}\" in map"))
+                        })
+                    };
 
-                    let id: Vec<u8> = map_get(&peer_map, "peer id")?.try_into()?;
-                    let ip: String = map_get(&peer_map, "ip")?.try_into()?;
-                    let port: i64 = map_get(&peer_map, "port")?.try_into()?;
+                    let id: Option<Vec<u8>> = field("peer id").and_then(bcode::bytes::bdecode).ok();
+                    let ip: String = BDecode::bdecode(field("ip")?)?;
+                    let port: u16 = BDecode::bdecode(field("port")?)?;
+                    let ip = ip
+                        .parse()
+                        .map_err(|_| bcode::Error::Custom(format!("Invalid peer ip \"{ip}\"")))?;
 
-                    out.push(Peer::new(Some(id), ip.parse()?, port as u16));
+                    out.push(Peer::new(id, ip, port));
                 }
 
                 Ok(out)
@@ -74,18 +134,49 @@ impl Response {
 
                 Ok(out)
             }
-            _ => Err(anyhow!("Unsupported peers model")),
-        }?;
-
-        Ok(Response {
-            failure_reason,
-            warning_message,
-            interval,
-            min_interval,
-            tracker_id,
-            complete,
-            incomplete,
-            peers,
-        })
+            _ => Err(bcode::Error::Custom("Unsupported peers model".to_string())),
+        }
+    }
+}
+
+/// Read and write the "peers6" key, which is compact with 18 bytes per peer.
+mod peers6 {
+    use super::*;
+
+    /// Convert IPv6 peers to the compact model, leaving out any IPv4 peers.
+    ///
+    /// # Arguments
+    ///
+    /// * `peers` - peers to convert.
+    pub fn bencode(peers: &[Peer]) -> bcode::Value {
+        let mut out = vec![];
+
+        for peer in peers {
+            if let IpAddr::V6(ip) = peer.ip {
+                out.extend_from_slice(&ip.octets());
+                out.extend_from_slice(&peer.port.to_be_bytes());
+            }
+        }
+
+        bcode::Value::ByteString(out)
+    }
+
+    /// Convert the compact model to peers.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - value to convert.
+    pub fn bdecode(value: bcode::Value) -> Result<Vec<Peer>, bcode::Error> {
+        let peers_bytes = bcode::bytes::bdecode(value)?;
+
+        Ok(peers_bytes
+            .chunks_exact(18)
+            .map(|peer_chunk| {
+                let ip = *array_ref![peer_chunk, 0, 16];
+                let port = u16::from_be_bytes(*array_ref![peer_chunk, 16, 2]);
+
+                Peer::new(None, IpAddr::from(ip), port)
+            })
+            .collect())
     }
 }
@@ -0,0 +1,152 @@
+use crate::request::DECODE_LIMITS;
+use crate::udp::SINGLE_REQUEST_RETRIES;
+use crate::UdpTracker;
+use anyhow::{anyhow, Result};
+use bcode::{BDecode, BEncode};
+use std::collections::BTreeMap;
+
+/// Statistics of a torrent from a tracker scrape.
+#[derive(Debug, Clone, Default, PartialEq, Eq, BEncode, BDecode)]
+pub struct ScrapeInfo {
+    /// Amount of seeders.
+    #[bcode(default)]
+    pub complete: i64,
+    /// Amount of times the torrent was downloaded.
+    #[bcode(default)]
+    pub downloaded: i64,
+    /// Amount of leechers.
+    #[bcode(default)]
+    pub incomplete: i64,
+}
+
+/// Struct representing a response from an HTTP scrape request.
+#[derive(Debug, Clone, Default, BEncode, BDecode)]
+pub struct ScrapeResponse {
+    #[bcode(rename = "failure reason")]
+    pub failure_reason: Option<String>,
+    /// Statistics by info hash.
+    #[bcode(default, with = "files")]
+    pub files: BTreeMap<Vec<u8>, ScrapeInfo>,
+}
+
+impl ScrapeResponse {
+    /// Converts a vector of bytes to a `ScrapeResponse`.
+    ///
+    /// # Arguments
+    ///
+    /// * `vec` - byte vector.
+    pub fn from_bytes(vec: Vec<u8>) -> Result<ScrapeResponse> {
+        let value = bcode::Decoder::with_options(&vec, DECODE_LIMITS).decode()?;
+        Ok(ScrapeResponse::bdecode(value)?)
+    }
+}
+
+/// Returns the scrape URL of an HTTP tracker, by convention the announce URL
+/// with the `announce` after the last `/` replaced by `scrape`.
+///
+/// Returns `None` if the tracker doesn't follow the convention, and can't be scraped.
+///
+/// # Arguments
+///
+/// * `announce` - announce URL of the tracker.
+pub fn scrape_url(announce: &str) -> Option<String> {
+    let slash = announce.rfind('/')?;
+    let rest = announce[slash + 1..].strip_prefix("announce")?;
+
+    Some(format!("{}scrape{rest}", &announce[..=slash]))
+}
+
+/// Get statistics of torrents from a tracker, over UDP for `udp://` URLs and HTTP otherwise.
+///
+/// Torrents the tracker doesn't know are left out. UDP trackers report them as all zeros, so
+/// torrents with all zeros are left out for both transports.
+///
+/// # Arguments
+///
+/// * `announce` - announce URL of the tracker.
+/// * `info_hashes` - info hashes of the torrents.
+pub async fn scrape(
+    announce: &str,
+    info_hashes: &[Vec<u8>],
+) -> Result<BTreeMap<Vec<u8>, ScrapeInfo>> {
+    if announce.starts_with("udp://") {
+        let mut udp_tracker = UdpTracker::new(announce).await?;
+        udp_tracker.retries = SINGLE_REQUEST_RETRIES;
+        let stats = udp_tracker.scrape(info_hashes).await?;
+
+        let files = info_hashes.iter().cloned().zip(stats).collect();
+
+        return Ok(known(files));
+    }
+
+    let mut final_url =
+        scrape_url(announce).ok_or_else(|| anyhow!("Tracker {announce} doesn't support scrape"))?;
+
+    for (index, info_hash) in info_hashes.iter().enumerate() {
+        let separator = if index == 0 && !final_url.contains('?') {
+            '?'
+        } else {
+            '&'
+        };
+
+        final_url.push_str(&format!(
+            "{separator}info_hash={}",
+            urlencoding::encode_binary(info_hash)
+        ));
+    }
+
+    let bytes = reqwest::get(final_url).await?.bytes().await?;
+    let response = ScrapeResponse::from_bytes(bytes.to_vec())?;
+
+    match response.failure_reason {
+        Some(reason) => Err(anyhow!("Tracker error: {reason}")),
+        None => Ok(known(response.files)),
+    }
+}
+
+/// Drop the statistics of torrents with all zeros, which trackers may report for unknown ones.
+///
+/// # Arguments
+///
+/// * `files` - statistics by info hash.
+fn known(mut files: BTreeMap<Vec<u8>, ScrapeInfo>) -> BTreeMap<Vec<u8>, ScrapeInfo> {
+    files.retain(|_, info| *info != ScrapeInfo::default());
+
+    files
+}
+
+/// Read and write the "files" key, a dictionary with info hashes as keys.
+mod files {
+    use super::*;
+
+    /// Convert statistics to a dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - statistics to convert.
+    pub fn bencode(files: &BTreeMap<Vec<u8>, ScrapeInfo>) -> bcode::Value {
+        bcode::Value::Dictionary(
+            files
+                .iter()
+                .map(|(info_hash, info)| (info_hash.clone(), info.bencode()))
+                .collect(),
+        )
+    }
+
+    /// Convert a dictionary to statistics.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - value to convert.
+    pub fn bdecode(value: bcode::Value) -> Result<BTreeMap<Vec<u8>, ScrapeInfo>, bcode::Error> {
+        match value {
+            bcode::Value::Dictionary(files) => files
+                .into_iter()
+                .map(|(info_hash, info)| Ok((info_hash, ScrapeInfo::bdecode(info)?)))
+                .collect(),
+            _ => Err(bcode::Error::Custom(
+                "\"files\" is not a dictionary".to_string(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use super::*;
+use crate::{Request, Response};
+use peer::Peer;
+use std::net::IpAddr;
+
+impl UdpTracker {
+    /// Announce to the tracker.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the announce request, its URL is ignored.
+    pub async fn announce(&self, request: &Request) -> Result<Response> {
+        let event: u32 = match request.event.as_str() {
+            "completed" => 1,
+            "started" => 2,
+            "stopped" => 3,
+            _ => 0,
+        };
+
+        let response = self
+            .transact(ACTION_ANNOUNCE, |connection_id, transaction_id| {
+                let mut packet = connection_id.to_be_bytes().to_vec();
+                packet.extend_from_slice(&ACTION_ANNOUNCE.to_be_bytes());
+                packet.extend_from_slice(&transaction_id.to_be_bytes());
+                packet.extend_from_slice(&request.info_hash);
+                packet.extend_from_slice(&request.peer_id);
+                packet.extend_from_slice(&request.downloaded.to_be_bytes());
+                packet.extend_from_slice(&request.left.to_be_bytes());
+                packet.extend_from_slice(&request.uploaded.to_be_bytes());
+                packet.extend_from_slice(&event.to_be_bytes());
+                packet.extend_from_slice(&0_u32.to_be_bytes()); // IP address, default
+                packet.extend_from_slice(&transaction_id.to_be_bytes()); // Key
+                packet.extend_from_slice(&(-1_i32).to_be_bytes()); // Amount of peers wanted, default
+                packet.extend_from_slice(&request.port.to_be_bytes());
+
+                packet
+            })
+            .await?;
+
+        if response.len() < 12 {
+            return Err(anyhow!("Announce response too short"));
+        }
+
+        // Peers are IPv6 if the request was sent over IPv6.
+        let peer_size = match self.addr {
+            SocketAddr::V4(_) => 6,
+            SocketAddr::V6(_) => 18,
+        };
+        let peers = response[12..]
+            .chunks_exact(peer_size)
+            .map(|chunk| {
+                let (ip, port) = chunk.split_at(peer_size - 2);
+                let ip = match ip.len() {
+                    4 => IpAddr::from(*array_ref![ip, 0, 4]),
+                    _ => IpAddr::from(*array_ref![ip, 0, 16]),
+                };
+
+                Peer::new(None, ip, u16::from_be_bytes(*array_ref![port, 0, 2]))
+            })
+            .collect();
+
+        Ok(Response {
+            interval: Some(u32::from_be_bytes(*array_ref![response, 0, 4]) as i64),
+            incomplete: Some(u32::from_be_bytes(*array_ref![response, 4, 4]) as i64),
+            complete: Some(u32::from_be_bytes(*array_ref![response, 8, 4]) as i64),
+            peers,
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,173 @@
+mod announce;
+mod scrape;
+
+use anyhow::{anyhow, Result};
+use arrayref::array_ref;
+use async_std::future::timeout;
+use async_std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Magic constant identifying the protocol in a connect request.
+const PROTOCOL_ID: u64 = 0x41727101980;
+
+/// Retransmissions for a single request, as waiting out the 8 of BEP 15 takes hours.
+pub(crate) const SINGLE_REQUEST_RETRIES: u32 = 2;
+
+/// How long a connection id may be used.
+const CONNECTION_ID_LIFETIME: Duration = Duration::from_secs(60);
+
+const ACTION_CONNECT: u32 = 0;
+const ACTION_ANNOUNCE: u32 = 1;
+const ACTION_SCRAPE: u32 = 2;
+const ACTION_ERROR: u32 = 3;
+
+/// Connection ids of trackers, shared by every request to them.
+static CONNECTION_IDS: Mutex<BTreeMap<SocketAddr, (u64, Instant)>> = Mutex::new(BTreeMap::new());
+
+/// Client for a UDP tracker (BEP 15).
+#[derive(Debug)]
+pub struct UdpTracker {
+    socket: UdpSocket,
+    addr: SocketAddr,
+    /// Time to wait for the first response, doubled after every retransmission.
+    pub timeout: Duration,
+    /// Times to retransmit a request before giving up.
+    pub retries: u32,
+    /// How long a connection id may be used.
+    pub connection_id_lifetime: Duration,
+}
+
+impl UdpTracker {
+    /// Create a client for a tracker.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - announce URL, like `udp://tracker.example.com:6969/announce`.
+    pub async fn new(url: &str) -> Result<UdpTracker> {
+        let host = url
+            .strip_prefix("udp://")
+            .and_then(|rest| rest.split('/').next())
+            .ok_or_else(|| anyhow!("Invalid UDP tracker URL \"{url}\""))?;
+        let addr = host
+            .to_socket_addrs()
+            .await?
+            .next()
+            .ok_or_else(|| anyhow!("Could not resolve \"{host}\""))?;
+        let bind = match addr {
+            SocketAddr::V4(_) => SocketAddr::from(([0; 4], 0)),
+            SocketAddr::V6(_) => SocketAddr::from(([0; 16], 0)),
+        };
+
+        Ok(UdpTracker {
+            socket: UdpSocket::bind(bind).await?,
+            addr,
+            timeout: Duration::from_secs(15),
+            retries: 8,
+            connection_id_lifetime: CONNECTION_ID_LIFETIME,
+        })
+    }
+
+    /// Returns a connection id for the tracker, connecting if there's no recent one.
+    async fn connection_id(&self) -> Result<u64> {
+        if let Some((id, created)) = CONNECTION_IDS.lock().unwrap().get(&self.addr) {
+            if created.elapsed() < self.connection_id_lifetime {
+                return Ok(*id);
+            }
+        }
+
+        // Boxed, as `transact` gets connection ids itself.
+        let response = Box::pin(
+            self.transact(ACTION_CONNECT, |protocol_id, transaction_id| {
+                let mut packet = protocol_id.to_be_bytes().to_vec();
+                packet.extend_from_slice(&ACTION_CONNECT.to_be_bytes());
+                packet.extend_from_slice(&transaction_id.to_be_bytes());
+
+                packet
+            }),
+        )
+        .await?;
+        let id = u64::from_be_bytes(*array_ref![
+            response
+                .get(..8)
+                .ok_or_else(|| anyhow!("Connect response too short"))?,
+            0,
+            8
+        ]);
+
+        CONNECTION_IDS
+            .lock()
+            .unwrap()
+            .insert(self.addr, (id, Instant::now()));
+
+        Ok(id)
+    }
+
+    /// Send a request until the tracker responds, waiting `timeout * 2 ^ min(n, 8)` before
+    /// retransmission `n + 1`.
+    ///
+    /// The request is created again for every transmission, with a new connection id once the
+    /// previous one expired. Returns the response after the action and transaction id.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - action of the request, expected in the response.
+    /// * `packet` - creates the request from a connection id (the protocol id when connecting)
+    ///   and a transaction id.
+    async fn transact(&self, action: u32, packet: impl Fn(u64, u32) -> Vec<u8>) -> Result<Vec<u8>> {
+        let transaction_id: u32 = rand::random();
+
+        for n in 0..=self.retries {
+            let connection_id = match action {
+                ACTION_CONNECT => PROTOCOL_ID,
+                _ => self.connection_id().await?,
+            };
+            self.socket
+                .send_to(&packet(connection_id, transaction_id), self.addr)
+                .await?;
+
+            let wait = self.timeout * 2_u32.pow(n.min(8));
+            if let Ok(response) = timeout(wait, self.receive(transaction_id)).await {
+                let response = response?;
+                let response_action = u32::from_be_bytes(*array_ref![response, 0, 4]);
+
+                return match response_action {
+                    ACTION_ERROR => {
+                        // The connection id might be the problem, so get a new one next time.
+                        CONNECTION_IDS.lock().unwrap().remove(&self.addr);
+
+                        Err(anyhow!(
+                            "Tracker error: {}",
+                            String::from_utf8_lossy(&response[8..])
+                        ))
+                    }
+                    _ if response_action == action => Ok(response[8..].to_vec()),
+                    _ => Err(anyhow!("Unexpected tracker action {response_action}")),
+                };
+            }
+        }
+
+        Err(anyhow!("No response from tracker {}", self.addr))
+    }
+
+    /// Wait for a response with the transaction id, ignoring anything else.
+    ///
+    /// # Arguments
+    ///
+    /// * `transaction_id` - transaction id of the request.
+    async fn receive(&self, transaction_id: u32) -> Result<Vec<u8>> {
+        let mut buf = vec![0; 65536];
+
+        loop {
+            let (len, from) = self.socket.recv_from(&mut buf).await?;
+
+            if from == self.addr
+                && len >= 8
+                && u32::from_be_bytes(*array_ref![buf, 4, 4]) == transaction_id
+            {
+                return Ok(buf[..len].to_vec());
+            }
+        }
+    }
+}
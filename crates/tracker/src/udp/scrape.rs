@@ -0,0 +1,38 @@
+use super::*;
+use crate::ScrapeInfo;
+
+impl UdpTracker {
+    /// Get statistics of torrents from the tracker, in the same order as the info hashes.
+    ///
+    /// # Arguments
+    ///
+    /// * `info_hashes` - info hashes of the torrents, at most about 70 fit in a request.
+    pub async fn scrape(&self, info_hashes: &[Vec<u8>]) -> Result<Vec<ScrapeInfo>> {
+        let response = self
+            .transact(ACTION_SCRAPE, |connection_id, transaction_id| {
+                let mut packet = connection_id.to_be_bytes().to_vec();
+                packet.extend_from_slice(&ACTION_SCRAPE.to_be_bytes());
+                packet.extend_from_slice(&transaction_id.to_be_bytes());
+                info_hashes
+                    .iter()
+                    .for_each(|info_hash| packet.extend_from_slice(info_hash));
+
+                packet
+            })
+            .await?;
+
+        if response.len() < info_hashes.len() * 12 {
+            return Err(anyhow!("Scrape response too short"));
+        }
+
+        Ok(response
+            .chunks_exact(12)
+            .take(info_hashes.len())
+            .map(|chunk| ScrapeInfo {
+                complete: u32::from_be_bytes(*array_ref![chunk, 0, 4]) as i64,
+                downloaded: u32::from_be_bytes(*array_ref![chunk, 4, 4]) as i64,
+                incomplete: u32::from_be_bytes(*array_ref![chunk, 8, 4]) as i64,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use async_std::channel;
+use async_std::sync::Arc;
+use common::{announce_reply, error_reply};
+use peer::TransferStats;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tracker::{AnnounceEvent, Announcer, Request, TrackerManager};
+
+/// Announce fields seen by the tracker: event, downloaded, left and uploaded.
+type Seen = (u32, i64, i64, i64);
+
+/// A UDP tracker failing the first announce, then asking for one every second.
+async fn stand_in_tracker(seen: channel::Sender<Seen>) -> String {
+    let mut announces = 0;
+    let tracker = common::stand_in_tracker(0, move |packet| {
+        let field = |at: usize| i64::from_be_bytes(packet[at..at + 8].try_into().unwrap());
+        let event = u32::from_be_bytes(packet[80..84].try_into().unwrap());
+        seen.try_send((event, field(56), field(64), field(72)))
+            .unwrap();
+        announces += 1;
+
+        Some(match announces {
+            1 => error_reply("try again"),
+            _ => announce_reply(1, 0, 0, &[]),
+        })
+    })
+    .await;
+
+    tracker.url
+}
+
+#[async_std::test]
+async fn announcer() {
+    let (seen_sender, seen) = channel::unbounded();
+    let url = stand_in_tracker(seen_sender).await;
+    let request = Request::new(
+        url.clone(),
+        vec![b'i'; 20],
+        vec![b'p'; 20],
+        6881,
+        0,
+        0,
+        0,
+        "started".to_string(),
+    )
+    .await;
+    let stats = Arc::new(TransferStats::default());
+    stats.left.store(100, Ordering::Relaxed);
+
+    let mut announcer =
+        Announcer::new(TrackerManager::new(vec![vec![url]]), request, stats.clone());
+    announcer.retry_interval = Duration::from_millis(200);
+    let (events, receiver) = channel::unbounded();
+    let (responses_sender, responses) = channel::unbounded();
+    let handle = async_std::task::spawn(announcer.run(receiver, move |response| {
+        responses_sender.try_send(response.interval).unwrap();
+    }));
+
+    // "started" is retried after a failure, with a backoff.
+    let start = Instant::now();
+    assert_eq!(seen.recv().await.unwrap(), (2, 0, 100, 0));
+    assert_eq!(seen.recv().await.unwrap(), (2, 0, 100, 0));
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    assert_eq!(responses.recv().await.unwrap(), Some(1));
+
+    // Regular announces follow the interval, with the current counts.
+    stats.downloaded.store(100, Ordering::Relaxed);
+    stats.left.store(0, Ordering::Relaxed);
+    stats.uploaded.store(5, Ordering::Relaxed);
+    assert_eq!(seen.recv().await.unwrap(), (0, 100, 0, 5));
+
+    // Then "completed" and "stopped" when told.
+    events.send(AnnounceEvent::Completed).await.unwrap();
+    assert_eq!(seen.recv().await.unwrap(), (1, 100, 0, 5));
+    events.send(AnnounceEvent::Stopped).await.unwrap();
+    assert_eq!(seen.recv().await.unwrap(), (3, 100, 0, 5));
+    handle.await.unwrap();
+}
@@ -1 +1,136 @@
+// Not every test uses every helper.
+#![allow(dead_code)]
 
+use async_std::net::UdpSocket;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Connection id given out for the first answered connect request, later ones count up from it.
+pub const CONNECTION_ID: u64 = 0xdead_beef;
+
+/// A UDP tracker (BEP 15) running in the background.
+pub struct StandInTracker {
+    /// Announce URL of the tracker.
+    pub url: String,
+    /// Connect requests received, including dropped ones.
+    pub connects: Arc<AtomicUsize>,
+}
+
+/// Start a UDP tracker on localhost.
+///
+/// Connect requests are answered by the tracker itself, except the first `drop_connects`
+/// which are dropped. Other requests are answered with the action and body returned by
+/// `reply`, or dropped if it returns `None`.
+///
+/// # Arguments
+///
+/// * `drop_connects` - amount of connect requests to drop first.
+/// * `reply` - answers a request, given the whole packet.
+pub async fn stand_in_tracker<F>(drop_connects: usize, mut reply: F) -> StandInTracker
+where
+    F: FnMut(&[u8]) -> Option<(u32, Vec<u8>)> + Send + 'static,
+{
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("udp://{}/announce", socket.local_addr().unwrap());
+    let connects = Arc::new(AtomicUsize::new(0));
+    let counter = connects.clone();
+
+    async_std::task::spawn(async move {
+        let mut buf = vec![0; 2048];
+
+        loop {
+            let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+            let packet = &buf[..len];
+            assert!(len >= 16);
+
+            let response = if action(packet) == 0 {
+                assert_eq!(&packet[..8], 0x41727101980_u64.to_be_bytes());
+                let connect = counter.fetch_add(1, Ordering::SeqCst);
+                let id = CONNECTION_ID + connect.saturating_sub(drop_connects) as u64;
+
+                (connect >= drop_connects).then(|| (0, id.to_be_bytes().to_vec()))
+            } else {
+                reply(packet)
+            };
+
+            if let Some((action, body)) = response {
+                let mut out = action.to_be_bytes().to_vec();
+                out.extend_from_slice(&packet[12..16]);
+                out.extend_from_slice(&body);
+                socket.send_to(&out, from).await.unwrap();
+            }
+        }
+    });
+
+    StandInTracker { url, connects }
+}
+
+/// Returns the action of a request.
+///
+/// # Arguments
+///
+/// * `packet` - the request.
+pub fn action(packet: &[u8]) -> u32 {
+    u32::from_be_bytes(packet[8..12].try_into().unwrap())
+}
+
+/// Returns the connection id a request was sent with.
+///
+/// # Arguments
+///
+/// * `packet` - the request.
+pub fn connection_id(packet: &[u8]) -> u64 {
+    u64::from_be_bytes(packet[..8].try_into().unwrap())
+}
+
+/// Returns the info hashes of a scrape request.
+///
+/// # Arguments
+///
+/// * `packet` - the request.
+pub fn scrape_info_hashes(packet: &[u8]) -> Vec<&[u8]> {
+    packet[16..].chunks_exact(20).collect()
+}
+
+/// Create an announce response.
+///
+/// # Arguments
+///
+/// * `interval` - seconds until the next announce.
+/// * `leechers` - amount of leechers.
+/// * `seeders` - amount of seeders.
+/// * `peers` - compact peers.
+pub fn announce_reply(interval: u32, leechers: u32, seeders: u32, peers: &[u8]) -> (u32, Vec<u8>) {
+    let mut body = vec![];
+    for value in [interval, leechers, seeders] {
+        body.extend_from_slice(&value.to_be_bytes());
+    }
+    body.extend_from_slice(peers);
+
+    (1, body)
+}
+
+/// Create a scrape response.
+///
+/// # Arguments
+///
+/// * `files` - seeders, completed downloads and leechers of every torrent.
+pub fn scrape_reply(files: &[[u32; 3]]) -> (u32, Vec<u8>) {
+    (
+        2,
+        files
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_be_bytes())
+            .collect(),
+    )
+}
+
+/// Create an error response.
+///
+/// # Arguments
+///
+/// * `message` - why the request failed.
+pub fn error_reply(message: &str) -> (u32, Vec<u8>) {
+    (3, message.as_bytes().to_vec())
+}
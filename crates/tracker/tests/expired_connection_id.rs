@@ -0,0 +1,32 @@
+mod common;
+
+use common::{scrape_reply, CONNECTION_ID};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tracker::UdpTracker;
+
+#[async_std::test]
+async fn expired_connection_id() {
+    // Drops the first scrape, and checks the connection id of the others.
+    let mut scrapes = 0;
+    let common::StandInTracker { url, connects } = common::stand_in_tracker(0, move |packet| {
+        scrapes += 1;
+        match scrapes {
+            1 => None,
+            _ => {
+                assert_eq!(common::connection_id(packet), CONNECTION_ID + 1);
+                Some(scrape_reply(&[[1, 2, 3]]))
+            }
+        }
+    })
+    .await;
+
+    let mut udp_tracker = UdpTracker::new(&url).await.unwrap();
+    udp_tracker.timeout = Duration::from_millis(100);
+    udp_tracker.connection_id_lifetime = Duration::from_millis(50);
+
+    // The retransmission is sent with a new connection id, as the first one expired meanwhile.
+    let scrape = udp_tracker.scrape(&[vec![b'a'; 20]]).await.unwrap();
+    assert_eq!(scrape[0].incomplete, 3);
+    assert_eq!(connects.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,54 @@
+use std::net::IpAddr;
+use tracker::Response;
+
+#[test]
+fn parse_tracker_response() {
+    // Compact peers.
+    let response = Response::from_bytes(
+        b"d8:completei5e10:incompletei3e8:intervali1800e5:peers12:\x7f\x00\x00\x01\x1a\xe1\x0a\x00\x00\x02\x1a\xe2e"
+            .to_vec(),
+    )
+    .unwrap();
+
+    assert_eq!(response.interval, Some(1800));
+    assert_eq!(response.complete, Some(5));
+    assert_eq!(response.peers.len(), 2);
+    assert_eq!(response.peers[0].ip, "127.0.0.1".parse::<IpAddr>().unwrap());
+    assert_eq!(response.peers[1].port, 6882);
+
+    // Dictionary peers.
+    let response = Response::from_bytes(
+        b"d8:intervali900e5:peersld2:ip3:::17:peer id20:aaaaaaaaaaaaaaaaaaaa4:porti6881eeee"
+            .to_vec(),
+    )
+    .unwrap();
+
+    assert_eq!(response.peers[0].ip, "::1".parse::<IpAddr>().unwrap());
+    assert_eq!(response.peers[0].id, Some(b"aaaaaaaaaaaaaaaaaaaa".to_vec()));
+
+    // Failures only carry a reason.
+    let response = Response::from_bytes(b"d14:failure reason4:nopee".to_vec()).unwrap();
+    assert_eq!(response.failure_reason, Some("nope".to_string()));
+    assert!(response.peers.is_empty());
+
+    // IPv6 peers come in "peers6" (BEP 7).
+    let response = Response::from_bytes(
+        b"d5:peers6:\x7f\x00\x00\x01\x1a\xe16:peers618:\x20\x01\x0d\xb8\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01\x1a\xe2e"
+            .to_vec(),
+    )
+    .unwrap();
+
+    assert_eq!(response.peers6.len(), 1);
+    assert_eq!(
+        response.peers6[0].ip,
+        "2001:db8::1".parse::<IpAddr>().unwrap()
+    );
+    assert_eq!(response.peers6[0].port, 6882);
+    assert_eq!(response.all_peers().count(), 2);
+
+    // Responses nesting too deep are rejected.
+    let mut bytes = b"d8:intervali900e1:z".to_vec();
+    bytes.extend([b'l'; 32]);
+    bytes.extend([b'e'; 33]);
+    assert!(Response::from_bytes(bytes).is_err());
+}
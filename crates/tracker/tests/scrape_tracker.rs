@@ -0,0 +1,82 @@
+mod common;
+
+use common::scrape_reply;
+use tracker::{ScrapeInfo, ScrapeResponse};
+
+#[async_std::test]
+async fn scrape_tracker() {
+    // Scrape URLs follow from the announce URL by convention.
+    for (announce, scrape) in [
+        (
+            "http://example.com/announce",
+            Some("http://example.com/scrape"),
+        ),
+        (
+            "http://example.com/x/announce",
+            Some("http://example.com/x/scrape"),
+        ),
+        (
+            "http://example.com/announce.php",
+            Some("http://example.com/scrape.php"),
+        ),
+        (
+            "http://example.com/announce?x2%0644",
+            Some("http://example.com/scrape?x2%0644"),
+        ),
+        ("http://example.com/a", None),
+        ("http://example.com/announce/x", None),
+        ("http://example.com/x%064announce", None),
+    ] {
+        assert_eq!(tracker::scrape_url(announce).as_deref(), scrape);
+    }
+
+    // HTTP responses hold a dictionary keyed by info hash.
+    let response = ScrapeResponse::from_bytes(
+        b"d5:filesd20:aaaaaaaaaaaaaaaaaaaad8:completei5e10:downloadedi50e10:incompletei10ee20:bbbbbbbbbbbbbbbbbbbbd8:completei1eeee"
+            .to_vec(),
+    )
+    .unwrap();
+    assert_eq!(response.failure_reason, None);
+    assert_eq!(
+        response.files[&vec![b'a'; 20]],
+        ScrapeInfo {
+            complete: 5,
+            downloaded: 50,
+            incomplete: 10,
+        }
+    );
+    assert_eq!(response.files[&vec![b'b'; 20]].complete, 1);
+    assert_eq!(response.files[&vec![b'b'; 20]].incomplete, 0);
+
+    let response = ScrapeResponse::from_bytes(b"d14:failure reason4:nopee".to_vec()).unwrap();
+    assert_eq!(response.failure_reason, Some("nope".to_string()));
+    assert!(response.files.is_empty());
+
+    // UDP trackers are scraped by their announce URL.
+    // The stand-in reports the position of each info hash as leechers, and zeros for unknown
+    // torrents, which are left out.
+    let common::StandInTracker { url, .. } = common::stand_in_tracker(0, |packet| {
+        let files = common::scrape_info_hashes(packet)
+            .into_iter()
+            .enumerate()
+            .map(|(index, info_hash)| match info_hash {
+                [b'u', ..] => [0; 3],
+                _ => [1, 2, index as u32],
+            })
+            .collect::<Vec<_>>();
+
+        Some(scrape_reply(&files))
+    })
+    .await;
+
+    let info_hashes = [vec![b'a'; 20], vec![b'u'; 20], vec![b'b'; 20]];
+    let files = tracker::scrape(&url, &info_hashes).await.unwrap();
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[&vec![b'b'; 20]].incomplete, 2);
+    assert!(!files.contains_key(&vec![b'u'; 20]));
+
+    // Trackers without a scrape URL fail early.
+    assert!(tracker::scrape("http://example.com/a", &[vec![b'a'; 20]])
+        .await
+        .is_err());
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::{announce_reply, error_reply};
+use tracker::{Request, TrackerManager};
+
+/// A UDP tracker answering announces with a single peer on `port`, or an error if `port` is 0.
+async fn stand_in_tracker(port: u16) -> String {
+    let tracker = common::stand_in_tracker(0, move |_| {
+        let mut peer = vec![10, 0, 0, 1];
+        peer.extend_from_slice(&port.to_be_bytes());
+
+        Some(match port {
+            0 => error_reply("go away"),
+            _ => announce_reply(0, 0, 0, &peer),
+        })
+    })
+    .await;
+
+    tracker.url
+}
+
+#[async_std::test]
+async fn tracker_tiers() {
+    let dead = "http://127.0.0.1:1/announce".to_string();
+    let failing = stand_in_tracker(0).await;
+    let first = stand_in_tracker(1).await;
+    let second = stand_in_tracker(2).await;
+    let request = Request::new(
+        String::new(),
+        vec![b'i'; 20],
+        vec![b'p'; 20],
+        6881,
+        0,
+        0,
+        0,
+        "started".to_string(),
+    )
+    .await;
+
+    // The tracker that answers is moved to the front of its tier.
+    let mut manager = TrackerManager::new(vec![
+        vec![dead.clone(), failing.clone(), first.clone()],
+        vec![second.clone()],
+    ]);
+    let (tracker, response) = manager.announce(&request).await.unwrap();
+    assert_eq!(tracker, first);
+    assert_eq!(response.peers[0].port, 1);
+    assert_eq!(manager.tiers()[0][0], first);
+    assert_eq!(manager.tiers()[0].len(), 3);
+
+    // The next tier is used when a whole tier fails.
+    let mut manager = TrackerManager::new(vec![
+        vec![dead.clone(), failing.clone()],
+        vec![],
+        vec![second.clone()],
+    ]);
+    let (tracker, _) = manager.announce(&request).await.unwrap();
+    assert_eq!(tracker, second);
+    assert_eq!(manager.tiers().len(), 2);
+
+    // Failing when every tracker does.
+    let mut manager = TrackerManager::new(vec![vec![dead, failing]]);
+    assert!(manager.announce(&request).await.is_err());
+    assert!(TrackerManager::new(vec![]).is_empty());
+
+    // Tiers are shuffled.
+    let tier = (0..20).map(|x| x.to_string()).collect::<Vec<String>>();
+    let manager = TrackerManager::new(vec![tier.clone()]);
+    let mut shuffled = manager.tiers()[0].clone();
+    assert_ne!(shuffled, tier);
+    shuffled.sort_by_key(|x| x.parse::<u32>().unwrap());
+    assert_eq!(shuffled, tier);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use async_std::net::UdpSocket;
+use common::{announce_reply, error_reply, scrape_reply, CONNECTION_ID};
+use std::net::IpAddr;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tracker::{Request, ScrapeInfo, UdpTracker};
+
+#[async_std::test]
+async fn udp_tracker() {
+    // Drops the first connect request, to force a retransmission.
+    let common::StandInTracker { url, connects } = common::stand_in_tracker(1, |packet| {
+        Some(match common::action(packet) {
+            1 if packet[16..36] == [0xff; 20] => error_reply("unknown torrent"),
+            1 => {
+                assert_eq!(packet.len(), 98);
+                assert_eq!(common::connection_id(packet), CONNECTION_ID);
+                assert_eq!(&packet[36..56], [b'p'; 20]);
+                assert_eq!(&packet[80..84], 2_u32.to_be_bytes());
+                assert_eq!(&packet[96..98], 51413_u16.to_be_bytes());
+
+                announce_reply(
+                    1800,
+                    3,
+                    5,
+                    &[10, 0, 0, 1, 0x1a, 0xe1, 10, 0, 0, 2, 0x1a, 0xe2],
+                )
+            }
+            2 => {
+                let hashes = common::scrape_info_hashes(packet);
+                scrape_reply(
+                    &(0..hashes.len() as u32)
+                        .map(|x| [5, 10, x])
+                        .collect::<Vec<_>>(),
+                )
+            }
+            action => panic!("unexpected action {action}"),
+        })
+    })
+    .await;
+
+    let mut udp_tracker = UdpTracker::new(&url).await.unwrap();
+    udp_tracker.timeout = Duration::from_millis(100);
+
+    // The first connect request is retransmitted.
+    let request = Request::new(
+        url.clone(),
+        vec![b'i'; 20],
+        vec![b'p'; 20],
+        51413,
+        0,
+        0,
+        100,
+        "started".to_string(),
+    )
+    .await;
+    let response = udp_tracker.announce(&request).await.unwrap();
+    assert_eq!(connects.load(Ordering::SeqCst), 2);
+    assert_eq!(response.interval, Some(1800));
+    assert_eq!(response.incomplete, Some(3));
+    assert_eq!(response.complete, Some(5));
+    assert_eq!(response.peers.len(), 2);
+    assert_eq!(response.peers[1].ip, "10.0.0.2".parse::<IpAddr>().unwrap());
+    assert_eq!(response.peers[1].port, 6882);
+
+    // The connection id is reused.
+    let scrape = udp_tracker
+        .scrape(&[vec![b'a'; 20], vec![b'b'; 20]])
+        .await
+        .unwrap();
+    assert_eq!(
+        scrape,
+        [
+            ScrapeInfo {
+                complete: 5,
+                downloaded: 10,
+                incomplete: 0,
+            },
+            ScrapeInfo {
+                complete: 5,
+                downloaded: 10,
+                incomplete: 1,
+            },
+        ]
+    );
+    assert_eq!(connects.load(Ordering::SeqCst), 2);
+
+    // Errors are reported, and `send_request` picks UDP from the URL.
+    let mut request = request;
+    request.info_hash = vec![0xff; 20];
+    let error = request.send_request().await.unwrap_err();
+    assert_eq!(error.to_string(), "Tracker error: unknown torrent");
+
+    // Giving up after the retries.
+    let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("udp://{}", silent.local_addr().unwrap());
+    let mut udp_tracker = UdpTracker::new(&url).await.unwrap();
+    udp_tracker.timeout = Duration::from_millis(10);
+    udp_tracker.retries = 2;
+    assert!(udp_tracker.scrape(&[vec![b'a'; 20]]).await.is_err());
+
+    // The backoff stops doubling, so many retries don't overflow it.
+    udp_tracker.timeout = Duration::from_micros(10);
+    udp_tracker.retries = 40;
+    assert!(udp_tracker.scrape(&[vec![b'a'; 20]]).await.is_err());
+}
@@ -1,8 +1,70 @@
-pub use clap::Parser;
+pub use clap::{Parser, Subcommand};
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 pub struct Args {
-    /// Path to torrent file
-    pub path: String,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+
+    /// Path to torrent file, or a magnet link
+    pub path: Option<String>,
+
+    /// Directory to save the downloaded files in, and seed them from
+    #[clap(short, long, default_value = ".")]
+    pub dir: String,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Show the structure of a bencoded file
+    Bdecode {
+        /// Path to bencoded file
+        path: String,
+
+        /// Print as JSON, without losing any information
+        #[clap(long)]
+        json: bool,
+
+        /// Show binary byte strings in full
+        #[clap(long)]
+        full: bool,
+    },
+
+    /// Create a torrent file from a file or directory
+    Create {
+        /// Path to file or directory
+        path: String,
+
+        /// Where to write the torrent file, defaults to the name with a `.torrent` extension
+        #[clap(short, long)]
+        output: Option<String>,
+
+        /// Tracker announce URL, repeat for more tiers and separate trackers in a tier with commas.
+        /// Leave out for a trackerless torrent, found through the DHT
+        #[clap(short, long)]
+        announce: Vec<String>,
+
+        /// Free-form comment
+        #[clap(short, long)]
+        comment: Option<String>,
+
+        /// Only find peers through the trackers
+        #[clap(long)]
+        private: bool,
+
+        /// Bytes per piece, picked from the total size by default
+        #[clap(long)]
+        piece_length: Option<u64>,
+
+        /// Creation date in seconds since the UNIX epoch, defaults to now
+        #[clap(long)]
+        creation_date: Option<i64>,
+    },
+
+    /// Show seeders, leechers and downloads of torrents from their trackers
+    Scrape {
+        /// Paths to torrent files, or magnet links
+        #[clap(required = true)]
+        paths: Vec<String>,
+    },
 }
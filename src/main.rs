@@ -1,14 +1,30 @@
 mod cli;
 
 use anyhow::{anyhow, Result};
+use async_std::future::timeout;
+use async_std::net::SocketAddr;
 use async_std::sync::{Arc, Mutex};
-use builder::Builder;
+use builder::{Builder, Storage};
 use cli::*;
-use torrent::Torrent;
+use dht::{Dht, DhtConfig};
+use peer::{Listener, Peer, PeerPool, TransferStats, UtMetadata, UtPex};
+use std::collections::BTreeMap;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use torrent::{Magnet, Torrent, TorrentBuilder};
+use tracker::{AnnounceEvent, Announcer, TrackerManager};
+
+/// Most peers to be connected to at once.
+const MAX_PEERS: usize = 25;
+
+/// Port to listen for incoming peers on, if it's free.
+const LISTEN_PORT: u16 = 6881;
+
+/// How often to look for peers in the DHT, and announce to it.
+const DHT_INTERVAL: Duration = Duration::from_secs(15 * 60);
 
 // TODO list:
 //
-// * IPv6 not working? - find workaround for networks that don't have 6rd or similar.
 // * Generate a peer id.
 // * Create piece download strategy.
 
@@ -16,47 +32,540 @@ use torrent::Torrent;
 async fn main() -> Result<()> {
     let args = cli::Args::parse();
 
-    if let Ok(bytes) = std::fs::read(args.path) {
-        // Open torrent and get information from tracker.
-        let peer_id = b"-qBhj010488887635243".to_vec();
-        let torrent = Torrent::from_bytes(bytes).await?;
-        let tracker = tracker::Request::from_torrent(&torrent, &peer_id).await;
-        let tracker_resp = tracker.send_request().await?;
-
-        // Create builder
-        let builder = Arc::new(Mutex::new(Builder::new(
-            torrent.get_piece_amount(),
-            torrent.get_piece_length() as usize,
-            u32::pow(2, 14) as usize,
-        )));
-
-        // Loop trough peers.
-        for (i, mut peer) in tracker_resp.peers.into_iter().enumerate() {
-            if i > 25 {
-                break;
-            }
-
-            // Clone values.
-            let mut info_hash = torrent.info_hash.clone();
-            let mut id = peer_id.clone();
-            let piece_amount = torrent.get_piece_amount();
-            let builder = builder.clone();
-
-            // Spawn an async task.
-            async_std::task::spawn(async move {
-                peer.setup(&mut info_hash, &mut id, piece_amount).await?;
-                println!("Ready with {:?}", peer.ip);
-                peer.start(builder).await?;
+    match args.command {
+        Some(Command::Bdecode { path, json, full }) => bdecode(&path, json, full),
+        Some(Command::Create {
+            path,
+            output,
+            announce,
+            comment,
+            private,
+            piece_length,
+            creation_date,
+        }) => {
+            let mut builder = TorrentBuilder::new(&path)
+                .created_by(concat!("riptorrent/", env!("CARGO_PKG_VERSION")))
+                .private(private);
+
+            for tier in announce {
+                builder = builder.announce_tier(tier.split(',').collect());
+            }
+            if let Some(comment) = comment {
+                builder = builder.comment(comment);
+            }
+            if let Some(piece_length) = piece_length {
+                builder = builder.piece_length(piece_length);
+            }
+            if let Some(creation_date) = creation_date {
+                builder = builder.creation_date(creation_date);
+            }
+
+            create(builder, output)
+        }
+        Some(Command::Scrape { paths }) => scrape(&paths).await,
+        None => {
+            let path = args
+                .path
+                .ok_or_else(|| anyhow!("Missing path to torrent file or magnet link"))?;
+
+            if path.starts_with("magnet:") {
+                download_magnet(&path.parse()?, &args.dir).await
+            } else {
+                download(&path, &args.dir).await
+            }
+        }
+    }
+}
+
+/// Print the structure of a bencoded file.
+///
+/// # Arguments
+///
+/// * `path` - path to bencoded file.
+/// * `json` - whether to print as JSON.
+/// * `full` - whether to show binary byte strings in full.
+fn bdecode(path: &str, json: bool, full: bool) -> Result<()> {
+    let bytes = std::fs::read(path)?;
+    let value = bcode::Decoder::new(&bytes, bcode::Mode::Lenient).decode()?;
+
+    if json {
+        println!("{:#}", bcode::to_json(&value));
+    } else {
+        let max_bytes = if full { None } else { Some(32) };
+        println!("{}", value.pretty().max_bytes(max_bytes));
+    }
+
+    Ok(())
+}
+
+/// Create a torrent file.
+///
+/// # Arguments
+///
+/// * `builder` - configured torrent builder.
+/// * `output` - where to write the torrent file, defaults to the torrent name with a `.torrent` extension.
+fn create(builder: TorrentBuilder, output: Option<String>) -> Result<()> {
+    let torrent = builder.build()?;
+    let output = output.unwrap_or_else(|| format!("{}.torrent", torrent.get_name()));
+
+    std::fs::write(&output, torrent.to_bytes()?)?;
+    println!(
+        "Created {output} (info hash {})",
+        bcode::to_hex(&torrent.info_hash)
+    );
+
+    Ok(())
+}
+
+/// Print statistics of torrents from their trackers, scraping each tracker once.
+///
+/// # Arguments
+///
+/// * `paths` - paths to torrent files, or magnet links.
+async fn scrape(paths: &[String]) -> Result<()> {
+    let mut info_hashes = BTreeMap::<String, Vec<Vec<u8>>>::new();
+
+    for path in paths {
+        let (info_hash, trackers) = if path.starts_with("magnet:") {
+            let magnet: Magnet = path.parse()?;
+            let info_hash = magnet
+                .swarm_hash()
+                .ok_or_else(|| anyhow!("Magnet link has no info hash"))?;
+
+            (info_hash, magnet.trackers)
+        } else {
+            let bytes = std::fs::read(path).map_err(|_| anyhow!("Failed reading torrent file"))?;
+            let torrent = Torrent::from_bytes(bytes).await?;
+            let trackers = TrackerManager::from_torrent(&torrent).tiers().concat();
+
+            (torrent.info_hash, trackers)
+        };
+
+        if trackers.is_empty() {
+            println!("{path} has no trackers");
+        }
+        for tracker in trackers {
+            info_hashes
+                .entry(tracker)
+                .or_default()
+                .push(info_hash.clone());
+        }
+    }
+
+    for (announce, info_hashes) in info_hashes {
+        match tracker::scrape(&announce, &info_hashes).await {
+            Ok(files) => {
+                println!("{announce}");
+
+                for info_hash in info_hashes {
+                    let hex = bcode::to_hex(&info_hash);
+
+                    match files.get(&info_hash) {
+                        Some(info) => println!(
+                            "  {hex}: {} seeders, {} leechers, {} downloads",
+                            info.complete, info.incomplete, info.downloaded
+                        ),
+                        None => println!("  {hex}: unknown to tracker"),
+                    }
+                }
+            }
+            Err(e) => println!("Tracker {announce} failed: {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Download a torrent from a magnet link, fetching the metadata from peers first.
+///
+/// # Arguments
+///
+/// * `magnet` - parsed magnet link.
+/// * `dir` - directory to save the files in.
+async fn download_magnet(magnet: &Magnet, dir: &str) -> Result<()> {
+    let peer_id = b"-qBhj010488887635243".to_vec();
+    let info_hash = magnet
+        .swarm_hash()
+        .ok_or_else(|| anyhow!("Magnet link has no info hash"))?;
+
+    // Find peers from the magnet link itself, and from its trackers.
+    let mut peers = magnet
+        .peers
+        .iter()
+        .filter_map(|peer| peer.parse::<SocketAddr>().ok())
+        .map(|addr| Peer::new(None, addr.ip(), addr.port()))
+        .collect::<Vec<Peer>>();
+
+    for announce in &magnet.trackers {
+        let mut tracker = tracker::Request::new(
+            announce.clone(),
+            info_hash.clone(),
+            peer_id.clone(),
+            LISTEN_PORT,
+            0,
+            0,
+            0,
+            "started".to_string(),
+        )
+        .await;
+        tracker.find_addresses().await;
+
+        match tracker.send_request().await {
+            Ok(tracker_resp) => peers.extend(tracker_resp.all_peers().cloned()),
+            Err(e) => println!("Tracker {announce} failed: {e}"),
+        }
+    }
+
+    // Find peers through the DHT too, unless the magnet link has enough.
+    if peers.len() < MAX_PEERS {
+        match find_dht_peers(&info_hash).await {
+            Ok(found) => peers.extend(
+                found
+                    .into_iter()
+                    .map(|addr| Peer::new(None, addr.ip(), addr.port())),
+            ),
+            Err(e) => println!("DHT failed: {e}"),
+        }
+    }
+
+    for mut peer in merge_alternatives(peers).into_iter().take(MAX_PEERS) {
+        let metadata = match timeout(
+            Duration::from_secs(30),
+            peer.fetch_metadata(&info_hash, &peer_id),
+        )
+        .await
+        {
+            Ok(Ok(metadata)) => metadata,
+            _ => continue,
+        };
+
+        if let Ok(torrent) = magnet.to_torrent(&metadata).await {
+            println!("Got metadata for {} from {:?}", torrent.get_name(), peer.ip);
+            return download_torrent(torrent, dir).await;
+        }
+    }
+
+    Err(anyhow!(
+        "Could not fetch the torrent metadata from any peer"
+    ))
+}
+
+/// Merge peers with the same peer id, so their other addresses are raced when connecting.
+///
+/// # Arguments
+///
+/// * `peers` - peers to merge, in order of preference.
+fn merge_alternatives(peers: Vec<Peer>) -> Vec<Peer> {
+    let mut out: Vec<Peer> = vec![];
+
+    for peer in peers {
+        let addr = SocketAddr::new(peer.ip, peer.port);
+        let same = out
+            .iter_mut()
+            .find(|known| peer.id.is_some() && known.id == peer.id);
+
+        match same {
+            Some(known) if SocketAddr::new(known.ip, known.port) != addr => {
+                if !known.alternatives.contains(&addr) {
+                    known.alternatives.push(addr);
+                }
+            }
+            Some(_) => {}
+            None => out.push(peer),
+        }
+    }
+
+    out
+}
 
-                Ok::<(), anyhow::Error>(())
-            });
+/// Join the DHT, keeping the routing table between runs.
+///
+/// # Arguments
+///
+/// * `nodes` - extra nodes to join through, as `host:port`.
+async fn join_dht(nodes: Vec<String>) -> Result<Arc<Dht>> {
+    let mut config = DhtConfig {
+        state: Some(std::env::temp_dir().join("riptorrent-dht.dat")),
+        ..Default::default()
+    };
+    config.bootstrap.extend(nodes);
+
+    // Use any free port if the default one is taken.
+    let dht = match Dht::bind(config.clone()).await {
+        Ok(dht) => dht,
+        Err(_) => {
+            config.bind.set_port(0);
+            Dht::bind(config).await?
         }
+    };
+    dht.bootstrap().await?;
+    dht.save()?;
+
+    Ok(dht)
+}
+
+/// Find peers of a torrent through the DHT.
+///
+/// # Arguments
+///
+/// * `info_hash` - info hash of the torrent.
+async fn find_dht_peers(info_hash: &[u8]) -> Result<Vec<SocketAddr>> {
+    let dht = join_dht(vec![]).await?;
+    let peers = dht.lookup_peers(info_hash).await?;
+    dht.save()?;
 
-        // wait
-        std::io::stdin().read_line(&mut String::new()).unwrap();
+    Ok(peers)
+}
+
+/// Announce a torrent to the DHT periodically, adding the peers found to the pool.
+///
+/// # Arguments
+///
+/// * `info_hash` - info hash of the torrent.
+/// * `port` - port peers can connect to.
+/// * `nodes` - DHT nodes from the torrent file.
+/// * `pool` - peers of the torrent.
+async fn announce_dht(
+    info_hash: Vec<u8>,
+    port: u16,
+    nodes: Vec<String>,
+    pool: Arc<std::sync::Mutex<PeerPool>>,
+) {
+    let dht = match join_dht(nodes).await {
+        Ok(dht) => dht,
+        Err(e) => return println!("DHT failed: {e}"),
+    };
+
+    // A round without any node accepting the announce is retried on the next one.
+    loop {
+        match dht.announce(&info_hash, Some(port)).await {
+            Ok(found) => {
+                for addr in found {
+                    pool.lock().unwrap().add(addr, 0);
+                }
+            }
+            Err(e) => println!("DHT announce failed: {e}"),
+        }
 
-        Ok(())
+        if let Err(e) = dht.save() {
+            println!("Saving the DHT failed: {e}");
+        }
+        async_std::task::sleep(DHT_INTERVAL).await;
+    }
+}
+
+/// Download a torrent.
+///
+/// # Arguments
+///
+/// * `path` - path to torrent file.
+/// * `dir` - directory to save the files in.
+async fn download(path: &str, dir: &str) -> Result<()> {
+    if let Ok(bytes) = std::fs::read(path) {
+        download_torrent(Torrent::from_bytes(bytes).await?, dir).await
     } else {
         Err(anyhow!("Failed reading torrent file"))
     }
 }
+
+/// Download a torrent, and seed it to incoming peers.
+///
+/// # Arguments
+///
+/// * `torrent` - torrent to download.
+/// * `dir` - directory to save the files in.
+async fn download_torrent(torrent: Torrent, dir: &str) -> Result<()> {
+    let peer_id = b"-qBhj010488887635243".to_vec();
+
+    // Pieces already on disk are verified, and seeded along with the ones we download.
+    let builder = Builder::new(
+        torrent.get_piece_amount(),
+        torrent.get_piece_length() as usize,
+        u32::pow(2, 14) as usize,
+    )
+    .with_total_length(torrent.get_total_length() as usize)
+    .with_storage(Storage::new(&torrent, dir)?);
+    let builder = Arc::new(Mutex::new(builder));
+
+    // Listen on the default port, or any free one if it's taken, for both families if we can.
+    let mut listener = None;
+    for addr in [
+        SocketAddr::from(([0; 16], LISTEN_PORT)),
+        SocketAddr::from(([0; 4], LISTEN_PORT)),
+        SocketAddr::from(([0; 16], 0)),
+        SocketAddr::from(([0; 4], 0)),
+    ] {
+        if let Ok(bound) = Listener::bind(addr, peer_id.clone()).await {
+            listener = Some(bound);
+            break;
+        }
+    }
+    let listener = listener.ok_or_else(|| anyhow!("Could not listen for incoming peers"))?;
+    let port = listener.local_addr()?.port();
+    let incoming = listener.add_torrent(torrent.info_hash.clone());
+    async_std::task::spawn(listener.run());
+
+    // Peers from the trackers, and later from the DHT and peer exchange.
+    let pool = Arc::new(std::sync::Mutex::new(PeerPool::new()));
+    let stats = Arc::new(TransferStats::default());
+    let left = builder.lock().await.get_left() as i64;
+    stats.left.store(left, Ordering::Relaxed);
+
+    // Announce to the trackers in the background, until we stop.
+    let trackers = TrackerManager::from_torrent(&torrent);
+    let (announce_events, events) = async_std::channel::unbounded();
+    let announcer = if !trackers.is_empty() {
+        let mut request = tracker::Request::from_torrent(&torrent, &peer_id, port).await?;
+        request.find_addresses().await;
+        let announcer = Announcer::new(trackers, request, stats.clone());
+        let pool = pool.clone();
+
+        Some(async_std::task::spawn(announcer.run(
+            events,
+            move |tracker_resp| {
+                let mut pool = pool.lock().unwrap();
+
+                for peer in merge_alternatives(tracker_resp.all_peers().cloned().collect()) {
+                    let addr = SocketAddr::new(peer.ip, peer.port);
+                    pool.add(addr, 0);
+
+                    for alternative in peer.alternatives {
+                        pool.add_alternative(addr, alternative);
+                    }
+                }
+            },
+        )))
+    } else {
+        None
+    };
+
+    // Private torrents must only get peers from their trackers.
+    if !torrent.is_private() {
+        async_std::task::spawn(announce_dht(
+            torrent.info_hash.clone(),
+            port,
+            torrent.get_nodes(),
+            pool.clone(),
+        ));
+    }
+
+    let swarm = Swarm {
+        metadata: Arc::new(torrent.get_info_bytes().to_vec()),
+        torrent: Arc::new(torrent),
+        peer_id,
+        port,
+        builder,
+        pool,
+        stats,
+    };
+
+    // Peers connecting to us.
+    let incoming_swarm = swarm.clone();
+    async_std::task::spawn(async move {
+        while let Ok(peer) = incoming.recv().await {
+            if incoming_swarm.pool.lock().unwrap().connected_amount() < MAX_PEERS {
+                incoming_swarm.connect(peer, true);
+            }
+        }
+    });
+
+    let completed_events = announce_events.clone();
+    async_std::task::spawn(async move {
+        // Seeding what was already on disk isn't a completed download.
+        let mut completed = swarm.stats.left.load(Ordering::Relaxed) == 0;
+
+        loop {
+            if !completed && swarm.stats.left.load(Ordering::Relaxed) == 0 {
+                completed_events.send(AnnounceEvent::Completed).await.ok();
+                completed = true;
+            }
+
+            // Connect to new peers, while there's room for them.
+            while swarm.pool.lock().unwrap().connected_amount() < MAX_PEERS {
+                let Some(addr) = swarm.pool.lock().unwrap().take_next() else {
+                    break;
+                };
+
+                let mut peer = Peer::new(None, addr.ip(), addr.port());
+                peer.alternatives = swarm.pool.lock().unwrap().alternatives(&addr);
+
+                swarm.connect(peer, false);
+            }
+
+            async_std::task::sleep(Duration::from_secs(5)).await;
+        }
+    });
+
+    // wait
+    std::io::stdin().read_line(&mut String::new()).unwrap();
+
+    // Tell the trackers we're leaving, without waiting too long for them.
+    announce_events.send(AnnounceEvent::Stopped).await.ok();
+    if let Some(announcer) = announcer {
+        timeout(Duration::from_secs(10), announcer).await.ok();
+    }
+
+    Ok(())
+}
+
+/// Everything the connections of a torrent share.
+#[derive(Debug, Clone)]
+struct Swarm {
+    torrent: Arc<Torrent>,
+    /// Info dictionary of the torrent, served to peers with `ut_metadata`.
+    metadata: Arc<Vec<u8>>,
+    peer_id: Vec<u8>,
+    /// Port of our listener, sent to peers so they can connect back.
+    port: u16,
+    builder: Arc<Mutex<Builder>>,
+    pool: Arc<std::sync::Mutex<PeerPool>>,
+    stats: Arc<TransferStats>,
+}
+
+impl Swarm {
+    /// Run the peer loop with a peer in a new task, until the connection ends.
+    ///
+    /// # Arguments
+    ///
+    /// * `peer` - peer to exchange pieces with.
+    /// * `incoming` - whether the peer connected to us, and the handshake is done.
+    fn connect(&self, mut peer: Peer, incoming: bool) {
+        let addr = SocketAddr::new(peer.ip, peer.port);
+        peer.stats = self.stats.clone();
+        peer.extensions.listen_port = Some(self.port);
+        peer.extensions
+            .register(UtMetadata::new(Some(self.metadata.clone())));
+
+        // Private torrents must only get peers from their trackers.
+        if !self.torrent.is_private() {
+            peer.extensions
+                .register(UtPex::new(self.pool.clone(), addr));
+        }
+
+        // Spawn an async task.
+        match incoming {
+            true => self.pool.lock().unwrap().connect_incoming(addr),
+            false => self.pool.lock().unwrap().connect(addr),
+        }
+        let swarm = self.clone();
+        async_std::task::spawn(async move {
+            let result = async {
+                if incoming {
+                    peer.setup_session(swarm.builder.clone()).await?;
+                } else {
+                    let mut info_hash = swarm.torrent.info_hash.clone();
+                    let mut id = swarm.peer_id.clone();
+                    peer.setup(&mut info_hash, &mut id, swarm.builder.clone())
+                        .await?;
+                }
+
+                println!("Ready with {:?}", peer.ip);
+                peer.start(swarm.builder.clone()).await
+            }
+            .await;
+
+            swarm.pool.lock().unwrap().disconnect(addr);
+            result
+        });
+    }
+}